@@ -1,7 +1,7 @@
 //! CLI argument parsing.
 
 use chrysalis_config::Platform;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 /// Chrysalis - Modern build system for Flutter
@@ -49,6 +49,14 @@ pub enum Command {
         /// Build mode (e.g., development, production, staging)
         #[arg(short, long)]
         mode: Option<String>,
+
+        /// Plan post-processing of the existing Flutter output without touching disk
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output format for --dry-run
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, requires = "dry_run")]
+        format: OutputFormat,
     },
 
     /// Generate default configuration file
@@ -61,10 +69,89 @@ pub enum Command {
     /// Clean build artifacts
     Clean,
 
+    /// Inspect or prune the persistent build cache
+    Cache {
+        #[command(subcommand)]
+        action: Option<CacheAction>,
+    },
+
+    /// Compare output sizes of two builds (directories or asset manifests)
+    Diff {
+        /// Old build directory or asset-manifest.json
+        old: PathBuf,
+
+        /// New build directory or asset-manifest.json
+        new: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+        format: DiffFormat,
+
+        /// List unchanged files too
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Show the references between output files
+    Graph {
+        /// Build output directory (defaults to the web output directory)
+        dir: Option<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+    },
+
     /// Show version information
     Version,
 }
 
+/// Output format for machine-readable reports.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// JSON
+    Json,
+}
+
+/// Output format for `chrysalis diff`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffFormat {
+    /// Terminal table
+    Text,
+    /// JSON
+    Json,
+    /// GitHub-flavoured markdown (for PR comments)
+    Markdown,
+}
+
+/// Output format for `chrysalis graph`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// JSON
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Show cache location and size (default)
+    Info,
+
+    /// Remove least recently used entries
+    Prune {
+        /// Maximum cache size in MB (defaults to build.cache.max_size_mb)
+        #[arg(long)]
+        max_size_mb: Option<u64>,
+
+        /// Remove all entries
+        #[arg(long, conflicts_with = "max_size_mb")]
+        all: bool,
+    },
+}
+
 impl Default for Command {
     fn default() -> Self {
         Self::Build {
@@ -72,6 +159,8 @@ impl Default for Command {
             all: false,
             clean: false,
             mode: None,
+            dry_run: false,
+            format: OutputFormat::Text,
         }
     }
 }
@@ -1,43 +1,45 @@
 //! Build command implementation.
 
+use crate::cli::OutputFormat;
+use crate::hooks::{self, Hook, HookEnv};
 use anyhow::{Context, Result};
 use chrysalis_config::{Config, Platform};
-use chrysalis_core::BuildContext;
+use chrysalis_core::{BuildContext, Cache};
 use chrysalis_flutter::FlutterExecutor;
-use chrysalis_plugins::{ChunkPlugin, HashPlugin, InjectPlugin, MinifyPlugin, Plugin};
+use chrysalis_plugins::{BudgetCheck, Plan, PlannedFile, ProcessOptions};
 use console::style;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
-use tracing::{error, info, warn};
+use tracing::{info, warn};
+
+/// Build command options.
+pub struct BuildOptions {
+    /// Build all enabled platforms.
+    pub build_all: bool,
+    /// Clean before build.
+    pub clean: bool,
+    /// Build mode (e.g., development, production, staging).
+    pub mode: Option<String>,
+    /// Plan post-processing without touching disk.
+    pub dry_run: bool,
+    /// Output format for dry runs.
+    pub format: OutputFormat,
+}
 
 pub async fn execute(
     config_path: PathBuf,
     project_dir: Option<PathBuf>,
     platforms: Vec<Platform>,
-    build_all: bool,
-    clean: bool,
-    mode: Option<String>,
+    options: BuildOptions,
 ) -> Result<()> {
     let start = Instant::now();
 
-    println!();
-    println!(
-        "{}",
-        style("╔═══════════════════════════════════════════════════╗").cyan()
-    );
-    println!(
-        "{}",
-        style("║           CHRYSALIS 🦋                            ║").cyan()
-    );
-    println!(
-        "{}",
-        style("║   Modern Build System for Flutter                 ║").cyan()
-    );
-    println!(
-        "{}",
-        style("╚═══════════════════════════════════════════════════╝").cyan()
-    );
-    println!();
+    // Keep stdout machine-readable
+    let quiet = options.dry_run && options.format == OutputFormat::Json;
+
+    if !quiet {
+        print_banner();
+    }
 
     // Determine project directory
     let project_dir = project_dir
@@ -59,7 +61,7 @@ pub async fn execute(
     config.validate()?;
 
     // Determine which platforms to build
-    let platforms_to_build = if build_all {
+    let platforms_to_build = if options.build_all {
         // Build all enabled platforms from config
         let enabled = config.platforms.enabled_platforms();
         if enabled.is_empty() {
@@ -82,7 +84,7 @@ pub async fn execute(
 
     // Build each platform
     for (idx, platform) in platforms_to_build.iter().enumerate() {
-        if idx > 0 {
+        if idx > 0 && !quiet {
             println!();
             println!("{}", style("─".repeat(50)).dim());
             println!();
@@ -92,8 +94,18 @@ pub async fn execute(
 
         // Currently only web platform is fully supported
         match platform {
+            Platform::Web if options.dry_run => {
+                plan_web_platform(&config, &project_dir, options.format).await?;
+            }
             Platform::Web => {
-                build_web_platform(&config, &project_dir, clean, mode.clone()).await?;
+                build_web_platform(&config, &project_dir, options.clean, options.mode.clone())
+                    .await?;
+            }
+            _ if options.dry_run => {
+                warn!(
+                    "Platform {} has no post-processing to plan, skipping",
+                    platform
+                );
             }
             _ => {
                 warn!(
@@ -101,11 +113,23 @@ pub async fn execute(
                     platform
                 );
                 // For other platforms, just run flutter build
-                build_other_platform(&config, &project_dir, *platform, mode.clone()).await?;
+                build_other_platform(&config, &project_dir, *platform, options.mode.clone())
+                    .await?;
             }
         }
     }
 
+    if options.dry_run {
+        if !quiet {
+            println!(
+                "{}",
+                style("✓ Dry run completed, nothing was written").green()
+            );
+            println!();
+        }
+        return Ok(());
+    }
+
     let elapsed = start.elapsed();
     println!();
     println!(
@@ -118,6 +142,28 @@ pub async fn execute(
     Ok(())
 }
 
+/// Print the Chrysalis banner.
+fn print_banner() {
+    println!();
+    println!(
+        "{}",
+        style("╔═══════════════════════════════════════════════════╗").cyan()
+    );
+    println!(
+        "{}",
+        style("║           CHRYSALIS 🦋                            ║").cyan()
+    );
+    println!(
+        "{}",
+        style("║   Modern Build System for Flutter                 ║").cyan()
+    );
+    println!(
+        "{}",
+        style("╚═══════════════════════════════════════════════════╝").cyan()
+    );
+    println!();
+}
+
 /// Build web platform with full post-processing pipeline.
 async fn build_web_platform(
     config: &Config,
@@ -157,6 +203,22 @@ async fn build_web_platform(
         println!();
     }
 
+    // Final output directory (processed in place without output_dir)
+    let flutter_build_dir = project_dir.join(web_config.flutter_build_dir());
+    let processing_dir = web_config
+        .output_dir
+        .as_ref()
+        .map(|dir| project_dir.join(dir))
+        .unwrap_or_else(|| flutter_build_dir.clone());
+
+    let hooks = &config.build.hooks;
+    let hook_env = HookEnv {
+        project_dir,
+        output_dir: &processing_dir,
+        mode: mode.as_deref(),
+        platform: Platform::Web,
+    };
+
     // Phase 1: Flutter build
     println!("{}", style("Phase 1: Flutter Build").yellow().bold());
     println!("{}", style("─".repeat(50)).dim());
@@ -166,9 +228,11 @@ async fn build_web_platform(
         Platform::Web,
         web_config.flutter.clone(),
         config.env.clone(),
-        mode,
+        mode.clone(),
     )?;
 
+    hooks::run(Hook::PreBuild, hooks, &hook_env)?;
+
     // Run pub get
     if web_config.flutter.run_pub_get {
         flutter_executor.pub_get()?;
@@ -177,12 +241,13 @@ async fn build_web_platform(
     // Run flutter build
     flutter_executor.build()?;
 
+    hooks::run(Hook::PostFlutter, hooks, &hook_env)?;
+
     println!();
 
     // Phase 2: Copy build artifacts to output directory
-    let flutter_build_dir = project_dir.join(web_config.flutter_build_dir());
-    let processing_dir = if let Some(output_dir) = &web_config.output_dir {
-        let output_path = project_dir.join(output_dir);
+    if web_config.output_dir.is_some() {
+        let output_path = &processing_dir;
 
         println!("{}", style("Phase 2: Copy Build Artifacts").yellow().bold());
         println!("{}", style("─".repeat(50)).dim());
@@ -194,72 +259,69 @@ async fn build_web_platform(
         );
 
         // Copy Flutter build output to output directory
-        chrysalis_core::copy_dir_all(&flutter_build_dir, &output_path)
+        chrysalis_core::copy_dir_all(&flutter_build_dir, output_path)
             .context("Failed to copy build artifacts")?;
 
         info!("✓ Build artifacts copied to {}", output_path.display());
         println!();
-
-        output_path
     } else {
         // Process in-place
         info!(
             "Processing files in-place at {}",
             flutter_build_dir.display()
         );
-        flutter_build_dir
-    };
+    }
 
     // Phase 3: Post-processing
     println!("{}", style("Phase 3: Post-Processing").yellow().bold());
     println!("{}", style("─".repeat(50)).dim());
 
-    let mut ctx = BuildContext::new(&processing_dir, web_config.exclude_patterns.clone())?;
+    let mut ctx = BuildContext::new(&processing_dir, web_config.exclude_patterns.clone())?
+        .with_parallel_jobs(config.build.parallel_jobs_or_cpus());
     ctx.scan()?;
 
-    // Build plugin pipeline
-    let mut plugins: Vec<Box<dyn Plugin>> = Vec::new();
-
-    // Determine if inject plugin will run (only if both chunk and inject are enabled)
-    let will_inject = web_config.plugins.chunk.enabled && web_config.plugins.inject.enabled;
-
-    // Phase 1: Minify
-    if web_config.plugins.minify.enabled {
-        // Skip index.html during minification if inject plugin will handle it
-        plugins.push(Box::new(MinifyPlugin::new(
-            web_config.plugins.minify.clone(),
-            will_inject,
-        )));
-    }
-
-    // Phase 2: Chunk (BEFORE hashing, so Flutter can reference main.dart.js)
-    if web_config.plugins.chunk.enabled {
-        plugins.push(Box::new(ChunkPlugin::new(
-            web_config.plugins.chunk.clone(),
-        )?));
-    }
-
-    // Phase 3: Hash (AFTER chunking, so stub and chunks get hashed together)
-    if web_config.plugins.hash.enabled {
-        plugins.push(Box::new(HashPlugin::new(web_config.plugins.hash.clone())?));
-    }
-
-    // Phase 4: Inject (updates references to hashed files)
-    if web_config.plugins.chunk.enabled && web_config.plugins.inject.enabled {
-        plugins.push(Box::new(InjectPlugin::new(
-            web_config.plugins.inject.clone(),
-        )));
-    }
+    // Persistent cache shared across builds
+    let cache = config
+        .build
+        .cache
+        .enabled
+        .then(|| Cache::new(project_dir.join(&config.build.cache.dir)));
 
     // Execute plugins
-    for plugin in plugins {
-        info!("Running plugin: {}", plugin.name());
-        if let Err(e) = plugin.execute(&mut ctx).await {
-            error!("Plugin '{}' failed: {}", plugin.name(), e);
-            return Err(e.into());
+    let options = ProcessOptions {
+        dry_run: false,
+        cache: cache.clone(),
+        working_dir: Some(project_dir.to_path_buf()),
+    };
+    chrysalis_plugins::process(&mut ctx, web_config, &options).await?;
+
+    hooks::run(Hook::PostProcess, hooks, &hook_env)?;
+
+    // Keep the cache within its size limit
+    if let Some(cache) = &cache
+        && let Some(max_size) = config.build.cache.max_size_bytes()
+    {
+        match cache.prune(max_size) {
+            Ok(removed) if removed.entries > 0 => info!(
+                "Pruned {} cache entries ({})",
+                removed.entries,
+                chrysalis_core::format_bytes(removed.size)
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to prune cache: {}", e),
         }
     }
 
+    // Bundle size report (kept out of the output directory)
+    let report_path = web_config
+        .report
+        .enabled
+        .then(|| project_dir.join(&web_config.report.path));
+    if let Some(path) = &report_path {
+        chrysalis_plugins::write_report(&ctx, path)?;
+        info!("Wrote bundle report to {}", path.display());
+    }
+
     println!();
 
     // Print summary
@@ -270,6 +332,9 @@ async fn build_web_platform(
     println!("  Platform:         web");
     println!("  Total files:      {}", stats.total_files);
     println!("  Minified files:   {}", stats.minified_files);
+    if stats.cached_files > 0 {
+        println!("  From cache:       {}", stats.cached_files);
+    }
     println!("  Hashed files:     {}", stats.hashed_files);
     println!("  Chunked files:    {}", stats.chunked_files);
     println!("  Total chunks:     {}", stats.total_chunks);
@@ -278,16 +343,179 @@ async fn build_web_platform(
         chrysalis_core::format_bytes(stats.bytes_saved)
     );
 
+    if stats.compressed_files > 0 {
+        println!("  Compressed files: {}", stats.compressed_files);
+        println!(
+            "  Transfer size:    {}",
+            chrysalis_core::format_bytes(stats.transfer_size)
+        );
+    }
+
     if stats.original_size > 0 {
         println!("  Compression:      {:.1}%", stats.compression_ratio());
     }
 
     println!("  Output:           {}", processing_dir.display());
+    if let Some(path) = &report_path {
+        println!("  Report:           {}", path.display());
+    }
+    println!();
+
+    // Size budgets
+    if !web_config.budgets.is_empty() {
+        let checks = chrysalis_plugins::check_budgets(&ctx, &web_config.budgets)?;
+        print_budgets(&checks);
+
+        let exceeded = checks.iter().filter(|c| !c.passed()).count();
+        if exceeded > 0 {
+            anyhow::bail!("{} of {} size budgets exceeded", exceeded, checks.len());
+        }
+    }
+
+    hooks::run(Hook::PostBuild, hooks, &hook_env)?;
+
+    Ok(())
+}
+
+/// Print the budget table.
+fn print_budgets(checks: &[BudgetCheck]) {
+    println!("{}", style("Size Budgets").yellow().bold());
+    println!("{}", style("─".repeat(50)).dim());
+
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    for check in checks {
+        let status = if check.passed() {
+            style("✓ under").green()
+        } else {
+            style("✗ over ").red()
+        };
+        println!(
+            "  {}  {:<width$}  {:>12} / {:<12}",
+            status,
+            check.name,
+            check.format(check.actual),
+            check.format(check.limit),
+            width = width
+        );
+    }
     println!();
+}
+
+/// Plan web post-processing on the existing Flutter output, without touching disk.
+async fn plan_web_platform(
+    config: &Config,
+    project_dir: &Path,
+    format: OutputFormat,
+) -> Result<()> {
+    let web_config = &config.platforms.web;
+
+    if !web_config.enabled {
+        warn!("Web platform is disabled in configuration, skipping");
+        return Ok(());
+    }
+
+    // Plan against the Flutter output the real build would copy and process
+    let build_dir = project_dir.join(web_config.flutter_build_dir());
+    if !build_dir.exists() {
+        anyhow::bail!(
+            "Flutter build output not found: {}\nRun `chrysalis build` (or `flutter build web`) first",
+            build_dir.display()
+        );
+    }
+
+    let mut ctx = BuildContext::new(&build_dir, web_config.exclude_patterns.clone())?
+        .with_parallel_jobs(config.build.parallel_jobs_or_cpus());
+    ctx.scan()?;
+
+    let options = ProcessOptions {
+        dry_run: true,
+        cache: None,
+        working_dir: Some(project_dir.to_path_buf()),
+    };
+    let plan = chrysalis_plugins::process(&mut ctx, web_config, &options)
+        .await?
+        .context("Dry run produced no plan")?;
+
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&plan)?);
+        }
+        OutputFormat::Text => print_plan(&plan),
+    }
 
     Ok(())
 }
 
+/// Print a dry-run plan.
+fn print_plan(plan: &Plan) {
+    use chrysalis_core::format_bytes;
+
+    println!("{}", style("Dry Run Plan").yellow().bold());
+    println!("{}", style("─".repeat(50)).dim());
+    println!("  Scanned:  {}", plan.build_dir.display());
+    println!("  Pipeline: {}", plan.plugins.join(" → "));
+    println!();
+
+    for file in &plan.files {
+        let mut actions = Vec::new();
+        if file.parent.is_some() {
+            actions.push("new".to_string());
+        }
+        if file.minify {
+            actions.push("minify".to_string());
+        }
+        if !file.chunks.is_empty() {
+            actions.push(format!("chunk ×{}", file.chunks.len()));
+        }
+        if file.hash {
+            actions.push("hash".to_string());
+        }
+        if file.inject {
+            actions.push("inject".to_string());
+        }
+        if file.compress {
+            actions.push("compress".to_string());
+        }
+        if file.removed {
+            actions.push("remove".to_string());
+        }
+
+        if actions.is_empty() {
+            continue;
+        }
+
+        println!(
+            "  {}  {} → {}  [{}]",
+            style(&file.original).cyan(),
+            format_bytes(file.size),
+            format_bytes(file.estimated_size),
+            actions.join(", ")
+        );
+        if file.path != file.original && !file.removed {
+            println!("      → {}", file.path);
+        }
+    }
+
+    let count = |f: fn(&PlannedFile) -> bool| plan.files.iter().filter(|p| f(p)).count();
+
+    println!();
+    println!("{}", style("Plan Summary").green().bold());
+    println!("{}", style("═".repeat(50)).dim());
+    println!("  Total files:      {}", count(|f| f.parent.is_none()));
+    println!("  To minify:        {}", count(|f| f.minify));
+    println!("  To chunk:         {}", count(|f| !f.chunks.is_empty()));
+    println!("  New chunks:       {}", count(|f| f.parent.is_some()));
+    println!("  To rename:        {}", count(|f| f.hash));
+    println!("  To inject:        {}", count(|f| f.inject));
+    println!("  To compress:      {}", count(|f| f.compress));
+    println!(
+        "  Estimated size:   {} → {}",
+        format_bytes(plan.total_size()),
+        format_bytes(plan.estimated_total_size())
+    );
+    println!();
+}
+
 /// Build other platforms (no post-processing yet).
 async fn build_other_platform(
     config: &Config,
@@ -305,9 +533,20 @@ async fn build_other_platform(
         platform,
         flutter_config,
         config.env.clone(),
-        mode,
+        mode.clone(),
     )?;
 
+    let hooks = &config.build.hooks;
+    let output_dir = flutter_executor.flutter_build_dir();
+    let hook_env = HookEnv {
+        project_dir,
+        output_dir: &output_dir,
+        mode: mode.as_deref(),
+        platform,
+    };
+
+    hooks::run(Hook::PreBuild, hooks, &hook_env)?;
+
     // Run pub get
     if flutter_executor.config().run_pub_get {
         flutter_executor.pub_get()?;
@@ -316,15 +555,16 @@ async fn build_other_platform(
     // Run flutter build
     flutter_executor.build()?;
 
+    hooks::run(Hook::PostFlutter, hooks, &hook_env)?;
+
     println!();
     println!("{}", style("Build Summary").green().bold());
     println!("{}", style("═".repeat(50)).dim());
     println!("  Platform:         {}", platform);
-    println!(
-        "  Output:           {}",
-        flutter_executor.flutter_build_dir().display()
-    );
+    println!("  Output:           {}", output_dir.display());
     println!();
 
+    hooks::run(Hook::PostBuild, hooks, &hook_env)?;
+
     Ok(())
 }
@@ -0,0 +1,126 @@
+//! Cache command - inspect and prune the persistent build cache.
+
+use crate::cli::CacheAction;
+use anyhow::{Context, Result};
+use chrysalis_config::Config;
+use chrysalis_core::{Cache, format_bytes};
+use console::style;
+use std::path::{Component, Path, PathBuf};
+
+/// Resolve `.` and `..` in an absolute path without touching the disk.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Resolve the cache directory, refusing one that is or contains the project
+/// or build output: pruning must never reach source or output files.
+fn cache_dir(config: &Config, project_dir: &Path) -> Result<PathBuf> {
+    let project_dir = normalize(&std::path::absolute(project_dir)?);
+    let dir = &config.build.cache.dir;
+    let cache_dir = normalize(&project_dir.join(dir));
+
+    let web = &config.platforms.web;
+    let protected = [Some(web.flutter_build_dir()), web.output_dir().cloned()]
+        .into_iter()
+        .flatten()
+        .map(|d| normalize(&project_dir.join(d)));
+    let protected = [project_dir.clone()].into_iter().chain(protected);
+    for protected in protected {
+        if protected.starts_with(&cache_dir) {
+            anyhow::bail!(
+                "build.cache.dir '{}' ({}) must not be or contain {}",
+                dir.display(),
+                cache_dir.display(),
+                protected.display()
+            );
+        }
+    }
+
+    Ok(cache_dir)
+}
+
+pub async fn execute(
+    config_path: PathBuf,
+    project_dir: Option<PathBuf>,
+    action: Option<CacheAction>,
+) -> Result<()> {
+    let project_dir = project_dir
+        .or_else(|| std::env::current_dir().ok())
+        .context("Failed to determine project directory")?;
+
+    // Load config to get the cache location and size limit
+    let config = if config_path.exists() {
+        Config::from_file(&config_path)?
+    } else {
+        Config::default()
+    };
+    config.validate()?;
+    let cache_config = &config.build.cache;
+    let cache = Cache::new(cache_dir(&config, &project_dir)?);
+
+    println!();
+
+    match action.unwrap_or(CacheAction::Info) {
+        CacheAction::Info => {
+            let stats = cache.stats();
+            println!("{}", style("Build Cache").cyan().bold());
+            println!("{}", style("═".repeat(50)).dim());
+            println!("  Location:         {}", cache.dir().display());
+            println!(
+                "  Status:           {}",
+                if cache_config.enabled {
+                    "enabled"
+                } else {
+                    "disabled"
+                }
+            );
+            println!("  Entries:          {}", stats.entries);
+            println!("  Size:             {}", format_bytes(stats.size));
+            match cache_config.max_size_bytes() {
+                Some(max_size) => println!("  Limit:            {}", format_bytes(max_size)),
+                None => println!("  Limit:            unlimited"),
+            }
+        }
+        CacheAction::Prune { max_size_mb, all } => {
+            let removed = if all {
+                cache.clear()?
+            } else {
+                let max_size = max_size_mb
+                    .map(|mb| mb * 1024 * 1024)
+                    .or_else(|| cache_config.max_size_bytes())
+                    .unwrap_or(u64::MAX);
+                cache.prune(max_size)?
+            };
+
+            println!(
+                "{}",
+                style(format!(
+                    "✓ Removed {} entries ({})",
+                    removed.entries,
+                    format_bytes(removed.size)
+                ))
+                .green()
+            );
+
+            let stats = cache.stats();
+            println!(
+                "  Remaining:        {} entries ({})",
+                stats.entries,
+                format_bytes(stats.size)
+            );
+        }
+    }
+
+    println!();
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Diff command - compare output sizes of two builds.
+
+use crate::cli::DiffFormat;
+use anyhow::{Context, Result};
+use chrysalis_config::Config;
+use chrysalis_core::{FileNaming, format_bytes};
+use chrysalis_plugins::{BuildSizes, DiffStatus, FileDiff, SizeDiff};
+use console::style;
+use std::path::{Path, PathBuf};
+
+pub async fn execute(
+    config_path: PathBuf,
+    old: PathBuf,
+    new: PathBuf,
+    format: DiffFormat,
+    all: bool,
+) -> Result<()> {
+    // Hashed names are recognised by the configured hash scheme and templates
+    let config = if config_path.exists() {
+        Config::from_file(&config_path)?
+    } else {
+        Config::default()
+    };
+    let plugins = &config.platforms.web.plugins;
+    let naming = FileNaming::from_config(&plugins.hash, &plugins.chunk)?;
+
+    let old_sizes = BuildSizes::load(&old, &naming)
+        .with_context(|| format!("Failed to load build sizes from {}", old.display()))?;
+    let new_sizes = BuildSizes::load(&new, &naming)
+        .with_context(|| format!("Failed to load build sizes from {}", new.display()))?;
+
+    let diff = SizeDiff::between(&old_sizes, &new_sizes);
+
+    match format {
+        DiffFormat::Text => print_text(&diff, &old, &new, all),
+        DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+        DiffFormat::Markdown => print!("{}", markdown(&diff, all)),
+    }
+
+    Ok(())
+}
+
+/// Format a signed size change.
+fn format_delta(delta: i64) -> String {
+    let sign = match delta.signum() {
+        1 => "+",
+        -1 => "-",
+        _ => "",
+    };
+    format!("{}{}", sign, format_bytes(delta.unsigned_abs()))
+}
+
+/// Format a size change relative to the old size.
+fn format_percent(old: u64, delta: i64) -> String {
+    if old == 0 {
+        if delta == 0 { "0.0%" } else { "new" }.to_string()
+    } else {
+        format!("{:+.1}%", delta as f64 * 100.0 / old as f64)
+    }
+}
+
+/// Format an optional size.
+fn format_size(size: Option<u64>) -> String {
+    size.map(format_bytes).unwrap_or_else(|| "–".to_string())
+}
+
+/// Files to list.
+fn listed(diff: &SizeDiff, all: bool) -> Vec<&FileDiff> {
+    if all {
+        diff.files.iter().collect()
+    } else {
+        diff.changes().collect()
+    }
+}
+
+/// Print the diff as a terminal table.
+fn print_text(diff: &SizeDiff, old: &Path, new: &Path, all: bool) {
+    println!();
+    println!("{}", style("Size Diff").cyan().bold());
+    println!("{}", style("═".repeat(50)).dim());
+    println!(
+        "  Old:     {} ({})",
+        old.display(),
+        format_bytes(diff.old_size)
+    );
+    println!(
+        "  New:     {} ({})",
+        new.display(),
+        format_bytes(diff.new_size)
+    );
+    println!(
+        "  Change:  {} ({})",
+        format_delta(diff.delta),
+        format_percent(diff.old_size, diff.delta)
+    );
+    println!();
+
+    println!("{}", style("By Type").yellow().bold());
+    println!("{}", style("─".repeat(50)).dim());
+    for kind in &diff.types {
+        println!(
+            "  {:<12} {:>12} → {:<12} {:>12} {:>8}",
+            kind.kind,
+            format_bytes(kind.old_size),
+            format_bytes(kind.new_size),
+            format_delta(kind.delta),
+            format_percent(kind.old_size, kind.delta)
+        );
+    }
+    println!();
+
+    let files = listed(diff, all);
+    println!("{}", style("Files").yellow().bold());
+    println!("{}", style("─".repeat(50)).dim());
+    if files.is_empty() {
+        println!("  No size changes");
+    }
+    for file in files {
+        let marker = match file.status {
+            DiffStatus::Added => style("+").green(),
+            DiffStatus::Removed => style("-").red(),
+            DiffStatus::Changed if file.delta > 0 => style("▲").red(),
+            DiffStatus::Changed => style("▼").green(),
+            DiffStatus::Unchanged => style(" ").dim(),
+        };
+        println!(
+            "  {} {:>12} → {:<12} {:>12}  {}",
+            marker,
+            format_size(file.old_size),
+            format_size(file.new_size),
+            format_delta(file.delta),
+            file.name
+        );
+    }
+    println!();
+}
+
+/// Render the diff as GitHub-flavoured markdown (for PR comments).
+fn markdown(diff: &SizeDiff, all: bool) -> String {
+    let mut out = format!(
+        "### Bundle size: {} → {} ({}, {})\n\n",
+        format_bytes(diff.old_size),
+        format_bytes(diff.new_size),
+        format_delta(diff.delta),
+        format_percent(diff.old_size, diff.delta)
+    );
+
+    out.push_str("| Type | Old | New | Change | % |\n");
+    out.push_str("|:-----|----:|----:|-------:|--:|\n");
+    for kind in &diff.types {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            kind.kind,
+            format_bytes(kind.old_size),
+            format_bytes(kind.new_size),
+            format_delta(kind.delta),
+            format_percent(kind.old_size, kind.delta)
+        ));
+    }
+
+    let files = listed(diff, all);
+    if files.is_empty() {
+        out.push_str("\nNo file size changes.\n");
+        return out;
+    }
+
+    out.push_str(&format!(
+        "\n<details>\n<summary>{} files</summary>\n\n",
+        files.len()
+    ));
+    out.push_str("| File | Status | Old | New | Change |\n");
+    out.push_str("|:-----|:-------|----:|----:|-------:|\n");
+    for file in files {
+        let status = match file.status {
+            DiffStatus::Added => "added",
+            DiffStatus::Removed => "removed",
+            DiffStatus::Changed => "changed",
+            DiffStatus::Unchanged => "unchanged",
+        };
+        out.push_str(&format!(
+            "| `{}` | {} | {} | {} | {} |\n",
+            file.name.replace('|', "\\|"),
+            status,
+            format_size(file.old_size),
+            format_size(file.new_size),
+            format_delta(file.delta)
+        ));
+    }
+    out.push_str("\n</details>\n");
+
+    out
+}
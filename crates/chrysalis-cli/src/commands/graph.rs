@@ -0,0 +1,53 @@
+//! Graph command - export the references between output files.
+
+use crate::cli::GraphFormat;
+use anyhow::{Context, Result};
+use chrysalis_config::Config;
+use chrysalis_core::BuildContext;
+use chrysalis_plugins::ReferenceGraph;
+use std::path::PathBuf;
+
+pub async fn execute(
+    config_path: PathBuf,
+    project_dir: Option<PathBuf>,
+    dir: Option<PathBuf>,
+    format: GraphFormat,
+) -> Result<()> {
+    let project_dir = project_dir
+        .or_else(|| std::env::current_dir().ok())
+        .context("Failed to determine project directory")?;
+
+    let config = if config_path.exists() {
+        Config::from_file(&config_path)?
+    } else {
+        Config::default()
+    };
+    let web_config = &config.platforms.web;
+
+    // Default to the final output of the web build
+    let dir = dir.unwrap_or_else(|| {
+        web_config
+            .output_dir
+            .as_ref()
+            .map(|output_dir| project_dir.join(output_dir))
+            .unwrap_or_else(|| project_dir.join(web_config.flutter_build_dir()))
+    });
+    if !dir.is_dir() {
+        anyhow::bail!("Build output not found: {}", dir.display());
+    }
+
+    let mut ctx = BuildContext::new(&dir, web_config.exclude_patterns.clone())?
+        .with_parallel_jobs(config.build.parallel_jobs_or_cpus());
+    ctx.scan()?;
+
+    // Nothing was renamed, so this only records the graph
+    chrysalis_plugins::update_references(&mut ctx)?;
+    let graph = ReferenceGraph::from_context(&ctx);
+
+    match format {
+        GraphFormat::Dot => print!("{}", graph.to_dot()),
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&graph)?),
+    }
+
+    Ok(())
+}
@@ -1,7 +1,10 @@
 //! Command handlers.
 
 mod build;
+mod cache;
 mod clean;
+mod diff;
+mod graph;
 mod init;
 
 use crate::cli::{Args, Command};
@@ -15,9 +18,30 @@ pub async fn execute(args: Args) -> Result<()> {
             all,
             clean,
             mode,
-        } => build::execute(args.config, args.project_dir, platform, all, clean, mode).await,
+            dry_run,
+            format,
+        } => {
+            let options = build::BuildOptions {
+                build_all: all,
+                clean,
+                mode,
+                dry_run,
+                format,
+            };
+            build::execute(args.config, args.project_dir, platform, options).await
+        }
         Command::Init { force } => init::execute(args.config, force).await,
         Command::Clean => clean::execute(args.project_dir).await,
+        Command::Cache { action } => cache::execute(args.config, args.project_dir, action).await,
+        Command::Diff {
+            old,
+            new,
+            format,
+            all,
+        } => diff::execute(args.config, old, new, format, all).await,
+        Command::Graph { dir, format } => {
+            graph::execute(args.config, args.project_dir, dir, format).await
+        }
         Command::Version => {
             println!("chrysalis {}", env!("CARGO_PKG_VERSION"));
             Ok(())
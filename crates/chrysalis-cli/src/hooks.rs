@@ -0,0 +1,91 @@
+//! Lifecycle hooks - user shell commands run at fixed points of a build.
+
+use anyhow::{Context, Result, bail};
+use chrysalis_config::{HooksConfig, Platform};
+use std::path::Path;
+use std::process::Command;
+use tracing::info;
+
+/// Point in the build at which hooks run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    /// Before `flutter pub get`.
+    PreBuild,
+    /// After `flutter build`.
+    PostFlutter,
+    /// After post-processing.
+    PostProcess,
+    /// After the build summary.
+    PostBuild,
+}
+
+impl Hook {
+    /// Name as written in the configuration.
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::PreBuild => "pre_build",
+            Hook::PostFlutter => "post_flutter",
+            Hook::PostProcess => "post_process",
+            Hook::PostBuild => "post_build",
+        }
+    }
+
+    /// Commands configured for this hook.
+    fn commands(self, config: &HooksConfig) -> &[String] {
+        match self {
+            Hook::PreBuild => &config.pre_build,
+            Hook::PostFlutter => &config.post_flutter,
+            Hook::PostProcess => &config.post_process,
+            Hook::PostBuild => &config.post_build,
+        }
+    }
+}
+
+/// Build details passed to hook commands as environment variables.
+pub struct HookEnv<'a> {
+    /// Flutter project directory (also the working directory).
+    pub project_dir: &'a Path,
+    /// Final output directory.
+    pub output_dir: &'a Path,
+    /// Build mode, if any.
+    pub mode: Option<&'a str>,
+    /// Platform being built.
+    pub platform: Platform,
+}
+
+/// Run the commands of a hook in order, failing on the first non-zero exit.
+pub fn run(hook: Hook, config: &HooksConfig, env: &HookEnv) -> Result<()> {
+    for command in hook.commands(config) {
+        info!("Running {} hook: {}", hook.name(), command);
+
+        let status = shell(command)
+            .current_dir(env.project_dir)
+            .env("CHRYSALIS_HOOK", hook.name())
+            .env("CHRYSALIS_PROJECT_DIR", env.project_dir)
+            .env("CHRYSALIS_OUTPUT_DIR", env.output_dir)
+            .env("CHRYSALIS_MODE", env.mode.unwrap_or_default())
+            .env("CHRYSALIS_PLATFORM", env.platform.to_string())
+            .status()
+            .with_context(|| format!("Failed to run {} hook: {}", hook.name(), command))?;
+
+        if !status.success() {
+            bail!("{} hook failed ({}): {}", hook.name(), status, command);
+        }
+    }
+
+    Ok(())
+}
+
+/// Command that runs `command` through the platform shell.
+fn shell(command: &str) -> Command {
+    let mut cmd;
+    if cfg!(windows) {
+        cmd = Command::new("cmd");
+        cmd.arg("/C");
+    } else {
+        cmd = Command::new("sh");
+        cmd.arg("-c");
+    }
+    cmd.arg(command);
+    cmd
+}
@@ -16,7 +16,9 @@ pub fn init(verbose: bool, debug: bool) -> Result<()> {
     tracing_subscriber::registry()
         .with(filter)
         .with(
+            // Logs go to stderr so stdout stays usable for JSON output
             fmt::layer()
+                .with_writer(std::io::stderr)
                 .with_target(false)
                 .with_thread_ids(false)
                 .with_thread_names(false)
@@ -2,6 +2,7 @@
 
 mod cli;
 mod commands;
+mod hooks;
 mod logger;
 
 use anyhow::Result;
@@ -2,6 +2,7 @@
 
 use crate::Result;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Build configuration shared across all platforms.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +16,59 @@ pub struct BuildConfig {
 
     /// Number of parallel jobs (0 = number of CPUs).
     pub parallel_jobs: usize,
+
+    /// Persistent cache for post-processing results.
+    pub cache: CacheConfig,
+
+    /// Shell commands run at fixed points of the build.
+    pub hooks: HooksConfig,
+}
+
+/// Persistent build cache configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Whether to reuse results from previous builds.
+    pub enabled: bool,
+
+    /// Cache directory (relative to project directory).
+    pub dir: PathBuf,
+
+    /// Maximum cache size in MB, pruned after each build (0 = unlimited).
+    pub max_size_mb: u64,
+}
+
+/// Lifecycle hooks.
+///
+/// Each hook is a list of shell commands, run in order from the project
+/// directory. A command that exits with a non-zero status aborts the build.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Before `flutter pub get`.
+    pub pre_build: Vec<String>,
+
+    /// After `flutter build`, before any copying or post-processing.
+    pub post_flutter: Vec<String>,
+
+    /// After the post-processing plugins.
+    pub post_process: Vec<String>,
+
+    /// After the build summary.
+    pub post_build: Vec<String>,
+}
+
+impl HooksConfig {
+    /// All hooks with their names, in the order they run.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &[String])> {
+        [
+            ("pre_build", self.pre_build.as_slice()),
+            ("post_flutter", self.post_flutter.as_slice()),
+            ("post_process", self.post_process.as_slice()),
+            ("post_build", self.post_build.as_slice()),
+        ]
+        .into_iter()
+    }
 }
 
 impl Default for BuildConfig {
@@ -23,14 +77,48 @@ impl Default for BuildConfig {
             clean_before_build: true,
             verbose: false,
             parallel_jobs: 0,
+            cache: CacheConfig::default(),
+            hooks: HooksConfig::default(),
         }
     }
 }
 
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: PathBuf::from(".chrysalis/cache"),
+            max_size_mb: 512,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Get maximum cache size in bytes (None = unlimited).
+    pub fn max_size_bytes(&self) -> Option<u64> {
+        (self.max_size_mb > 0).then(|| self.max_size_mb * 1024 * 1024)
+    }
+}
+
 impl BuildConfig {
     /// Validate build configuration.
     pub fn validate(&self) -> Result<()> {
-        // No validation needed currently
+        if self.cache.enabled && self.cache.dir.as_os_str().is_empty() {
+            return Err(crate::ConfigError::InvalidValue {
+                field: "build.cache.dir".to_string(),
+                reason: "cache directory cannot be empty".to_string(),
+            });
+        }
+
+        for (name, commands) in self.hooks.iter() {
+            if commands.iter().any(|c| c.trim().is_empty()) {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: format!("build.hooks.{}", name),
+                    reason: "hook command cannot be empty".to_string(),
+                });
+            }
+        }
+
         Ok(())
     }
 
@@ -71,5 +159,38 @@ mod tests {
     fn test_validation() {
         let config = BuildConfig::default();
         assert!(config.validate().is_ok());
+
+        let mut config = BuildConfig::default();
+        config.cache.dir = PathBuf::new();
+        assert!(config.validate().is_err());
+
+        let mut config = BuildConfig::default();
+        config.hooks.post_process = vec!["./upload.sh".to_string(), " ".to_string()];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_hooks_from_yaml() {
+        let yaml = r#"
+hooks:
+  pre_build:
+    - dart run build_runner build
+  post_build:
+    - ./scripts/upload.sh
+    - echo done
+"#;
+        let config: BuildConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.hooks.pre_build, vec!["dart run build_runner build"]);
+        assert!(config.hooks.post_flutter.is_empty());
+        assert_eq!(config.hooks.post_build.len(), 2);
+    }
+
+    #[test]
+    fn test_cache_max_size() {
+        let mut config = CacheConfig::default();
+        assert_eq!(config.max_size_bytes(), Some(512 * 1024 * 1024));
+
+        config.max_size_mb = 0;
+        assert_eq!(config.max_size_bytes(), None);
     }
 }
@@ -12,21 +12,28 @@ mod env;
 mod env_loader;
 mod error;
 mod flutter;
+mod pipeline;
 mod platform;
 mod platforms;
 mod plugins;
 mod web;
 
-pub use build::BuildConfig;
+pub use build::{BuildConfig, CacheConfig, HooksConfig};
 pub use config::{Config, ConfigBuilder};
 pub use env::EnvConfig;
 pub use env_loader::EnvLoader;
 pub use error::{ConfigError, Result};
 pub use flutter::FlutterConfig;
+pub use pipeline::PipelineStep;
 pub use platform::Platform;
 pub use platforms::PlatformsConfig;
-pub use plugins::{ChunkConfig, HashConfig, InjectConfig, MinifyConfig, PluginsConfig};
-pub use web::WebConfig;
+pub use plugins::{
+    BUILTIN_PLUGINS, ChunkConfig, ChunkLoaderConfig, CompressConfig, CspConfig, CspMode,
+    ExternalPluginConfig, FetchPriority, HashAlgorithm, HashConfig, HashEncoding, HintRel,
+    InjectConfig, InjectPosition, ManifestConfig, MinifyConfig, PluginsConfig, ResourceHintRule,
+    ResourceHintsConfig, ScriptExecution, ServiceWorkerConfig, SriAlgorithm, SriConfig,
+};
+pub use web::{BudgetsConfig, FileBudget, ReportConfig, WebConfig};
 
 #[cfg(test)]
 mod tests;
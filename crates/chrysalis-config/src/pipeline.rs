@@ -0,0 +1,100 @@
+//! Post-processing pipeline configuration.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// One step of the web post-processing pipeline.
+///
+/// Written either as a plugin name or as a map whose `options` override the
+/// plugin's `plugins.<name>` section for this step:
+///
+/// ```yaml
+/// pipeline:
+///   - minify
+///   - name: hash
+///     options:
+///       hash_length: 10
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StepDef")]
+pub struct PipelineStep {
+    /// Plugin name.
+    pub name: String,
+
+    /// Options merged over the plugin's configuration section.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub options: Map<String, Value>,
+}
+
+/// Accepted spellings of a pipeline step.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StepDef {
+    Name(String),
+    Step {
+        name: String,
+        #[serde(default)]
+        options: Map<String, Value>,
+    },
+}
+
+impl From<StepDef> for PipelineStep {
+    fn from(def: StepDef) -> Self {
+        match def {
+            StepDef::Name(name) => Self::new(name),
+            StepDef::Step { name, options } => Self { name, options },
+        }
+    }
+}
+
+impl PipelineStep {
+    /// Create a step without options.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            options: Map::new(),
+        }
+    }
+}
+
+/// Merge `overlay` into `base`: objects are merged key by key, anything else
+/// is replaced.
+pub(crate) fn merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge(base.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_step_forms() {
+        let yaml = r#"
+- minify
+- name: hash
+  options:
+    hash_length: 10
+"#;
+        let steps: Vec<PipelineStep> = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(steps[0], PipelineStep::new("minify"));
+        assert_eq!(steps[1].name, "hash");
+        assert_eq!(steps[1].options["hash_length"], 10);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut base = serde_json::json!({"a": 1, "b": {"c": 2, "d": 3}});
+        merge(&mut base, &serde_json::json!({"b": {"c": 4}, "e": [5]}));
+        assert_eq!(
+            base,
+            serde_json::json!({"a": 1, "b": {"c": 4, "d": 3}, "e": [5]})
+        );
+    }
+}
@@ -1,7 +1,8 @@
 //! Plugin configuration.
 
-use crate::Result;
+use crate::{PipelineStep, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Plugins configuration.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -18,8 +19,31 @@ pub struct PluginsConfig {
 
     /// Injection plugin configuration.
     pub inject: InjectConfig,
+
+    /// Service worker plugin configuration.
+    pub service_worker: ServiceWorkerConfig,
+
+    /// Precompression plugin configuration.
+    pub compress: CompressConfig,
+
+    /// Asset manifest configuration.
+    pub manifest: ManifestConfig,
+
+    /// External process plugins, keyed by the name used in `pipeline`.
+    pub external: BTreeMap<String, ExternalPluginConfig>,
 }
 
+/// Built-in plugin names, in default pipeline order.
+pub const BUILTIN_PLUGINS: &[&str] = &[
+    "minify",
+    "chunk",
+    "hash",
+    "inject",
+    "service_worker",
+    "compress",
+    "manifest",
+];
+
 /// Minification configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -47,9 +71,19 @@ pub struct HashConfig {
     /// Whether hashing is enabled.
     pub enabled: bool,
 
-    /// Hash length for content-based hashing.
+    /// Hash length for content-based hashing, in encoded characters.
     pub hash_length: usize,
 
+    /// Digest algorithm.
+    pub algorithm: HashAlgorithm,
+
+    /// How the digest is written in file names.
+    pub encoding: HashEncoding,
+
+    /// Output path template for hashed files, relative to the output
+    /// directory (`[dir]`, `[name]`, `[ext]`, `[hash]` or `[hash:N]`).
+    pub file_name: String,
+
     /// Files to include in hashing (glob patterns).
     pub include: Vec<String>,
 
@@ -57,6 +91,74 @@ pub struct HashConfig {
     pub exclude: Vec<String>,
 }
 
+/// Digest algorithm for content hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// MD5 (128 bits)
+    #[default]
+    Md5,
+    /// SHA-256 (256 bits)
+    Sha256,
+    /// XXH3, 128-bit variant (fast, non-cryptographic)
+    Xxh3,
+    /// BLAKE3 (256 bits)
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Name as written in the configuration.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Xxh3 => "xxh3",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Digest size in bytes.
+    pub fn digest_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Md5 | HashAlgorithm::Xxh3 => 16,
+            HashAlgorithm::Sha256 | HashAlgorithm::Blake3 => 32,
+        }
+    }
+}
+
+/// Encoding of content hashes in file names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HashEncoding {
+    /// Lowercase hexadecimal (`0-9a-f`)
+    #[default]
+    Hex,
+    /// Lowercase RFC 4648 base32 without padding (`a-z2-7`)
+    Base32,
+    /// URL-safe base64 without padding (`A-Za-z0-9-_`)
+    Base64url,
+}
+
+impl HashEncoding {
+    /// Name as written in the configuration.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashEncoding::Hex => "hex",
+            HashEncoding::Base32 => "base32",
+            HashEncoding::Base64url => "base64url",
+        }
+    }
+
+    /// Number of characters needed to encode `bytes` bytes.
+    pub fn encoded_len(&self, bytes: usize) -> usize {
+        match self {
+            HashEncoding::Hex => bytes * 2,
+            HashEncoding::Base32 => (bytes * 8).div_ceil(5),
+            HashEncoding::Base64url => (bytes * 8).div_ceil(6),
+        }
+    }
+}
+
 /// Chunking configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -70,6 +172,15 @@ pub struct ChunkConfig {
     /// Minimum file size for chunking in kilobytes.
     pub min_chunk_size_kb: usize,
 
+    /// Output path template for chunk files, relative to the output
+    /// directory (`[dir]`, `[name]`, `[ext]`, `[index]`).
+    pub file_name: String,
+
+    /// Also chunk WebAssembly modules (`*.wasm`) and ES module entrypoints
+    /// (`*.mjs`); the loader serves the merged chunks to `fetch`, so
+    /// `WebAssembly.instantiateStreaming` keeps working.
+    pub wasm: bool,
+
     /// Files to include in chunking (glob patterns).
     pub include: Vec<String>,
 
@@ -84,8 +195,327 @@ pub struct InjectConfig {
     /// Whether injection is enabled.
     pub enabled: bool,
 
-    /// Whether to inline the chunk manifest.
+    /// Whether to inline the chunk loader and manifest in index.html;
+    /// otherwise both are written to separate hashed files.
     pub inline_manifest: bool,
+
+    /// Where the resource hints and the loader go in index.html.
+    pub position: InjectPosition,
+
+    /// CSS selector of the element to inject before, with `position: before`.
+    pub selector: Option<String>,
+
+    /// Subresource Integrity configuration.
+    pub sri: SriConfig,
+
+    /// Content-Security-Policy configuration.
+    pub csp: CspConfig,
+
+    /// Chunk loader runtime configuration.
+    pub loader: ChunkLoaderConfig,
+
+    /// `<link>` resource hints for files needed at startup.
+    pub hints: ResourceHintsConfig,
+}
+
+/// Injection position in index.html.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum InjectPosition {
+    /// Right after the `<head>` start tag.
+    HeadStart,
+    /// Right before the `</head>` end tag.
+    #[default]
+    HeadEnd,
+    /// Right after the `<body>` start tag.
+    BodyStart,
+    /// Right before the first element matching `selector`.
+    Before,
+}
+
+/// Resource hint configuration.
+///
+/// Hints are considered for the chunks of chunked files, `canvaskit.wasm`
+/// and `skwasm.wasm`, `flutter_bootstrap.js` and the fonts listed in
+/// `FontManifest.json`. Fonts get no hint by default, since preloading every
+/// font in the manifest costs bandwidth for fonts a page may never use.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceHintsConfig {
+    /// Whether to inject resource hints.
+    pub enabled: bool,
+
+    /// Rules matched in order against original paths (of the chunked file
+    /// for chunks); the first match decides, files matching none get no hint.
+    pub rules: Vec<ResourceHintRule>,
+}
+
+/// A resource hint rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceHintRule {
+    /// Glob pattern over original paths relative to the output directory.
+    pub pattern: String,
+
+    /// Link relation to emit.
+    pub rel: HintRel,
+
+    /// Fetch priority (`fetchpriority` attribute).
+    #[serde(default)]
+    pub priority: FetchPriority,
+}
+
+/// Link relation of a resource hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HintRel {
+    /// `<link rel="preload">`: fetch now, needed for this page.
+    Preload,
+    /// `<link rel="modulepreload">`: fetch and parse an ES module now.
+    Modulepreload,
+    /// `<link rel="prefetch">`: fetch when idle, e.g. for a later navigation.
+    Prefetch,
+    /// No hint (to exclude files from later rules).
+    None,
+}
+
+impl HintRel {
+    /// Value of the `rel` attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HintRel::Preload => "preload",
+            HintRel::Modulepreload => "modulepreload",
+            HintRel::Prefetch => "prefetch",
+            HintRel::None => "none",
+        }
+    }
+}
+
+/// Fetch priority of a resource hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchPriority {
+    /// Browser default (no attribute).
+    #[default]
+    Auto,
+    /// `fetchpriority="high"`
+    High,
+    /// `fetchpriority="low"`
+    Low,
+}
+
+/// Chunk loader runtime configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkLoaderConfig {
+    /// Retries per chunk after the first failed request.
+    pub retries: u32,
+
+    /// Base delay before the first retry in milliseconds; doubles on every
+    /// retry, with jitter.
+    pub retry_delay_ms: u64,
+
+    /// Upper bound for the retry delay in milliseconds.
+    pub max_retry_delay_ms: u64,
+
+    /// Maximum number of chunk requests in flight (0 = no limit).
+    pub concurrency: usize,
+
+    /// Request timeout in milliseconds (0 = no timeout).
+    pub timeout_ms: u64,
+
+    /// Base URLs tried in turn after the page's own origin fails, e.g.
+    /// `https://cdn2.example.com/app/`.
+    pub fallback_origins: Vec<String>,
+
+    /// Whether to check each chunk's digest before the chunks are merged.
+    ///
+    /// Digests are skipped where Web Crypto is unavailable (insecure
+    /// contexts) unless SRI is enabled, in which case loading fails.
+    pub verify: bool,
+
+    /// How merged chunks of classic scripts are run.
+    pub execution: ScriptExecution,
+
+    /// Name of the Trusted Types policy that creates the chunk scripts (for
+    /// `require-trusted-types-for 'script'`); none if unset.
+    pub trusted_types_policy: Option<String>,
+
+    /// Whether to append `//# sourceURL=` naming the original file, so
+    /// DevTools and error reporters show it instead of an anonymous script.
+    pub source_url: bool,
+}
+
+/// How the chunk loader runs merged scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptExecution {
+    /// An inline `<script>` with the merged text (allowed by its CSP hash).
+    #[default]
+    Inline,
+    /// A `<script src="blob:...">` (allowed by `blob:` in `script-src`).
+    Blob,
+}
+
+/// Subresource Integrity (SRI) configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SriConfig {
+    /// Whether to add integrity attributes and verify chunk digests.
+    pub enabled: bool,
+
+    /// Digest algorithm.
+    pub algorithm: SriAlgorithm,
+
+    /// Value of the `crossorigin` attribute added alongside `integrity`.
+    pub crossorigin: String,
+}
+
+/// Content-Security-Policy configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CspConfig {
+    /// Whether to generate a Content-Security-Policy.
+    pub enabled: bool,
+
+    /// Where to emit the policy.
+    pub mode: CspMode,
+
+    /// Headers file name (relative to the output directory), used in `headers` mode.
+    pub headers_file: String,
+
+    /// Policy directives; script hashes are appended to `script-src`.
+    pub directives: BTreeMap<String, Vec<String>>,
+}
+
+/// Content-Security-Policy output mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CspMode {
+    /// `<meta http-equiv="Content-Security-Policy">` in index.html.
+    #[default]
+    Meta,
+    /// A `_headers` file (Netlify / Cloudflare Pages format).
+    Headers,
+}
+
+/// Subresource Integrity digest algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SriAlgorithm {
+    /// SHA-256
+    Sha256,
+    /// SHA-384
+    #[default]
+    Sha384,
+    /// SHA-512
+    Sha512,
+}
+
+impl SriAlgorithm {
+    /// Get the algorithm prefix used in integrity values (e.g., "sha384").
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SriAlgorithm::Sha256 => "sha256",
+            SriAlgorithm::Sha384 => "sha384",
+            SriAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+/// Service worker configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceWorkerConfig {
+    /// Whether to rewrite the `RESOURCES` map of `flutter_service_worker.js`.
+    pub enabled: bool,
+}
+
+/// Precompression configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressConfig {
+    /// Whether precompression is enabled.
+    pub enabled: bool,
+
+    /// Whether to write Brotli (`.br`) variants.
+    pub brotli: bool,
+
+    /// Brotli quality level (0-11).
+    pub brotli_level: u32,
+
+    /// Whether to write gzip (`.gz`) variants.
+    pub gzip: bool,
+
+    /// Gzip compression level (0-9).
+    pub gzip_level: u32,
+
+    /// Whether to write zstd (`.zst`) variants.
+    pub zstd: bool,
+
+    /// Zstd compression level (1-22).
+    pub zstd_level: i32,
+
+    /// Minimum file size for compression in kilobytes.
+    pub min_size_kb: usize,
+
+    /// Minimum size reduction (in percent) a variant must achieve to be written.
+    pub min_savings_percent: f64,
+
+    /// Files to include in compression (glob patterns).
+    pub include: Vec<String>,
+
+    /// Files to exclude from compression (glob patterns).
+    pub exclude: Vec<String>,
+}
+
+/// Asset manifest configuration.
+///
+/// Disabled by default: the manifest is for servers that render the host page
+/// themselves and need the final names, and for `chrysalis diff`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManifestConfig {
+    /// Whether to write the asset manifest.
+    pub enabled: bool,
+
+    /// Manifest file name (relative to the output directory).
+    pub file_name: String,
+}
+
+/// External process plugin configuration.
+///
+/// The executable receives a JSON description of the build on stdin and
+/// replies with file operations on stdout. It only runs when listed in
+/// `pipeline`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalPluginConfig {
+    /// Executable to run (looked up on `PATH`, or relative to the project root).
+    pub command: String,
+
+    /// Arguments passed to the executable.
+    pub args: Vec<String>,
+
+    /// Extra environment variables.
+    pub env: BTreeMap<String, String>,
+
+    /// Maximum run time in seconds (0 = no limit).
+    pub timeout_secs: u64,
+
+    /// Options passed to the plugin in its request.
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for ExternalPluginConfig {
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            args: Vec::new(),
+            env: BTreeMap::new(),
+            timeout_secs: 300,
+            options: serde_json::Map::new(),
+        }
+    }
 }
 
 impl Default for MinifyConfig {
@@ -105,6 +535,9 @@ impl Default for HashConfig {
         Self {
             enabled: true,
             hash_length: 8,
+            algorithm: HashAlgorithm::default(),
+            encoding: HashEncoding::default(),
+            file_name: "[dir]/[name].[hash].[ext]".to_string(),
             include: vec!["*.js".to_string(), "*.css".to_string()],
             exclude: vec!["*.map".to_string()],
         }
@@ -117,6 +550,8 @@ impl Default for ChunkConfig {
             enabled: true,
             chunk_size_kb: 400,
             min_chunk_size_kb: 400,
+            file_name: "[dir]/[name].chunk[index].[ext]".to_string(),
+            wasm: false,
             include: vec!["*.js".to_string()],
             exclude: vec!["flutter_service_worker.js".to_string()],
         }
@@ -128,6 +563,139 @@ impl Default for InjectConfig {
         Self {
             enabled: true,
             inline_manifest: true,
+            position: InjectPosition::HeadEnd,
+            selector: None,
+            sri: SriConfig::default(),
+            csp: CspConfig::default(),
+            loader: ChunkLoaderConfig::default(),
+            hints: ResourceHintsConfig::default(),
+        }
+    }
+}
+
+impl Default for ResourceHintsConfig {
+    fn default() -> Self {
+        let rule = |pattern: &str, rel, priority| ResourceHintRule {
+            pattern: pattern.to_string(),
+            rel,
+            priority,
+        };
+        Self {
+            enabled: true,
+            rules: vec![
+                rule(
+                    "flutter_bootstrap.js",
+                    HintRel::Preload,
+                    FetchPriority::High,
+                ),
+                rule("main.dart.*", HintRel::Preload, FetchPriority::High),
+            ],
+        }
+    }
+}
+
+impl Default for CspConfig {
+    fn default() -> Self {
+        let directive = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+
+        let mut directives = BTreeMap::new();
+        directives.insert("default-src".to_string(), directive(&["'self'"]));
+        directives.insert(
+            "script-src".to_string(),
+            directive(&["'self'", "'wasm-unsafe-eval'"]),
+        );
+        directives.insert(
+            "style-src".to_string(),
+            directive(&["'self'", "'unsafe-inline'"]),
+        );
+        directives.insert(
+            "img-src".to_string(),
+            directive(&["'self'", "data:", "blob:"]),
+        );
+        directives.insert(
+            "font-src".to_string(),
+            directive(&["'self'", "data:", "https://fonts.gstatic.com"]),
+        );
+        directives.insert(
+            "connect-src".to_string(),
+            directive(&["'self'", "https://fonts.gstatic.com"]),
+        );
+
+        Self {
+            enabled: false,
+            mode: CspMode::default(),
+            headers_file: "_headers".to_string(),
+            directives,
+        }
+    }
+}
+
+impl Default for SriConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            algorithm: SriAlgorithm::default(),
+            crossorigin: "anonymous".to_string(),
+        }
+    }
+}
+
+impl Default for ChunkLoaderConfig {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            retry_delay_ms: 500,
+            max_retry_delay_ms: 8000,
+            concurrency: 6,
+            timeout_ms: 30000,
+            fallback_origins: Vec::new(),
+            verify: true,
+            execution: ScriptExecution::Inline,
+            trusted_types_policy: None,
+            source_url: true,
+        }
+    }
+}
+
+impl Default for ServiceWorkerConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl Default for CompressConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            brotli: true,
+            brotli_level: 11,
+            gzip: true,
+            gzip_level: 9,
+            zstd: true,
+            zstd_level: 19,
+            min_size_kb: 1,
+            min_savings_percent: 5.0,
+            include: vec![
+                "*.js".to_string(),
+                "*.mjs".to_string(),
+                "*.css".to_string(),
+                "*.html".to_string(),
+                "*.json".to_string(),
+                "*.wasm".to_string(),
+                "*.svg".to_string(),
+                "*.ttf".to_string(),
+                "*.otf".to_string(),
+            ],
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl Default for ManifestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file_name: "asset-manifest.json".to_string(),
         }
     }
 }
@@ -144,9 +712,80 @@ impl ChunkConfig {
     }
 }
 
+impl CompressConfig {
+    /// Get minimum file size in bytes.
+    pub fn min_size_bytes(&self) -> usize {
+        self.min_size_kb * 1024
+    }
+}
+
 impl PluginsConfig {
+    /// Default pipeline, derived from the enabled plugins.
+    pub fn default_pipeline(&self) -> Vec<PipelineStep> {
+        // Inject only runs when it has something to add (chunk loader, SRI or CSP)
+        let will_inject = self.inject.enabled
+            && (self.chunk.enabled || self.inject.sri.enabled || self.inject.csp.enabled);
+
+        [
+            ("minify", self.minify.enabled),
+            ("chunk", self.chunk.enabled),
+            ("hash", self.hash.enabled),
+            ("inject", will_inject),
+            ("service_worker", self.service_worker.enabled),
+            ("compress", self.compress.enabled),
+            ("manifest", self.manifest.enabled),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| PipelineStep::new(name))
+        .collect()
+    }
+
+    /// Configuration for a pipeline step: the plugin's section with the
+    /// step's options merged in and the plugin enabled.
+    ///
+    /// For external plugins the step's options are merged into the options
+    /// passed to the process.
+    pub fn with_step(&self, step: &PipelineStep) -> Result<Self> {
+        let field = format!("platforms.web.pipeline.{}", step.name);
+        let invalid = |reason: String| crate::ConfigError::InvalidValue {
+            field: field.clone(),
+            reason,
+        };
+        let options = serde_json::Value::Object(step.options.clone());
+
+        let mut value = serde_json::to_value(self).map_err(|e| invalid(e.to_string()))?;
+        if let Some(external) = value["external"].get_mut(&step.name) {
+            crate::pipeline::merge(&mut external["options"], &options);
+        } else if BUILTIN_PLUGINS.contains(&step.name.as_str()) {
+            let section = &mut value[&step.name];
+            crate::pipeline::merge(section, &options);
+            section["enabled"] = serde_json::Value::Bool(true);
+        } else {
+            return Err(invalid(format!("unknown plugin '{}'", step.name)));
+        }
+
+        serde_json::from_value(value).map_err(|e| invalid(e.to_string()))
+    }
+
     /// Validate plugins configuration.
     pub fn validate(&self) -> Result<()> {
+        // Validate external plugins
+        for (name, external) in &self.external {
+            if BUILTIN_PLUGINS.contains(&name.as_str()) {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: format!("plugins.external.{}", name),
+                    reason: "name is used by a built-in plugin".to_string(),
+                });
+            }
+            if external.command.is_empty() {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: format!("plugins.external.{}.command", name),
+                    reason: "command cannot be empty".to_string(),
+                });
+            }
+        }
+
         // Validate chunk config
         if self.chunk.enabled {
             if self.chunk.chunk_size_kb == 0 {
@@ -164,10 +803,129 @@ impl PluginsConfig {
         }
 
         // Validate hash config
-        if self.hash.enabled && (self.hash.hash_length == 0 || self.hash.hash_length > 32) {
+        if self.hash.enabled {
+            let max_length = self
+                .hash
+                .encoding
+                .encoded_len(self.hash.algorithm.digest_len());
+            if self.hash.hash_length == 0 || self.hash.hash_length > max_length {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.hash.hash_length".to_string(),
+                    reason: format!(
+                        "hash length must be between 1 and {} for {} in {}",
+                        max_length,
+                        self.hash.algorithm.as_str(),
+                        self.hash.encoding.as_str()
+                    ),
+                });
+            }
+        }
+
+        // Validate compress config
+        if self.compress.enabled {
+            if self.compress.brotli_level > 11 {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.compress.brotli_level".to_string(),
+                    reason: "brotli level must be between 0 and 11".to_string(),
+                });
+            }
+            if self.compress.gzip_level > 9 {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.compress.gzip_level".to_string(),
+                    reason: "gzip level must be between 0 and 9".to_string(),
+                });
+            }
+            if !(1..=22).contains(&self.compress.zstd_level) {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.compress.zstd_level".to_string(),
+                    reason: "zstd level must be between 1 and 22".to_string(),
+                });
+            }
+            if !(0.0..100.0).contains(&self.compress.min_savings_percent) {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.compress.min_savings_percent".to_string(),
+                    reason: "min savings must be between 0 and 100".to_string(),
+                });
+            }
+        }
+
+        // Validate SRI config
+        if self.inject.sri.enabled
+            && !matches!(
+                self.inject.sri.crossorigin.as_str(),
+                "anonymous" | "use-credentials"
+            )
+        {
+            return Err(crate::ConfigError::InvalidValue {
+                field: "plugins.inject.sri.crossorigin".to_string(),
+                reason: "crossorigin must be 'anonymous' or 'use-credentials'".to_string(),
+            });
+        }
+
+        // Validate CSP config
+        if self.inject.csp.enabled
+            && self.inject.csp.mode == CspMode::Headers
+            && self.inject.csp.headers_file.trim().is_empty()
+        {
+            return Err(crate::ConfigError::InvalidValue {
+                field: "plugins.inject.csp.headers_file".to_string(),
+                reason: "headers file cannot be empty in headers mode".to_string(),
+            });
+        }
+
+        // Validate chunk loader config
+        for origin in &self.inject.loader.fallback_origins {
+            if !(origin.starts_with("https://") || origin.starts_with("http://")) {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.inject.loader.fallback_origins".to_string(),
+                    reason: format!("'{}' must be an absolute http(s) URL", origin),
+                });
+            }
+        }
+        if self.inject.loader.max_retry_delay_ms < self.inject.loader.retry_delay_ms {
+            return Err(crate::ConfigError::InvalidValue {
+                field: "plugins.inject.loader.max_retry_delay_ms".to_string(),
+                reason: "max retry delay must not be less than retry_delay_ms".to_string(),
+            });
+        }
+        match (&self.inject.position, &self.inject.selector) {
+            (InjectPosition::Before, None) => {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.inject.selector".to_string(),
+                    reason: "a selector is required with position 'before'".to_string(),
+                });
+            }
+            (_, Some(selector)) if selector.trim().is_empty() => {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.inject.selector".to_string(),
+                    reason: "selector cannot be empty".to_string(),
+                });
+            }
+            _ => {}
+        }
+        for rule in &self.inject.hints.rules {
+            if rule.pattern.trim().is_empty() {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.inject.hints.rules".to_string(),
+                    reason: "pattern cannot be empty".to_string(),
+                });
+            }
+        }
+        if let Some(policy) = &self.inject.loader.trusted_types_policy {
+            let valid = |c: char| c.is_ascii_alphanumeric() || "-#=_/@.%".contains(c);
+            if policy.is_empty() || !policy.chars().all(valid) {
+                return Err(crate::ConfigError::InvalidValue {
+                    field: "plugins.inject.loader.trusted_types_policy".to_string(),
+                    reason: format!("'{}' is not a valid Trusted Types policy name", policy),
+                });
+            }
+        }
+
+        // Validate manifest config
+        if self.manifest.enabled && self.manifest.file_name.trim().is_empty() {
             return Err(crate::ConfigError::InvalidValue {
-                field: "plugins.hash.hash_length".to_string(),
-                reason: "hash length must be between 1 and 32".to_string(),
+                field: "plugins.manifest.file_name".to_string(),
+                reason: "file name cannot be empty".to_string(),
             });
         }
 
@@ -46,7 +46,7 @@ platforms:
     let config = Config::from_file(temp_file.path()).unwrap();
     assert!(config.platforms.web.flutter.release);
     assert_eq!(config.build.parallel_jobs, 4);
-    assert_eq!(config.build.verbose, true);
+    assert!(config.build.verbose);
 }
 
 #[test]
@@ -62,3 +62,232 @@ fn test_config_builder() {
     assert!(config.build.verbose);
     assert_eq!(config.build.parallel_jobs, 2);
 }
+
+#[test]
+fn test_manifest_config() {
+    let mut config = Config::default();
+    assert!(!config.platforms.web.plugins.manifest.enabled);
+
+    config.platforms.web.plugins.manifest.enabled = true;
+    assert!(config.validate().is_ok());
+
+    config.platforms.web.plugins.manifest.file_name = " ".to_string();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_compress_config_validation() {
+    let mut config = Config::default();
+    assert!(!config.platforms.web.plugins.compress.enabled);
+
+    config.platforms.web.plugins.compress.enabled = true;
+    assert!(config.validate().is_ok());
+
+    config.platforms.web.plugins.compress.brotli_level = 12;
+    assert!(config.validate().is_err());
+
+    config.platforms.web.plugins.compress.brotli_level = 11;
+    config.platforms.web.plugins.compress.zstd_level = 0;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_hash_config_validation() {
+    let yaml = r#"
+platforms:
+  web:
+    plugins:
+      hash:
+        algorithm: blake3
+        encoding: base64url
+        hash_length: 43
+"#;
+    let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+    let hash = &config.platforms.web.plugins.hash;
+    assert_eq!(hash.algorithm, crate::HashAlgorithm::Blake3);
+    assert_eq!(hash.encoding, crate::HashEncoding::Base64url);
+    assert!(config.validate().is_ok());
+
+    // 128-bit digests are shorter
+    config.platforms.web.plugins.hash.algorithm = crate::HashAlgorithm::Md5;
+    assert!(config.validate().is_err());
+
+    config.platforms.web.plugins.hash.encoding = crate::HashEncoding::Hex;
+    config.platforms.web.plugins.hash.hash_length = 32;
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_csp_config_validation() {
+    let mut config = Config::default();
+    let csp = &mut config.platforms.web.plugins.inject.csp;
+    assert!(!csp.enabled);
+    assert_eq!(csp.mode, crate::CspMode::Meta);
+    assert!(csp.directives.contains_key("script-src"));
+
+    csp.enabled = true;
+    csp.mode = crate::CspMode::Headers;
+    csp.headers_file = String::new();
+    assert!(config.validate().is_err());
+
+    config.platforms.web.plugins.inject.csp.headers_file = "_headers".to_string();
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_chunk_loader_config_validation() {
+    let yaml = r#"
+platforms:
+  web:
+    plugins:
+      inject:
+        loader:
+          retries: 5
+          fallback_origins: ["https://cdn2.example.com/app/"]
+"#;
+    let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+    let loader = &config.platforms.web.plugins.inject.loader;
+    assert_eq!(loader.retries, 5);
+    assert_eq!(loader.concurrency, 6);
+    assert!(loader.verify);
+    assert!(config.validate().is_ok());
+
+    let loader = &mut config.platforms.web.plugins.inject.loader;
+    loader.fallback_origins.push("cdn3.example.com".to_string());
+    assert!(config.validate().is_err());
+
+    let loader = &mut config.platforms.web.plugins.inject.loader;
+    loader.fallback_origins.pop();
+    loader.max_retry_delay_ms = 100;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_script_execution_config() {
+    let yaml = r#"
+platforms:
+  web:
+    plugins:
+      inject:
+        loader:
+          execution: blob
+          trusted_types_policy: chrysalis
+"#;
+    let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+    let loader = &config.platforms.web.plugins.inject.loader;
+    assert_eq!(loader.execution, ScriptExecution::Blob);
+    assert_eq!(loader.trusted_types_policy.as_deref(), Some("chrysalis"));
+    assert!(loader.source_url);
+    assert!(config.validate().is_ok());
+
+    config
+        .platforms
+        .web
+        .plugins
+        .inject
+        .loader
+        .trusted_types_policy = Some("my policy".to_string());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_external_plugin_config() {
+    let yaml = r#"
+platforms:
+  web:
+    pipeline:
+      - minify
+      - name: banner
+        options:
+          text: "hello"
+    plugins:
+      external:
+        banner:
+          command: node
+          args: ["tools/banner.js"]
+          options:
+            position: top
+"#;
+    let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+    assert!(config.validate().is_ok());
+
+    let web = &config.platforms.web;
+    let step = web.plugins.with_step(&web.pipeline[1]).unwrap();
+    let banner = &step.external["banner"];
+    assert_eq!(banner.timeout_secs, 300);
+    assert_eq!(banner.options["text"], "hello");
+    assert_eq!(banner.options["position"], "top");
+
+    let plugins = &mut config.platforms.web.plugins;
+    plugins.external.get_mut("banner").unwrap().command = String::new();
+    assert!(config.validate().is_err());
+
+    let plugins = &mut config.platforms.web.plugins;
+    let mut banner = plugins.external.remove("banner").unwrap();
+    banner.command = "node".to_string();
+    plugins.external.insert("hash".to_string(), banner);
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_resource_hints_config() {
+    let config = Config::default();
+    let hints = &config.platforms.web.plugins.inject.hints;
+    assert!(hints.enabled);
+    assert_eq!(hints.rules[0].pattern, "flutter_bootstrap.js");
+    assert_eq!(hints.rules[0].priority, FetchPriority::High);
+    // Fonts are opt-in
+    assert!(!hints.rules.iter().any(|r| r.pattern.starts_with("assets")));
+
+    let yaml = r#"
+platforms:
+  web:
+    plugins:
+      inject:
+        hints:
+          rules:
+            - pattern: "canvaskit/**"
+              rel: prefetch
+            - pattern: "*.mjs"
+              rel: modulepreload
+              priority: low
+"#;
+    let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+    let rules = &config.platforms.web.plugins.inject.hints.rules;
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].rel, HintRel::Prefetch);
+    assert_eq!(rules[0].priority, FetchPriority::Auto);
+    assert_eq!(rules[1].rel.as_str(), "modulepreload");
+    assert!(config.validate().is_ok());
+
+    config.platforms.web.plugins.inject.hints.rules[0].pattern = " ".to_string();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_inject_position_config() {
+    let config = Config::default();
+    let inject = &config.platforms.web.plugins.inject;
+    assert_eq!(inject.position, InjectPosition::HeadEnd);
+    assert!(inject.selector.is_none());
+
+    let yaml = r#"
+platforms:
+  web:
+    plugins:
+      inject:
+        position: body-start
+"#;
+    let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(
+        config.platforms.web.plugins.inject.position,
+        InjectPosition::BodyStart
+    );
+    assert!(config.validate().is_ok());
+
+    // `before` needs a selector
+    config.platforms.web.plugins.inject.position = InjectPosition::Before;
+    assert!(config.validate().is_err());
+    config.platforms.web.plugins.inject.selector = Some("#app".to_string());
+    assert!(config.validate().is_ok());
+}
@@ -1,6 +1,6 @@
 //! Web platform configuration.
 
-use crate::{FlutterConfig, PluginsConfig, Result};
+use crate::{FlutterConfig, PipelineStep, PluginsConfig, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -23,6 +23,99 @@ pub struct WebConfig {
 
     /// Plugins configuration for web.
     pub plugins: PluginsConfig,
+
+    /// Post-processing steps, in order. Empty derives the order from the
+    /// enabled plugins.
+    pub pipeline: Vec<PipelineStep>,
+
+    /// Bundle size report configuration.
+    pub report: ReportConfig,
+
+    /// Size budgets checked after post-processing.
+    pub budgets: BudgetsConfig,
+}
+
+/// Bundle size report configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportConfig {
+    /// Whether to write an HTML bundle size report after the build.
+    pub enabled: bool,
+
+    /// Report path (relative to project root). Kept out of the output
+    /// directory so it is not deployed.
+    pub path: PathBuf,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: PathBuf::from("chrysalis-report.html"),
+        }
+    }
+}
+
+/// Size budgets checked against the final output.
+///
+/// Sizes are in kilobytes. Chunks count toward the file they were split
+/// from. A build that breaks any budget fails.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetsConfig {
+    /// Per-file limits.
+    pub files: Vec<FileBudget>,
+
+    /// Files downloaded on first load (glob patterns on original paths).
+    pub initial: Vec<String>,
+
+    /// Maximum transfer size of the initial download in kilobytes.
+    pub max_initial_kb: Option<u64>,
+
+    /// Maximum number of chunks.
+    pub max_chunks: Option<usize>,
+
+    /// Maximum total output size in kilobytes.
+    pub max_total_kb: Option<u64>,
+}
+
+/// Size limit for the files matching a glob pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileBudget {
+    /// Glob pattern, matched against original and final paths.
+    pub pattern: String,
+
+    /// Maximum combined size of the matching files in kilobytes.
+    pub max_size_kb: u64,
+}
+
+impl Default for BudgetsConfig {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            initial: vec![
+                "index.html".to_string(),
+                "flutter.js".to_string(),
+                "flutter_bootstrap.js".to_string(),
+                "main.dart.js".to_string(),
+                "main.dart.mjs".to_string(),
+                "main.dart.wasm".to_string(),
+            ],
+            max_initial_kb: None,
+            max_chunks: None,
+            max_total_kb: None,
+        }
+    }
+}
+
+impl BudgetsConfig {
+    /// Whether any budget is configured.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+            && self.max_initial_kb.is_none()
+            && self.max_chunks.is_none()
+            && self.max_total_kb.is_none()
+    }
 }
 
 impl Default for WebConfig {
@@ -33,6 +126,9 @@ impl Default for WebConfig {
             exclude_patterns: vec!["*.map".to_string(), "*.txt".to_string()],
             flutter: FlutterConfig::default(),
             plugins: PluginsConfig::default(),
+            pipeline: Vec::new(),
+            report: ReportConfig::default(),
+            budgets: BudgetsConfig::default(),
         }
     }
 }
@@ -46,9 +142,36 @@ impl WebConfig {
 
         self.flutter.validate()?;
         self.plugins.validate()?;
+        for step in &self.pipeline {
+            self.plugins.with_step(step)?.validate()?;
+        }
+
+        if self.report.enabled && self.report.path.as_os_str().is_empty() {
+            return Err(crate::ConfigError::InvalidValue {
+                field: "platforms.web.report.path".to_string(),
+                reason: "report path cannot be empty".to_string(),
+            });
+        }
+
+        if self.budgets.files.iter().any(|b| b.pattern.is_empty()) {
+            return Err(crate::ConfigError::InvalidValue {
+                field: "platforms.web.budgets.files".to_string(),
+                reason: "budget pattern cannot be empty".to_string(),
+            });
+        }
+
         Ok(())
     }
 
+    /// Post-processing steps, in order.
+    pub fn pipeline_steps(&self) -> Vec<PipelineStep> {
+        if self.pipeline.is_empty() {
+            self.plugins.default_pipeline()
+        } else {
+            self.pipeline.clone()
+        }
+    }
+
     /// Get the Flutter build output directory (where Flutter writes its output).
     /// This is always "build/{platform}" by Flutter convention.
     pub fn flutter_build_dir(&self) -> PathBuf {
@@ -85,4 +208,76 @@ mod tests {
         // Disabled config should skip validation
         assert!(config.validate().is_ok());
     }
+
+    #[test]
+    fn test_report_config_validation() {
+        let mut config = WebConfig::default();
+        assert!(!config.report.enabled);
+
+        config.report.enabled = true;
+        assert!(config.validate().is_ok());
+
+        config.report.path = PathBuf::new();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_budgets_config() {
+        let mut config = WebConfig::default();
+        assert!(config.budgets.is_empty());
+
+        let yaml = r#"
+files:
+  - pattern: "main.dart*.js"
+    max_size_kb: 3072
+max_chunks: 10
+"#;
+        config.budgets = serde_yaml::from_str(yaml).unwrap();
+        assert!(!config.budgets.is_empty());
+        assert_eq!(config.budgets.files[0].max_size_kb, 3072);
+        assert_eq!(config.budgets.max_chunks, Some(10));
+        assert!(config.budgets.initial.contains(&"main.dart.js".to_string()));
+        assert!(config.validate().is_ok());
+
+        config.budgets.files[0].pattern = String::new();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_pipeline_config() {
+        let mut config = WebConfig::default();
+        let names: Vec<_> = config
+            .pipeline_steps()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(
+            names,
+            ["minify", "chunk", "hash", "inject", "service_worker"]
+        );
+
+        let yaml = r#"
+- minify
+- hash
+- name: compress
+  options:
+    gzip: false
+"#;
+        config.pipeline = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.pipeline_steps().len(), 3);
+        assert!(config.validate().is_ok());
+
+        let compress = config.plugins.with_step(&config.pipeline[2]).unwrap();
+        assert!(compress.compress.enabled);
+        assert!(!compress.compress.gzip);
+        assert!(compress.compress.brotli);
+
+        config.pipeline[2]
+            .options
+            .insert("brotli_level".into(), 12.into());
+        assert!(config.validate().is_err());
+
+        config.pipeline = vec![PipelineStep::new("unknown")];
+        assert!(config.validate().is_err());
+    }
 }
@@ -0,0 +1,294 @@
+//! Persistent content-addressed cache.
+
+use crate::{BuildError, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Counter for unique temporary file names across worker threads.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Cache usage summary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of entries.
+    pub entries: usize,
+
+    /// Total size in bytes.
+    pub size: u64,
+}
+
+/// On-disk cache of build results, keyed by the hash of their inputs.
+///
+/// Entries live at `{dir}/{key[..2]}/{key[2..]}`. Reads refresh an entry's
+/// modification time, so pruning removes the least recently used entries.
+/// Only files in that layout are counted or removed, so a misconfigured
+/// directory never loses anything else.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Create a cache rooted at `dir` (created on first write).
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Cache directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Compute a cache key from a namespace (e.g. minifier identity), a
+    /// fingerprint of the settings that affect the output, and the input bytes.
+    pub fn key(namespace: &str, fingerprint: &str, content: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(namespace.as_bytes());
+        hasher.update([0]);
+        hasher.update(fingerprint.as_bytes());
+        hasher.update([0]);
+        hasher.update(content);
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Path of the entry for a key.
+    fn entry_path(&self, key: &str) -> PathBuf {
+        let split = key.len().min(2);
+        self.dir.join(&key[..split]).join(&key[split..])
+    }
+
+    /// Get a cached entry.
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let path = self.entry_path(key);
+        let content = std::fs::read(&path).ok()?;
+
+        // Mark as recently used (best effort)
+        if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        Some(content)
+    }
+
+    /// Store an entry.
+    ///
+    /// Entries are written to a temporary file and renamed into place, so
+    /// concurrent writers and interrupted builds never leave partial entries.
+    pub fn put(&self, key: &str, content: &[u8]) -> Result<()> {
+        let path = self.entry_path(key);
+        let parent = path.parent().unwrap_or(&self.dir);
+        std::fs::create_dir_all(parent).map_err(|source| BuildError::Io {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+
+        let temp = parent.join(format!(
+            ".{}.{}.{}.tmp",
+            key,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        crate::write_file_content(&temp, content)?;
+        std::fs::rename(&temp, &path).map_err(|source| {
+            let _ = std::fs::remove_file(&temp);
+            BuildError::Io {
+                path: path.clone(),
+                source,
+            }
+        })
+    }
+
+    /// Whether a path relative to the cache directory is an entry: two hex
+    /// digits, then the remaining 62 (temporary files start with `.`).
+    fn is_entry(relative: &Path) -> bool {
+        let is_hex = |name: Option<&std::ffi::OsStr>, len: usize| {
+            name.and_then(|n| n.to_str()).is_some_and(|n| {
+                n.len() == len && n.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+            })
+        };
+        let mut components = relative.iter();
+        is_hex(components.next(), 2) && is_hex(components.next(), 62) && components.next().is_none()
+    }
+
+    /// List entries with their size and last use time.
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        WalkDir::new(&self.dir)
+            .min_depth(2)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| e.path().strip_prefix(&self.dir).is_ok_and(Self::is_entry))
+            .filter_map(|e| {
+                let metadata = e.metadata().ok()?;
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some((e.into_path(), metadata.len(), modified))
+            })
+            .collect()
+    }
+
+    /// Get the number of entries and total size.
+    pub fn stats(&self) -> CacheStats {
+        self.entries()
+            .iter()
+            .fold(CacheStats::default(), |stats, (_, size, _)| CacheStats {
+                entries: stats.entries + 1,
+                size: stats.size + size,
+            })
+    }
+
+    /// Remove an entry, and its shard directory once empty.
+    fn remove_entry(&self, path: &Path) -> Result<()> {
+        std::fs::remove_file(path).map_err(|source| BuildError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        // Fails while other entries or temporary files remain
+        if let Some(shard) = path.parent() {
+            let _ = std::fs::remove_dir(shard);
+        }
+        Ok(())
+    }
+
+    /// Remove least recently used entries until the cache fits in `max_size` bytes.
+    ///
+    /// Returns what was removed.
+    pub fn prune(&self, max_size: u64) -> Result<CacheStats> {
+        let mut entries = self.entries();
+        let mut size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+
+        // Oldest first
+        entries.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+
+        let mut removed = CacheStats::default();
+        for (path, entry_size, _) in entries {
+            if size <= max_size {
+                break;
+            }
+
+            self.remove_entry(&path)?;
+            size -= entry_size;
+            removed.entries += 1;
+            removed.size += entry_size;
+        }
+
+        Ok(removed)
+    }
+
+    /// Remove every entry, and the cache directory once empty.
+    ///
+    /// Returns what was removed.
+    pub fn clear(&self) -> Result<CacheStats> {
+        let mut removed = CacheStats::default();
+        for (path, size, _) in self.entries() {
+            self.remove_entry(&path)?;
+            removed.entries += 1;
+            removed.size += size;
+        }
+
+        let _ = std::fs::remove_dir(&self.dir);
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let cache = Cache::new(temp.path().join("cache"));
+
+        let key = Cache::key("js", "{}", b"var a = 1;");
+        assert_eq!(key.len(), 64);
+        assert_ne!(key, Cache::key("css", "{}", b"var a = 1;"));
+        assert_ne!(key, Cache::key("js", "{\"x\":1}", b"var a = 1;"));
+
+        assert!(cache.get(&key).is_none());
+        cache.put(&key, b"var a=1").unwrap();
+        assert_eq!(cache.get(&key).unwrap(), b"var a=1");
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.size, 7);
+    }
+
+    #[test]
+    fn test_cache_prune() {
+        let temp = TempDir::new().unwrap();
+        let cache = Cache::new(temp.path());
+
+        for i in 0..4u8 {
+            let key = Cache::key("js", "", &[i]);
+            cache.put(&key, &[i; 100]).unwrap();
+        }
+        assert_eq!(cache.stats().size, 400);
+
+        let removed = cache.prune(250).unwrap();
+        assert_eq!(removed.entries, 2);
+        assert_eq!(cache.stats().size, 200);
+
+        let removed = cache.clear().unwrap();
+        assert_eq!(removed.entries, 2);
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_cache_only_touches_entries() {
+        let temp = TempDir::new().unwrap();
+        let cache = Cache::new(temp.path());
+        let key = Cache::key("js", "", b"a");
+        cache.put(&key, b"cached").unwrap();
+
+        // Files outside the entry layout, as in a project directory
+        let shard = temp.path().join(&key[..2]);
+        let others = [
+            temp.path().join("main.dart"),
+            temp.path().join("lib/app.dart"),
+            temp.path().join("ab/cd"),
+            shard.join(format!(".{}.1.0.tmp", key)),
+            shard.join(key[2..].to_uppercase()),
+        ];
+        for path in &others {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"keep").unwrap();
+        }
+
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                entries: 1,
+                size: 6
+            }
+        );
+        assert_eq!(cache.prune(0).unwrap().entries, 1);
+        cache.put(&key, b"cached").unwrap();
+        assert_eq!(cache.clear().unwrap().entries, 1);
+
+        assert!(cache.get(&key).is_none());
+        for path in &others {
+            assert!(path.is_file(), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn test_cache_clear_removes_empty_directories() {
+        let temp = TempDir::new().unwrap();
+        let cache = Cache::new(temp.path().join("cache"));
+        for i in 0..3u8 {
+            cache.put(&Cache::key("js", "", &[i]), &[i]).unwrap();
+        }
+
+        assert_eq!(cache.clear().unwrap().entries, 3);
+        assert!(!cache.dir().exists());
+        assert_eq!(cache.clear().unwrap(), CacheStats::default());
+    }
+}
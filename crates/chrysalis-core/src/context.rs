@@ -1,8 +1,9 @@
 //! Build context - shared state across all plugins.
 
-use crate::{BuildError, BuildStats, FileInfo, Result, Scanner};
+use crate::{BuildError, BuildStats, FileInfo, Result, Scanner, WorkerPool};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tracing::{debug, info};
 
 /// Build context maintains state across all build plugins.
@@ -26,8 +27,17 @@ pub struct BuildContext {
     /// File dependencies: file -> set of dependencies.
     dependencies: HashMap<PathBuf, HashSet<PathBuf>>,
 
+    /// Files written by plugins rather than by the Flutter build.
+    generated: HashSet<PathBuf>,
+
     /// Build statistics.
     stats: BuildStats,
+
+    /// Number of parallel workers (0 = number of CPUs).
+    parallel_jobs: usize,
+
+    /// Worker pool for per-file work, started on first use.
+    workers: OnceLock<WorkerPool>,
 }
 
 impl BuildContext {
@@ -46,10 +56,29 @@ impl BuildContext {
             file_mapping: HashMap::new(),
             chunks: HashMap::new(),
             dependencies: HashMap::new(),
+            generated: HashSet::new(),
             stats: BuildStats::new(),
+            parallel_jobs: 0,
+            workers: OnceLock::new(),
         })
     }
 
+    /// Set the number of parallel workers (0 = number of CPUs).
+    pub fn with_parallel_jobs(mut self, jobs: usize) -> Self {
+        self.parallel_jobs = jobs;
+        self.workers = OnceLock::new();
+        self
+    }
+
+    /// Get the worker pool, starting it on first use.
+    pub fn workers(&self) -> Result<&WorkerPool> {
+        if let Some(workers) = self.workers.get() {
+            return Ok(workers);
+        }
+        let workers = WorkerPool::new(self.parallel_jobs)?;
+        Ok(self.workers.get_or_init(|| workers))
+    }
+
     /// Scan the build directory and index all files.
     pub fn scan(&mut self) -> Result<()> {
         info!("Scanning build directory: {}", self.build_dir.display());
@@ -105,9 +134,28 @@ impl BuildContext {
         Ok(())
     }
 
+    /// Add a file written by a plugin (e.g. a runtime it injects).
+    pub fn add_generated_file(&mut self, file: FileInfo) -> Result<()> {
+        let path = file.absolute.clone();
+        self.add_file(file)?;
+        self.generated.insert(path);
+        Ok(())
+    }
+
+    /// Whether a file was written by a plugin.
+    pub fn is_generated<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.generated.contains(path.as_ref())
+    }
+
     /// Remove a file from the context.
     pub fn remove_file<P: AsRef<Path>>(&mut self, path: P) -> Option<FileInfo> {
-        self.files.remove(path.as_ref())
+        let path = path.as_ref();
+        self.generated.remove(path);
+        self.dependencies.remove(path);
+        for dependencies in self.dependencies.values_mut() {
+            dependencies.remove(path);
+        }
+        self.files.remove(path)
     }
 
     /// Rename a file and update all mappings.
@@ -120,7 +168,13 @@ impl BuildContext {
             .remove(old_path)
             .ok_or_else(|| BuildError::FileNotFound(old_path.to_path_buf()))?;
 
-        // Rename physical file
+        // Rename physical file (possibly into a new directory)
+        if let Some(parent) = new_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|source| BuildError::Io {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
         std::fs::rename(&file.absolute, new_path).map_err(|source| BuildError::Io {
             path: old_path.to_path_buf(),
             source,
@@ -143,6 +197,9 @@ impl BuildContext {
         // Update mappings
         self.files.insert(new_path.to_path_buf(), file);
         self.file_mapping.insert(old_relative, new_relative);
+        if self.generated.remove(old_path) {
+            self.generated.insert(new_path.to_path_buf());
+        }
 
         // Update chunks mapping if this file is a parent of chunks
         if let Some(chunk_paths) = self.chunks.remove(old_path) {
@@ -158,10 +215,73 @@ impl BuildContext {
             }
         }
 
+        // Update the dependency graph on both ends
+        if let Some(dependencies) = self.dependencies.remove(old_path) {
+            self.dependencies
+                .insert(new_path.to_path_buf(), dependencies);
+        }
+        for dependencies in self.dependencies.values_mut() {
+            if dependencies.remove(old_path) {
+                dependencies.insert(new_path.to_path_buf());
+            }
+        }
+
         debug!("Renamed: {} -> {}", old_path.display(), new_path.display());
         Ok(())
     }
 
+    /// Drop a file whose content is identical to `existing`.
+    ///
+    /// The duplicate is deleted and its name maps to the existing file, so
+    /// references to it resolve there.
+    pub fn merge_file<P: AsRef<Path>>(&mut self, duplicate: P, existing: P) -> Result<()> {
+        let duplicate = duplicate.as_ref();
+        let existing = existing.as_ref();
+
+        let existing_relative = self
+            .files
+            .get(existing)
+            .map(|f| f.relative.clone())
+            .ok_or_else(|| BuildError::FileNotFound(existing.to_path_buf()))?;
+        let file = self
+            .files
+            .get(duplicate)
+            .ok_or_else(|| BuildError::FileNotFound(duplicate.to_path_buf()))?;
+
+        std::fs::remove_file(duplicate).map_err(|source| BuildError::Io {
+            path: duplicate.to_path_buf(),
+            source,
+        })?;
+        self.file_mapping
+            .insert(file.relative.clone(), existing_relative);
+        self.files.remove(duplicate);
+        self.generated.remove(duplicate);
+
+        for chunk_paths in self.chunks.values_mut() {
+            for chunk_path in chunk_paths.iter_mut() {
+                if chunk_path == duplicate {
+                    *chunk_path = existing.to_path_buf();
+                }
+            }
+        }
+
+        // Dependents of the duplicate now depend on the existing file
+        if let Some(dependencies) = self.dependencies.remove(duplicate) {
+            self.dependencies
+                .entry(existing.to_path_buf())
+                .or_default()
+                .extend(dependencies);
+        }
+        for dependencies in self.dependencies.values_mut() {
+            if dependencies.remove(duplicate) {
+                dependencies.insert(existing.to_path_buf());
+            }
+        }
+
+        debug!("Merged: {} -> {}", duplicate.display(), existing.display());
+        Ok(())
+    }
+
     /// Add chunk information.
     pub fn add_chunk_info<P: AsRef<Path>>(&mut self, parent: P, chunks: Vec<PathBuf>) {
         let num_chunks = chunks.len();
@@ -187,6 +307,11 @@ impl BuildContext {
         self.dependencies.get(file.as_ref())
     }
 
+    /// Get the whole dependency graph: file -> files it references.
+    pub fn dependencies(&self) -> &HashMap<PathBuf, HashSet<PathBuf>> {
+        &self.dependencies
+    }
+
     /// Get build statistics.
     pub fn stats(&self) -> &BuildStats {
         &self.stats
@@ -212,3 +337,20 @@ impl BuildContext {
         &self.chunks
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_context_starts_pool_once() {
+        let temp = TempDir::new().unwrap();
+        let ctx = BuildContext::new(temp.path(), vec![])
+            .unwrap()
+            .with_parallel_jobs(3);
+        let workers = ctx.workers().unwrap();
+        assert_eq!(workers.jobs(), 3);
+        assert!(std::ptr::eq(workers, ctx.workers().unwrap()));
+    }
+}
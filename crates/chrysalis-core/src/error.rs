@@ -40,6 +40,10 @@ pub enum BuildError {
     #[error("Invalid glob pattern: {0}")]
     GlobPattern(String),
 
+    /// Invalid file name template.
+    #[error("Invalid file name template '{template}': {reason}")]
+    InvalidTemplate { template: String, reason: String },
+
     /// Other errors.
     #[error(transparent)]
     Other(#[from] anyhow::Error),
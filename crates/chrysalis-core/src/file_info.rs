@@ -1,5 +1,6 @@
 //! File information structure.
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 /// Information about a file in the build.
@@ -17,6 +18,12 @@ pub struct FileInfo {
     /// File size in bytes.
     pub size: u64,
 
+    /// Size when the file was scanned or created, before any processing.
+    pub original_size: u64,
+
+    /// Size right after minification, if the file was minified.
+    pub minified_size: Option<u64>,
+
     /// Parent directory (relative to build directory).
     pub dir: PathBuf,
 
@@ -28,6 +35,9 @@ pub struct FileInfo {
 
     /// Whether the file has been modified.
     pub modified: bool,
+
+    /// Sizes of precompressed variants, keyed by encoding (e.g., "br", "gzip").
+    pub compressed_sizes: BTreeMap<String, u64>,
 }
 
 impl FileInfo {
@@ -51,10 +61,13 @@ impl FileInfo {
             relative,
             name,
             size,
+            original_size: size,
+            minified_size: None,
             dir,
             ext,
             content: None,
             modified: false,
+            compressed_sizes: BTreeMap::new(),
         }
     }
 
@@ -105,6 +118,14 @@ impl FileInfo {
         self.modified = true;
     }
 
+    /// Get the smallest size this file can be transferred in.
+    pub fn transfer_size(&self) -> u64 {
+        self.compressed_sizes
+            .values()
+            .copied()
+            .fold(self.size, u64::min)
+    }
+
     /// Clear content from memory.
     pub fn clear_content(&mut self) {
         self.content = None;
@@ -134,4 +155,14 @@ mod tests {
         assert!(css_file.is_css());
         assert!(!css_file.is_js());
     }
+
+    #[test]
+    fn test_transfer_size() {
+        let mut file = FileInfo::new("build/web/main.dart.js", "main.dart.js", 1000);
+        assert_eq!(file.transfer_size(), 1000);
+
+        file.compressed_sizes.insert("gzip".to_string(), 300);
+        file.compressed_sizes.insert("br".to_string(), 250);
+        assert_eq!(file.transfer_size(), 250);
+    }
 }
@@ -1,120 +1,548 @@
 //! Unified file naming conventions.
 
-use std::path::Path;
+use crate::{BuildError, Result};
+use chrysalis_config::{ChunkConfig, HashAlgorithm, HashConfig, HashEncoding};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
 
-/// File naming utilities.
-pub struct FileNaming;
+/// How content hashes are computed and written in file names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashScheme {
+    /// Digest algorithm.
+    pub algorithm: HashAlgorithm,
 
-impl FileNaming {
-    /// Add hash to filename: `filename.ext` -> `filename.{hash}.ext`
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use chrysalis_core::FileNaming;
-    ///
-    /// let name = FileNaming::add_hash("main.dart.js", "abc12345");
-    /// assert_eq!(name, "main.dart.abc12345.js");
-    /// ```
-    pub fn add_hash(filename: &str, hash: &str) -> String {
-        let path = Path::new(filename);
-        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
-        let ext = path
-            .extension()
-            .map(|e| format!(".{}", e.to_string_lossy()))
-            .unwrap_or_default();
+    /// Encoding of the digest.
+    pub encoding: HashEncoding,
+
+    /// Number of encoded characters kept.
+    pub length: usize,
+}
+
+impl Default for HashScheme {
+    fn default() -> Self {
+        Self::new(HashAlgorithm::Md5, HashEncoding::Hex, 8)
+    }
+}
+
+impl From<&HashConfig> for HashScheme {
+    fn from(config: &HashConfig) -> Self {
+        Self::new(config.algorithm, config.encoding, config.hash_length)
+    }
+}
+
+impl HashScheme {
+    /// Create a hash scheme.
+    pub fn new(algorithm: HashAlgorithm, encoding: HashEncoding, length: usize) -> Self {
+        Self {
+            algorithm,
+            encoding,
+            length,
+        }
+    }
 
-        format!("{}.{}{}", stem, hash, ext)
+    /// Encoded digest of `content`, untruncated.
+    pub fn full_hash(&self, content: &[u8]) -> String {
+        let digest = match self.algorithm {
+            HashAlgorithm::Md5 => md5::compute(content).0.to_vec(),
+            HashAlgorithm::Sha256 => Sha256::digest(content).to_vec(),
+            HashAlgorithm::Xxh3 => xxhash_rust::xxh3::xxh3_128(content).to_be_bytes().to_vec(),
+            HashAlgorithm::Blake3 => blake3::hash(content).as_bytes().to_vec(),
+        };
+
+        match self.encoding {
+            HashEncoding::Hex => data_encoding::HEXLOWER.encode(&digest),
+            HashEncoding::Base32 => data_encoding::BASE32_NOPAD
+                .encode(&digest)
+                .to_ascii_lowercase(),
+            HashEncoding::Base64url => data_encoding::BASE64URL_NOPAD.encode(&digest),
+        }
     }
 
-    /// Add chunk suffix to hashed filename: `filename.{hash}.ext` -> `filename.{hash}.chunk{N}.ext`
+    /// Content hash of `content`: the encoded digest, truncated to the
+    /// configured length.
     ///
     /// # Examples
     ///
     /// ```
-    /// use chrysalis_core::FileNaming;
+    /// use chrysalis_config::{HashAlgorithm, HashEncoding};
+    /// use chrysalis_core::HashScheme;
     ///
-    /// let name = FileNaming::add_chunk_suffix("main.dart.abc12345.js", 0);
-    /// assert_eq!(name, "main.dart.abc12345.chunk0.js");
+    /// let scheme = HashScheme::new(HashAlgorithm::Sha256, HashEncoding::Base32, 10);
+    /// assert_eq!(scheme.hash(b"hello world").len(), 10);
     /// ```
-    pub fn add_chunk_suffix(hashed_filename: &str, chunk_index: usize) -> String {
-        let path = Path::new(hashed_filename);
-        let ext = path
+    pub fn hash(&self, content: &[u8]) -> String {
+        let mut hash = self.full_hash(content);
+        hash.truncate(self.length);
+        hash
+    }
+
+    /// Check if `s` could be a hash of this scheme: the configured length,
+    /// in the encoding's alphabet.
+    pub fn matches(&self, s: &str) -> bool {
+        s.len() == self.length
+            && s.bytes().all(|b| match self.encoding {
+                HashEncoding::Hex => b.is_ascii_digit() || (b'a'..=b'f').contains(&b),
+                HashEncoding::Base32 => b.is_ascii_lowercase() || (b'2'..=b'7').contains(&b),
+                HashEncoding::Base64url => b.is_ascii_alphanumeric() || b == b'-' || b == b'_',
+            })
+    }
+}
+
+/// A literal or placeholder in a file name template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    /// `[dir]/`: the directory and its separator, or nothing at the root.
+    DirPrefix,
+    Dir,
+    Name,
+    Ext,
+    Hash,
+    Index,
+}
+
+/// Values substituted into a template, or captured when matching one.
+#[derive(Debug, Clone, Copy, Default)]
+struct Values<'a> {
+    dir: &'a str,
+    name: &'a str,
+    ext: &'a str,
+    hash: &'a str,
+    index: &'a str,
+}
+
+/// Output path template, relative to the output directory, e.g.
+/// `assets/[dir]/[name].[hash:10].[ext]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileTemplate {
+    template: String,
+    parts: Vec<Part>,
+    /// Length given as `[hash:N]`.
+    hash_length: Option<usize>,
+}
+
+impl FileTemplate {
+    /// Parse a template for hashed files.
+    fn hashed(template: &str) -> Result<Self> {
+        Self::parse(template, &["dir", "name", "ext", "hash"], "hash")
+    }
+
+    /// Parse a template for chunk files.
+    fn chunk(template: &str) -> Result<Self> {
+        Self::parse(template, &["dir", "name", "ext", "index"], "index")
+    }
+
+    /// Parse a template using only `placeholders`, which must include `required`.
+    fn parse(template: &str, placeholders: &[&str], required: &str) -> Result<Self> {
+        let invalid = |reason: String| BuildError::InvalidTemplate {
+            template: template.to_string(),
+            reason,
+        };
+
+        if template.starts_with('/') || template.split('/').any(|s| s == "..") {
+            return Err(invalid(
+                "must be a relative path inside the output directory".to_string(),
+            ));
+        }
+
+        let mut parts = Vec::new();
+        let mut hash_length = None;
+        let mut rest = template;
+        while let Some(start) = rest.find('[') {
+            let end = rest[start..]
+                .find(']')
+                .map(|end| start + end)
+                .ok_or_else(|| invalid("unclosed '['".to_string()))?;
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+
+            let placeholder = &rest[start + 1..end];
+            let key = match placeholder.split_once(':') {
+                Some(("hash", length)) => {
+                    let length = length
+                        .parse()
+                        .ok()
+                        .filter(|length| *length > 0)
+                        .ok_or_else(|| invalid(format!("invalid hash length '{}'", length)))?;
+                    hash_length = Some(length);
+                    "hash"
+                }
+                _ => placeholder,
+            };
+            let part = match key {
+                _ if !placeholders.contains(&key) => None,
+                "dir" => Some(Part::Dir),
+                "name" => Some(Part::Name),
+                "ext" => Some(Part::Ext),
+                "hash" => Some(Part::Hash),
+                "index" => Some(Part::Index),
+                _ => None,
+            }
+            .ok_or_else(|| invalid(format!("unknown placeholder [{}]", placeholder)))?;
+            if part == Part::Hash && parts.contains(&Part::Hash) {
+                return Err(invalid("[hash] may only appear once".to_string()));
+            }
+            parts.push(part);
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        // `[dir]/` disappears for files at the root
+        let mut normalized = Vec::with_capacity(parts.len());
+        let mut parts = parts.into_iter().peekable();
+        while let Some(part) = parts.next() {
+            if part == Part::Dir
+                && let Some(Part::Literal(next)) = parts.peek_mut()
+                && let Some(after) = next.strip_prefix('/')
+            {
+                *next = after.to_string();
+                let empty = next.is_empty();
+                normalized.push(Part::DirPrefix);
+                if empty {
+                    parts.next();
+                }
+            } else {
+                normalized.push(part);
+            }
+        }
+        let parts = normalized;
+
+        if !parts
+            .iter()
+            .any(|p| matches!((p, required), (Part::Hash, "hash") | (Part::Index, "index")))
+        {
+            return Err(invalid(format!("must contain [{}]", required)));
+        }
+
+        // References in a moved file are rewritten for its new directory
+        // before it is hashed, so the directory cannot depend on the hash
+        if let Some(hash) = parts.iter().position(|p| *p == Part::Hash)
+            && parts[hash..].iter().any(|p| match p {
+                Part::Literal(text) => text.contains('/'),
+                Part::Dir | Part::DirPrefix => true,
+                _ => false,
+            })
+        {
+            return Err(invalid(
+                "[hash] must be in the file name, not in a directory".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            parts,
+            hash_length,
+        })
+    }
+
+    /// Fill in the template for the file at `relative`.
+    fn render(&self, relative: &Path, hash: &str, index: &str) -> PathBuf {
+        let dir = relative
+            .parent()
+            .map(|dir| dir.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let name = relative.file_stem().unwrap_or_default().to_string_lossy();
+        let ext = relative
             .extension()
-            .map(|e| format!(".{}", e.to_string_lossy()))
+            .map(|ext| ext.to_string_lossy())
             .unwrap_or_default();
-        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let values = Values {
+            dir: &dir,
+            name: &name,
+            ext: &ext,
+            hash,
+            index,
+        };
 
-        format!("{}.chunk{}{}", stem, chunk_index, ext)
+        let mut path = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => path.push_str(text),
+                Part::DirPrefix => {
+                    if !values.dir.is_empty() {
+                        path.push_str(values.dir);
+                        path.push('/');
+                    }
+                }
+                Part::Dir => path.push_str(values.dir),
+                Part::Name => path.push_str(values.name),
+                Part::Ext => {
+                    // No extension: drop the dot before it
+                    if values.ext.is_empty() {
+                        if path.ends_with('.') {
+                            path.pop();
+                        }
+                    } else {
+                        path.push_str(values.ext);
+                    }
+                }
+                Part::Hash => path.push_str(values.hash),
+                Part::Index => path.push_str(values.index),
+            }
+        }
+
+        PathBuf::from(path)
     }
 
-    /// Extract hash from hashed filename.
-    ///
-    /// Returns None if filename doesn't contain a hash.
-    pub fn extract_hash(filename: &str) -> Option<String> {
-        let path = Path::new(filename);
-        let stem = path.file_stem()?.to_string_lossy();
+    /// Match a relative URL path against the template.
+    fn capture<'a>(&self, path: &'a str, scheme: &HashScheme) -> Option<Values<'a>> {
+        let mut values = Values::default();
+        Self::match_parts(&self.parts, path, scheme, &mut values).then_some(values)
+    }
 
-        // Hash is the last component before extension (8 chars)
-        let parts: Vec<&str> = stem.split('.').collect();
-        if parts.len() < 2 {
+    /// Match `s` against `parts`, trying every split for variable-length parts.
+    fn match_parts<'a>(
+        parts: &[Part],
+        s: &'a str,
+        scheme: &HashScheme,
+        values: &mut Values<'a>,
+    ) -> bool {
+        let Some((part, rest)) = parts.split_first() else {
+            return s.is_empty();
+        };
+        let is_dir = |dir: &str| dir.split('/').all(|segment| !segment.is_empty());
+
+        match part {
+            Part::Literal(text) => s
+                .strip_prefix(text.as_str())
+                .is_some_and(|s| Self::match_parts(rest, s, scheme, values)),
+            Part::Hash => {
+                let Some(hash) = s.get(..scheme.length).filter(|h| scheme.matches(h)) else {
+                    return false;
+                };
+                values.hash = hash;
+                Self::match_parts(rest, &s[scheme.length..], scheme, values)
+            }
+            _ => (0..=s.len())
+                .filter(|end| s.is_char_boundary(*end))
+                .any(|end| {
+                    let (value, remainder) = s.split_at(end);
+                    let slot = match part {
+                        Part::DirPrefix if value.is_empty() => &mut values.dir,
+                        Part::DirPrefix => match value.strip_suffix('/') {
+                            Some(dir) if is_dir(dir) => {
+                                values.dir = dir;
+                                return Self::match_parts(rest, remainder, scheme, values);
+                            }
+                            _ => return false,
+                        },
+                        Part::Dir if value.is_empty() || is_dir(value) => &mut values.dir,
+                        Part::Name if !value.is_empty() && !value.contains('/') => &mut values.name,
+                        Part::Ext if !value.is_empty() && !value.contains(['/', '.']) => {
+                            &mut values.ext
+                        }
+                        Part::Index
+                            if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) =>
+                        {
+                            &mut values.index
+                        }
+                        _ => return false,
+                    };
+                    *slot = value;
+                    Self::match_parts(rest, remainder, scheme, values)
+                }),
+        }
+    }
+
+    /// The path a file had before the template renamed it, if the template
+    /// keeps its name.
+    fn original(&self, path: &str, scheme: &HashScheme) -> Option<String> {
+        if !self.parts.contains(&Part::Name) {
             return None;
         }
 
-        let last = parts.last()?;
-        if last.len() == 8 && last.chars().all(|c| c.is_ascii_hexdigit()) {
-            Some(last.to_string())
+        let values = self.capture(path, scheme)?;
+        let name = if values.ext.is_empty() {
+            values.name.to_string()
+        } else {
+            format!("{}.{}", values.name, values.ext)
+        };
+        Some(if values.dir.is_empty() {
+            name
         } else {
-            None
+            format!("{}/{}", values.dir, name)
+        })
+    }
+}
+
+/// File naming utilities.
+///
+/// Output paths of hashed files and chunks follow the templates of
+/// [`HashConfig::file_name`] and [`ChunkConfig::file_name`]. Hashes are
+/// recognised by the length and alphabet of the hash scheme, so a name part
+/// that happens to fit (e.g. `dart` with 4-character base64url hashes) is
+/// taken for a hash; keep hashes long enough to avoid that.
+#[derive(Debug, Clone)]
+pub struct FileNaming {
+    scheme: HashScheme,
+    file_name: FileTemplate,
+    chunk_file_name: FileTemplate,
+}
+
+impl Default for FileNaming {
+    fn default() -> Self {
+        Self::new(HashScheme::default())
+    }
+}
+
+impl FileNaming {
+    /// Create naming utilities for a hash scheme, with the default templates.
+    pub fn new(scheme: HashScheme) -> Self {
+        Self {
+            scheme,
+            file_name: FileTemplate::hashed(&HashConfig::default().file_name)
+                .expect("default hash template is valid"),
+            chunk_file_name: FileTemplate::chunk(&ChunkConfig::default().file_name)
+                .expect("default chunk template is valid"),
         }
     }
 
-    /// Check if filename has hash.
-    pub fn has_hash(filename: &str) -> bool {
-        Self::extract_hash(filename).is_some()
+    /// Create naming utilities from the hash and chunk configuration.
+    pub fn from_config(hash: &HashConfig, chunk: &ChunkConfig) -> Result<Self> {
+        Self::new(HashScheme::from(hash))
+            .with_file_name(&hash.file_name)?
+            .with_chunk_file_name(&chunk.file_name)
     }
 
-    /// Remove hash from filename: `filename.{hash}.ext` -> `filename.ext`
-    pub fn remove_hash(filename: &str) -> String {
-        if let Some(hash) = Self::extract_hash(filename) {
-            filename.replace(&format!(".{}", hash), "")
-        } else {
-            filename.to_string()
+    /// Name hashed files by `template`.
+    ///
+    /// A `[hash:N]` placeholder overrides the length of the hash scheme.
+    pub fn with_file_name(mut self, template: &str) -> Result<Self> {
+        let file_name = FileTemplate::hashed(template)?;
+
+        if let Some(length) = file_name.hash_length {
+            let max_length = self
+                .scheme
+                .encoding
+                .encoded_len(self.scheme.algorithm.digest_len());
+            if length > max_length {
+                return Err(BuildError::InvalidTemplate {
+                    template: template.to_string(),
+                    reason: format!(
+                        "hash length must be between 1 and {} for {} in {}",
+                        max_length,
+                        self.scheme.algorithm.as_str(),
+                        self.scheme.encoding.as_str()
+                    ),
+                });
+            }
+            self.scheme.length = length;
         }
+
+        self.file_name = file_name;
+        Ok(self)
     }
 
-    /// Get original filename from any processed filename.
+    /// Name chunk files by `template`.
+    pub fn with_chunk_file_name(mut self, template: &str) -> Result<Self> {
+        self.chunk_file_name = FileTemplate::chunk(template)?;
+        Ok(self)
+    }
+
+    /// The hash scheme.
+    pub fn scheme(&self) -> &HashScheme {
+        &self.scheme
+    }
+
+    /// Relative output path of the file at `relative` with content hash `hash`.
     ///
-    /// Removes both hash and chunk suffixes.
-    pub fn get_original_name(filename: &str) -> String {
-        let path = Path::new(filename);
-        let ext = path
-            .extension()
-            .map(|e| format!(".{}", e.to_string_lossy()))
-            .unwrap_or_default();
-        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    /// # Examples
+    ///
+    /// ```
+    /// use chrysalis_core::FileNaming;
+    /// use std::path::Path;
+    ///
+    /// let naming = FileNaming::default();
+    /// let path = naming.hashed_path(Path::new("main.dart.js"), "abc12345");
+    /// assert_eq!(path, Path::new("main.dart.abc12345.js"));
+    ///
+    /// let naming = FileNaming::default()
+    ///     .with_file_name("assets/[dir]/[name].[hash].[ext]")
+    ///     .unwrap();
+    /// let path = naming.hashed_path(Path::new("css/style.css"), "abc12345");
+    /// assert_eq!(path, Path::new("assets/css/style.abc12345.css"));
+    /// ```
+    pub fn hashed_path(&self, relative: &Path, hash: &str) -> PathBuf {
+        self.file_name.render(relative, hash, "")
+    }
 
-        // Remove .chunkN suffix if present
-        let stem = if let Some(idx) = stem.rfind(".chunk") {
-            &stem[..idx]
-        } else {
-            &stem
-        };
+    /// Relative directory the file at `relative` moves to when hashed.
+    ///
+    /// Templates never put the hash in a directory, so this is known before
+    /// the content is final.
+    pub fn hashed_dir(&self, relative: &Path) -> PathBuf {
+        self.hashed_path(relative, "")
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
 
-        // Remove hash (8 hex chars) if present
-        let parts: Vec<&str> = stem.split('.').collect();
-        let stem = if parts.len() >= 2 {
-            let last = parts.last().unwrap();
-            if last.len() == 8 && last.chars().all(|c| c.is_ascii_hexdigit()) {
-                parts[..parts.len() - 1].join(".")
-            } else {
-                stem.to_string()
-            }
-        } else {
-            stem.to_string()
+    /// Relative output path of chunk `index` of the file at `relative`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrysalis_core::FileNaming;
+    /// use std::path::Path;
+    ///
+    /// let naming = FileNaming::default();
+    /// let path = naming.chunk_path(Path::new("main.dart.js"), 0);
+    /// assert_eq!(path, Path::new("main.dart.chunk0.js"));
+    /// ```
+    pub fn chunk_path(&self, relative: &Path, index: usize) -> PathBuf {
+        self.chunk_file_name
+            .render(relative, "", &index.to_string())
+    }
+
+    /// Extract hash from a hashed relative path.
+    ///
+    /// Returns None if the path does not match the hash template.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrysalis_core::FileNaming;
+    ///
+    /// let naming = FileNaming::default();
+    /// assert_eq!(naming.extract_hash("main.dart.abc12345.js").as_deref(), Some("abc12345"));
+    /// ```
+    pub fn extract_hash(&self, path: &str) -> Option<String> {
+        self.file_name
+            .capture(path, &self.scheme)
+            .map(|values| values.hash.to_string())
+    }
+
+    /// Check if a relative path has a hash.
+    pub fn has_hash(&self, path: &str) -> bool {
+        self.extract_hash(path).is_some()
+    }
+
+    /// Remove the hash from a relative path: `filename.{hash}.ext` -> `filename.ext`
+    pub fn remove_hash(&self, path: &str) -> String {
+        self.file_name
+            .original(path, &self.scheme)
+            .unwrap_or_else(|| path.to_string())
+    }
+
+    /// Get the original relative path of any processed relative path.
+    ///
+    /// Removes both hash and chunk suffixes. Parts a template leaves out
+    /// (e.g. the name in `static/[hash].[ext]`) cannot be recovered; the
+    /// asset manifest records exact original paths.
+    pub fn get_original_name(&self, path: &str) -> String {
+        let strip_hash =
+            |path: String| self.file_name.original(&path, &self.scheme).unwrap_or(path);
+        let strip_chunk = |path: String| {
+            self.chunk_file_name
+                .original(&path, &self.scheme)
+                .unwrap_or(path)
         };
 
-        format!("{}{}", stem, ext)
+        // Chunks are hashed after they are cut, but both orders are accepted
+        strip_hash(strip_chunk(strip_hash(path.to_string())))
     }
 }
 
@@ -123,66 +551,190 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_add_hash() {
+    fn test_hashed_path() {
+        let naming = FileNaming::default();
         assert_eq!(
-            FileNaming::add_hash("main.dart.js", "abc12345"),
-            "main.dart.abc12345.js"
+            naming.hashed_path(Path::new("main.dart.js"), "abc12345"),
+            Path::new("main.dart.abc12345.js")
         );
         assert_eq!(
-            FileNaming::add_hash("style.css", "def67890"),
-            "style.def67890.css"
+            naming.hashed_path(Path::new("css/style.css"), "def67890"),
+            Path::new("css/style.def67890.css")
         );
     }
 
     #[test]
-    fn test_add_chunk_suffix() {
+    fn test_chunk_path() {
+        let naming = FileNaming::default();
         assert_eq!(
-            FileNaming::add_chunk_suffix("main.dart.abc12345.js", 0),
-            "main.dart.abc12345.chunk0.js"
+            naming.chunk_path(Path::new("main.dart.abc12345.js"), 0),
+            Path::new("main.dart.abc12345.chunk0.js")
         );
         assert_eq!(
-            FileNaming::add_chunk_suffix("main.dart.abc12345.js", 5),
-            "main.dart.abc12345.chunk5.js"
+            naming.chunk_path(Path::new("main.dart.abc12345.js"), 5),
+            Path::new("main.dart.abc12345.chunk5.js")
         );
     }
 
     #[test]
     fn test_extract_hash() {
+        let naming = FileNaming::default();
         assert_eq!(
-            FileNaming::extract_hash("main.dart.abc12345.js"),
+            naming.extract_hash("main.dart.abc12345.js"),
             Some("abc12345".to_string())
         );
-        assert_eq!(FileNaming::extract_hash("main.dart.js"), None);
+        assert_eq!(naming.extract_hash("main.dart.js"), None);
     }
 
     #[test]
     fn test_has_hash() {
-        assert!(FileNaming::has_hash("main.dart.abc12345.js"));
-        assert!(!FileNaming::has_hash("main.dart.js"));
+        let naming = FileNaming::default();
+        assert!(naming.has_hash("main.dart.abc12345.js"));
+        assert!(!naming.has_hash("main.dart.js"));
     }
 
     #[test]
     fn test_remove_hash() {
+        let naming = FileNaming::default();
+        assert_eq!(naming.remove_hash("main.dart.abc12345.js"), "main.dart.js");
+        assert_eq!(naming.remove_hash("main.dart.js"), "main.dart.js");
+    }
+
+    #[test]
+    fn test_get_original_name() {
+        let naming = FileNaming::default();
         assert_eq!(
-            FileNaming::remove_hash("main.dart.abc12345.js"),
+            naming.get_original_name("main.dart.abc12345.chunk0.js"),
             "main.dart.js"
         );
-        assert_eq!(FileNaming::remove_hash("main.dart.js"), "main.dart.js");
+        assert_eq!(
+            naming.get_original_name("main.dart.abc12345.js"),
+            "main.dart.js"
+        );
+        assert_eq!(naming.get_original_name("main.dart.js"), "main.dart.js");
     }
 
     #[test]
-    fn test_get_original_name() {
+    fn test_hash_schemes() {
+        // echo -n "hello world" | md5sum
+        let md5 = HashScheme::new(HashAlgorithm::Md5, HashEncoding::Hex, 32);
+        assert_eq!(md5.hash(b"hello world"), "5eb63bbbe01eeed093cb22bb8f5acdc3");
+
+        for algorithm in [
+            HashAlgorithm::Md5,
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Xxh3,
+            HashAlgorithm::Blake3,
+        ] {
+            for encoding in [
+                HashEncoding::Hex,
+                HashEncoding::Base32,
+                HashEncoding::Base64url,
+            ] {
+                let full = HashScheme::new(algorithm, encoding, usize::MAX);
+                assert_eq!(
+                    full.full_hash(b"").len(),
+                    encoding.encoded_len(algorithm.digest_len())
+                );
+
+                let scheme = HashScheme::new(algorithm, encoding, 12);
+                let hash = scheme.hash(b"hello world");
+                assert!(
+                    scheme.matches(&hash),
+                    "{:?} {:?}: {}",
+                    algorithm,
+                    encoding,
+                    hash
+                );
+                assert_ne!(hash, scheme.hash(b"hello world!"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_naming_follows_scheme() {
+        let scheme = HashScheme::new(HashAlgorithm::Sha256, HashEncoding::Base64url, 12);
+        let naming = FileNaming::new(scheme);
+
         assert_eq!(
-            FileNaming::get_original_name("main.dart.abc12345.chunk0.js"),
+            naming.get_original_name("main.dart.aB3-_x9Qz0Lk.chunk2.js"),
             "main.dart.js"
         );
+        assert!(naming.has_hash("style.aB3-_x9Qz0Lk.css"));
+
+        // An 8-character hex hash is not a hash of this scheme
+        assert!(!naming.has_hash("main.dart.abc12345.js"));
+        assert!(!FileNaming::default().has_hash("main.dart.aB3-_x9Qz0Lk.js"));
+    }
+
+    #[test]
+    fn test_templates() {
+        let naming = FileNaming::default()
+            .with_file_name("assets/[dir]/[name].[hash:10].[ext]")
+            .unwrap()
+            .with_chunk_file_name("chunks/[dir]/[name]-[index].[ext]")
+            .unwrap();
+        assert_eq!(naming.scheme().length, 10);
+
+        let hashed = naming.hashed_path(Path::new("main.dart.js"), "0123456789");
+        assert_eq!(hashed, Path::new("assets/main.dart.0123456789.js"));
         assert_eq!(
-            FileNaming::get_original_name("main.dart.abc12345.js"),
-            "main.dart.js"
+            naming.hashed_dir(Path::new("icons/app.css")),
+            Path::new("assets/icons")
         );
+
+        let chunk = naming.chunk_path(Path::new("web/main.dart.js"), 3);
+        assert_eq!(chunk, Path::new("chunks/web/main.dart-3.js"));
+        let hashed_chunk = naming.hashed_path(&chunk, "abcdef0123");
+        assert_eq!(
+            hashed_chunk,
+            Path::new("assets/chunks/web/main.dart-3.abcdef0123.js")
+        );
+
         assert_eq!(
-            FileNaming::get_original_name("main.dart.js"),
+            naming.get_original_name("assets/main.dart.0123456789.js"),
             "main.dart.js"
         );
+        assert_eq!(
+            naming.get_original_name("assets/chunks/web/main.dart-3.abcdef0123.js"),
+            "web/main.dart.js"
+        );
+        assert_eq!(naming.get_original_name("index.html"), "index.html");
+
+        // Without [name] the original cannot be recovered
+        let naming = FileNaming::default()
+            .with_file_name("static/[hash].[ext]")
+            .unwrap();
+        let hashed = naming.hashed_path(Path::new("sub/style.css"), "abc12345");
+        assert_eq!(hashed, Path::new("static/abc12345.css"));
+        assert!(naming.has_hash("static/abc12345.css"));
+        assert_eq!(
+            naming.get_original_name("static/abc12345.css"),
+            "static/abc12345.css"
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        let naming = FileNaming::default;
+        for template in [
+            "[dir]/[name].[ext]",
+            "/[name].[hash].[ext]",
+            "../[hash].[ext]",
+            "[hash]/[name].[ext]",
+            "[name].[hash].[ext",
+            "[name].[hash:0].[ext]",
+            "[name].[hash:33].[ext]",
+            "[name].[hash].[hash].[ext]",
+            "[name].[index].[hash].[ext]",
+        ] {
+            assert!(naming().with_file_name(template).is_err(), "{}", template);
+        }
+        assert!(naming().with_chunk_file_name("[name].[ext]").is_err());
+        assert!(
+            naming()
+                .with_chunk_file_name("[name].[hash].[ext]")
+                .is_err()
+        );
     }
 }
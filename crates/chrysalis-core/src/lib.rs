@@ -5,7 +5,10 @@
 //! - File scanning and filtering
 //! - File naming conventions
 //! - Hash calculation utilities
+//! - Bounded worker pool for per-file work
+//! - Persistent content-addressed cache
 
+mod cache;
 mod context;
 mod error;
 mod file_info;
@@ -13,14 +16,17 @@ mod file_naming;
 mod scanner;
 mod stats;
 mod utils;
+mod workers;
 
+pub use cache::{Cache, CacheStats};
 pub use context::BuildContext;
 pub use error::{BuildError, Result};
 pub use file_info::FileInfo;
-pub use file_naming::FileNaming;
+pub use file_naming::{FileNaming, HashScheme};
 pub use scanner::Scanner;
 pub use stats::BuildStats;
 pub use utils::{
-    calculate_hash, copy_dir_all, format_bytes, is_flutter_framework_file, read_file_content,
-    write_file_content,
+    calculate_hash, calculate_integrity, copy_dir_all, format_bytes, is_flutter_framework_file,
+    read_file_content, write_file_content,
 };
+pub use workers::WorkerPool;
@@ -1,6 +1,6 @@
 //! Build statistics tracking.
 
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Build statistics.
 #[derive(Debug, Clone, Default)]
@@ -26,11 +26,23 @@ pub struct BuildStats {
     /// Bytes saved by minification.
     pub bytes_saved: u64,
 
+    /// Number of minified files served from the persistent cache.
+    pub cached_files: usize,
+
+    /// Number of files with precompressed variants.
+    pub compressed_files: usize,
+
+    /// Estimated transfer size (smallest precompressed variant of each file).
+    pub transfer_size: u64,
+
     /// Original total size.
     pub original_size: u64,
 
     /// Final total size.
     pub final_size: u64,
+
+    /// Time spent in each plugin, in execution order.
+    pub plugin_timings: Vec<(String, Duration)>,
 }
 
 impl BuildStats {
@@ -65,11 +77,26 @@ impl BuildStats {
         self.bytes_saved += original.saturating_sub(minified);
     }
 
+    /// Record a persistent cache hit.
+    pub fn record_cache_hit(&mut self) {
+        self.cached_files += 1;
+    }
+
     /// Record hashing.
     pub fn record_hash(&mut self) {
         self.hashed_files += 1;
     }
 
+    /// Record precompression of a file.
+    pub fn record_compression(&mut self) {
+        self.compressed_files += 1;
+    }
+
+    /// Record the time a plugin took.
+    pub fn record_plugin_timing(&mut self, plugin: impl Into<String>, elapsed: Duration) {
+        self.plugin_timings.push((plugin.into(), elapsed));
+    }
+
     /// Record chunking.
     pub fn record_chunk(&mut self, num_chunks: usize) {
         self.chunked_files += 1;
@@ -96,6 +123,16 @@ mod tests {
         stats.record_chunk(3);
         assert_eq!(stats.chunked_files, 1);
         assert_eq!(stats.total_chunks, 3);
+
+        stats.record_compression();
+        assert_eq!(stats.compressed_files, 1);
+
+        stats.record_cache_hit();
+        assert_eq!(stats.cached_files, 1);
+
+        stats.record_plugin_timing("minify", Duration::from_millis(5));
+        assert_eq!(stats.plugin_timings.len(), 1);
+        assert_eq!(stats.plugin_timings[0].0, "minify");
     }
 
     #[test]
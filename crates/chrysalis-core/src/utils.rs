@@ -1,13 +1,27 @@
 //! Utility functions for build system.
 
-use crate::{BuildError, Result};
+use crate::{BuildError, HashScheme, Result};
+use chrysalis_config::{HashAlgorithm, HashEncoding, SriAlgorithm};
+use sha2::{Digest, Sha256, Sha384, Sha512};
 use std::path::Path;
 
-/// Calculate MD5 hash of content.
+/// Calculate MD5 hex hash of content.
+///
+/// Content hashes in file names use the configured [`HashScheme`]; this is
+/// the fixed format of Flutter's service worker and the asset manifest.
 pub fn calculate_hash(content: &[u8], length: usize) -> String {
-    let digest = md5::compute(content);
-    let hash = format!("{:x}", digest);
-    hash[..length.min(hash.len())].to_string()
+    HashScheme::new(HashAlgorithm::Md5, HashEncoding::Hex, length.min(32)).hash(content)
+}
+
+/// Calculate a Subresource Integrity value: `{algorithm}-{base64 digest}`.
+pub fn calculate_integrity(content: &[u8], algorithm: SriAlgorithm) -> String {
+    let digest = match algorithm {
+        SriAlgorithm::Sha256 => Sha256::digest(content).to_vec(),
+        SriAlgorithm::Sha384 => Sha384::digest(content).to_vec(),
+        SriAlgorithm::Sha512 => Sha512::digest(content).to_vec(),
+    };
+    let encoded = data_encoding::BASE64.encode(&digest);
+    format!("{}-{}", algorithm.as_str(), encoded)
 }
 
 /// Read file content from disk.
@@ -113,6 +127,19 @@ mod tests {
         assert_eq!(hash.len(), 8);
     }
 
+    #[test]
+    fn test_calculate_integrity() {
+        // echo -n "alert('Hello, world.');" | openssl dgst -sha384 -binary | openssl base64 -A
+        let integrity = calculate_integrity(b"alert('Hello, world.');", SriAlgorithm::Sha384);
+        assert_eq!(
+            integrity,
+            "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO"
+        );
+
+        let integrity = calculate_integrity(b"", SriAlgorithm::Sha256);
+        assert!(integrity.starts_with("sha256-"));
+    }
+
     #[test]
     fn test_format_bytes() {
         assert_eq!(format_bytes(0), "0 B");
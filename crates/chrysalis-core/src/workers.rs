@@ -0,0 +1,79 @@
+//! Bounded worker pool for per-file work.
+
+use crate::{BuildError, Result};
+use rayon::prelude::*;
+use std::sync::Arc;
+
+/// Worker pool sized by `build.parallel_jobs`.
+///
+/// Results are always returned in input order, so plugins stay deterministic
+/// regardless of how work is scheduled.
+#[derive(Debug, Clone)]
+pub struct WorkerPool {
+    pool: Arc<rayon::ThreadPool>,
+    jobs: usize,
+}
+
+impl WorkerPool {
+    /// Create a pool with `jobs` workers (0 = number of CPUs).
+    pub fn new(jobs: usize) -> Result<Self> {
+        let jobs = if jobs == 0 {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        } else {
+            jobs
+        };
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .thread_name(|i| format!("chrysalis-worker-{}", i))
+            .build()
+            .map_err(|e| BuildError::Other(anyhow::anyhow!("Failed to start workers: {}", e)))?;
+
+        Ok(Self {
+            pool: Arc::new(pool),
+            jobs,
+        })
+    }
+
+    /// Number of workers.
+    pub fn jobs(&self) -> usize {
+        self.jobs
+    }
+
+    /// Apply `f` to every item on the pool, returning results in input order.
+    pub fn map<T, R, F>(&self, items: Vec<T>, f: F) -> Vec<R>
+    where
+        T: Send,
+        R: Send,
+        F: Fn(T) -> R + Sync + Send,
+    {
+        if self.jobs <= 1 || items.len() <= 1 {
+            return items.into_iter().map(f).collect();
+        }
+
+        self.pool.install(|| items.into_par_iter().map(f).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_preserves_order() {
+        let pool = WorkerPool::new(4).unwrap();
+        assert_eq!(pool.jobs(), 4);
+
+        let items: Vec<usize> = (0..1000).collect();
+        let results = pool.map(items, |i| i * 2);
+        assert_eq!(results, (0..1000).map(|i| i * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_default_jobs() {
+        let pool = WorkerPool::new(0).unwrap();
+        assert!(pool.jobs() > 0);
+    }
+}
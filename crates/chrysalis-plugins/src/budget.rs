@@ -0,0 +1,268 @@
+//! Size budgets checked against the final build output.
+
+use crate::Result;
+use crate::chunk::folded_chunks;
+use crate::manifest::{ManifestPlugin, to_url_path};
+use chrysalis_config::BudgetsConfig;
+use chrysalis_core::{BuildContext, FileInfo};
+use glob::Pattern;
+
+/// What a budget limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetUnit {
+    /// Size in bytes.
+    Bytes,
+    /// Number of items.
+    Count,
+}
+
+/// Result of checking one budget.
+#[derive(Debug, Clone)]
+pub struct BudgetCheck {
+    /// Budget description (e.g. the glob pattern).
+    pub name: String,
+
+    /// Measured value.
+    pub actual: u64,
+
+    /// Configured limit.
+    pub limit: u64,
+
+    /// Unit of `actual` and `limit`.
+    pub unit: BudgetUnit,
+}
+
+impl BudgetCheck {
+    /// Whether the measured value is within the limit.
+    pub fn passed(&self) -> bool {
+        self.actual <= self.limit
+    }
+
+    /// Format a value of this budget's unit.
+    pub fn format(&self, value: u64) -> String {
+        match self.unit {
+            BudgetUnit::Bytes => chrysalis_core::format_bytes(value),
+            BudgetUnit::Count => value.to_string(),
+        }
+    }
+}
+
+/// A top-level output file with its chunks folded in.
+struct BudgetFile<'a> {
+    original: String,
+    path: String,
+    file: &'a FileInfo,
+    chunks: Vec<&'a FileInfo>,
+}
+
+impl BudgetFile<'_> {
+    /// Size on disk, including chunks.
+    fn size(&self) -> u64 {
+        self.file.size + self.chunks.iter().map(|c| c.size).sum::<u64>()
+    }
+
+    /// Transfer size, including chunks.
+    fn transfer_size(&self) -> u64 {
+        self.file.transfer_size() + self.chunks.iter().map(|c| c.transfer_size()).sum::<u64>()
+    }
+
+    /// Whether the original or final path matches a pattern.
+    fn matches(&self, pattern: &Pattern) -> bool {
+        pattern.matches(&self.original) || pattern.matches(&self.path)
+    }
+}
+
+/// Compile glob patterns.
+fn compile<'a>(patterns: impl IntoIterator<Item = &'a String>) -> Result<Vec<Pattern>> {
+    Ok(patterns
+        .into_iter()
+        .map(|p| Pattern::new(p))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("Invalid budget pattern: {}", e))?)
+}
+
+/// Collect output files, folding chunks into the file they were split from.
+fn budget_files(ctx: &BuildContext) -> Vec<BudgetFile<'_>> {
+    let originals = ManifestPlugin::original_paths(ctx);
+
+    let folded = folded_chunks(ctx);
+
+    let mut files: Vec<BudgetFile> = ctx
+        .files()
+        .filter(|f| !folded.contains(f.absolute.as_path()))
+        .map(|file| BudgetFile {
+            original: to_url_path(originals.get(&file.relative).unwrap_or(&file.relative)),
+            path: to_url_path(&file.relative),
+            file,
+            chunks: ctx
+                .get_chunk_info(&file.absolute)
+                .into_iter()
+                .flatten()
+                .filter_map(|c| ctx.get_file(c))
+                .collect(),
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// Check the configured budgets against a processed build.
+///
+/// Returns one entry per configured budget, in configuration order.
+pub fn check_budgets(ctx: &BuildContext, config: &BudgetsConfig) -> Result<Vec<BudgetCheck>> {
+    let files = budget_files(ctx);
+    let mut checks = Vec::new();
+
+    let patterns = compile(config.files.iter().map(|b| &b.pattern))?;
+    for (budget, pattern) in config.files.iter().zip(&patterns) {
+        checks.push(BudgetCheck {
+            name: budget.pattern.clone(),
+            actual: files
+                .iter()
+                .filter(|f| f.matches(pattern))
+                .map(|f| f.size())
+                .sum(),
+            limit: budget.max_size_kb * 1024,
+            unit: BudgetUnit::Bytes,
+        });
+    }
+
+    if let Some(max_initial_kb) = config.max_initial_kb {
+        let initial = compile(&config.initial)?;
+        checks.push(BudgetCheck {
+            name: "initial download (transfer)".to_string(),
+            actual: files
+                .iter()
+                .filter(|f| initial.iter().any(|p| f.matches(p)))
+                .map(|f| f.transfer_size())
+                .sum(),
+            limit: max_initial_kb * 1024,
+            unit: BudgetUnit::Bytes,
+        });
+    }
+
+    if let Some(max_chunks) = config.max_chunks {
+        checks.push(BudgetCheck {
+            name: "chunks".to_string(),
+            actual: ctx.chunks().values().map(|c| c.len() as u64).sum(),
+            limit: max_chunks as u64,
+            unit: BudgetUnit::Count,
+        });
+    }
+
+    if let Some(max_total_kb) = config.max_total_kb {
+        checks.push(BudgetCheck {
+            name: "total output".to_string(),
+            actual: files.iter().map(|f| f.size()).sum(),
+            limit: max_total_kb * 1024,
+            unit: BudgetUnit::Bytes,
+        });
+    }
+
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrysalis_config::FileBudget;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A processed build: a hashed `main.dart.js` stub with two chunks, and
+    /// two chunks of a removed `canvaskit.wasm`.
+    fn context() -> (TempDir, BuildContext) {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("canvaskit")).unwrap();
+        let files = [
+            ("index.html", 1000),
+            ("main.dart.js", 1024),
+            ("main.dart.chunk0.js", 2048),
+            ("main.dart.chunk1.js", 2048),
+            ("canvaskit/canvaskit.chunk0.wasm", 4096),
+            ("canvaskit/canvaskit.chunk1.wasm", 4096),
+        ];
+        for (name, size) in files {
+            fs::write(root.join(name), vec![b'x'; size]).unwrap();
+        }
+
+        let mut ctx = BuildContext::new(root, vec![]).unwrap();
+        ctx.scan().unwrap();
+        let main = root.join("main.dart.0123abcd.js");
+        ctx.rename_file(root.join("main.dart.js"), main.clone()).unwrap();
+        ctx.add_chunk_info(
+            &main,
+            vec![
+                root.join("main.dart.chunk0.js"),
+                root.join("main.dart.chunk1.js"),
+            ],
+        );
+        ctx.add_chunk_info(
+            root.join("canvaskit/canvaskit.wasm"),
+            vec![
+                root.join("canvaskit/canvaskit.chunk0.wasm"),
+                root.join("canvaskit/canvaskit.chunk1.wasm"),
+            ],
+        );
+
+        let compressed = [
+            ("index.html", 300),
+            ("main.dart.0123abcd.js", 100),
+            ("main.dart.chunk0.js", 500),
+            ("main.dart.chunk1.js", 500),
+        ];
+        for (name, size) in compressed {
+            let file = ctx.get_file_mut(root.join(name)).unwrap();
+            file.compressed_sizes.insert("br".to_string(), size);
+        }
+
+        (temp, ctx)
+    }
+
+    fn budget(pattern: &str, max_size_kb: u64) -> FileBudget {
+        FileBudget {
+            pattern: pattern.to_string(),
+            max_size_kb,
+        }
+    }
+
+    #[test]
+    fn test_check_budgets() {
+        let (_temp, ctx) = context();
+        let config = BudgetsConfig {
+            files: vec![
+                budget("main.dart.js", 5),
+                budget("*.chunk*.js", 1),
+                budget("*.wasm", 7),
+            ],
+            initial: vec!["index.html".to_string(), "main.dart.js".to_string()],
+            max_initial_kb: Some(2),
+            max_chunks: Some(3),
+            max_total_kb: Some(14),
+        };
+
+        let checks = check_budgets(&ctx, &config).unwrap();
+        let results: Vec<(&str, u64, u64, bool)> = checks
+            .iter()
+            .map(|c| (c.name.as_str(), c.actual, c.limit, c.passed()))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                // The stub under its original name, with its chunks folded in
+                ("main.dart.js", 5120, 5120, true),
+                // Folded chunks do not count on their own
+                ("*.chunk*.js", 0, 1024, true),
+                // Chunks of the removed canvaskit.wasm do
+                ("*.wasm", 8192, 7168, false),
+                // Smallest variants of index.html, the stub and its chunks
+                ("initial download (transfer)", 1400, 2048, true),
+                ("chunks", 4, 3, false),
+                ("total output", 14312, 14336, true),
+            ]
+        );
+        assert_eq!(checks[4].unit, BudgetUnit::Count);
+        assert_eq!(checks[2].format(checks[2].actual), "8.00 KB");
+    }
+}
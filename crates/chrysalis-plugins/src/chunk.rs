@@ -1,11 +1,17 @@
 //! Chunking plugin for large file splitting.
 
-use crate::{Plugin, PluginError, Result};
+use crate::manifest::to_url_path;
+use crate::{Plan, Plugin, PluginError, Result};
 use chrysalis_config::ChunkConfig;
 use chrysalis_core::{BuildContext, FileInfo, FileNaming};
 use glob::Pattern;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::Path;
+use swc_core::common::{FileName, GLOBALS, SourceMap, sync::Lrc};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::parser::{Parser, StringInput, Syntax, lexer::Lexer};
+use swc_core::ecma::visit::{Visit, VisitWith};
 use tracing::{info, warn};
 
 /// Chunk metadata.
@@ -22,6 +28,7 @@ pub struct ChunkMetadata {
 /// Chunk plugin splits large files into smaller chunks.
 pub struct ChunkPlugin {
     config: ChunkConfig,
+    naming: FileNaming,
     chunk_size: usize,
     min_size: usize,
     include_patterns: Vec<Pattern>,
@@ -31,10 +38,18 @@ pub struct ChunkPlugin {
 impl ChunkPlugin {
     /// Create a new chunk plugin.
     pub fn new(config: ChunkConfig) -> Result<Self> {
+        // WebAssembly modules and their ES module entrypoints
+        let wasm_patterns: &[&str] = if config.wasm {
+            &["*.wasm", "*.mjs"]
+        } else {
+            &[]
+        };
         let include_patterns = config
             .include
             .iter()
-            .map(|p| Pattern::new(p))
+            .map(String::as_str)
+            .chain(wasm_patterns.iter().copied())
+            .map(Pattern::new)
             .collect::<std::result::Result<Vec<_>, _>>()
             .map_err(|e| anyhow::anyhow!("Invalid include pattern: {}", e))?;
 
@@ -45,11 +60,13 @@ impl ChunkPlugin {
             .collect::<std::result::Result<Vec<_>, _>>()
             .map_err(|e| anyhow::anyhow!("Invalid exclude pattern: {}", e))?;
 
+        let naming = FileNaming::default().with_chunk_file_name(&config.file_name)?;
         let chunk_size = config.chunk_size_bytes();
         let min_size = config.min_chunk_size_bytes();
 
         Ok(Self {
             config,
+            naming,
             chunk_size,
             min_size,
             include_patterns,
@@ -111,18 +128,13 @@ impl ChunkPlugin {
 
     /// Generate a stub loader for chunked JS files.
     /// The stub will lookup chunks from the global ChunkLoader manifest at runtime.
-    fn generate_stub(
-        &self,
-        file_name: &str,
-        _chunk_paths: &[PathBuf],
-        _build_dir: &std::path::Path,
-    ) -> Result<String> {
+    fn generate_stub(&self, file_name: &str) -> String {
         // Generate stub that looks up chunks from manifest at runtime
         // This way, the chunk file names can be hashed after this stub is created
         let stub = format!(
             r#"// Chrysalis chunked file stub
 (async function() {{
-  const fileName = '{file_name}';
+  const fileName = {file_name};
   const maxRetries = 3;
   let retryCount = 0;
 
@@ -138,29 +150,9 @@ impl ChunkPlugin {
         throw new Error('ChunkLoader not available after ' + maxRetries + ' retries');
       }}
 
-      // Get chunks from manifest (injected at build time with actual hashed names)
-      const chunks = window.ChunkLoader.manifest[fileName];
-      if (!chunks || chunks.length === 0) {{
-        throw new Error('No chunks found in manifest for: ' + fileName);
-      }}
-
-      // Load all chunks in parallel using XHR
-      const chunkData = await Promise.all(chunks.map(chunk => window.ChunkLoader.loadChunk(chunk)));
-
-      // Merge chunks
-      const totalLength = chunkData.reduce((sum, data) => sum + data.length, 0);
-      const merged = new Uint8Array(totalLength);
-      let offset = 0;
-      for (const data of chunkData) {{
-        merged.set(data, offset);
-        offset += data.length;
-      }}
-
-      // Execute the code
-      const text = new TextDecoder().decode(merged);
-      const script = document.createElement('script');
-      script.textContent = text;
-      document.head.appendChild(script);
+      // Load, verify and merge the chunks listed in the manifest (injected
+      // at build time with actual hashed names), then run them
+      await window.ChunkLoader.runScript(fileName);
     }} catch (e) {{
       console.error('[Chrysalis] Failed to load chunked file:', e);
       throw e;
@@ -170,19 +162,214 @@ impl ChunkPlugin {
   await loadWithRetry();
 }})();
 "#,
-            file_name = file_name
+            file_name = js_string(file_name)
         );
 
-        Ok(stub)
+        stub
+    }
+
+    /// Generate a stub for chunked ES modules.
+    ///
+    /// The stub imports the merged chunks as a module and re-exports
+    /// `exports`, so `import()` of the original URL keeps working.
+    fn generate_module_stub(&self, file_name: &str, exports: &[String]) -> String {
+        let named: Vec<&str> = exports
+            .iter()
+            .map(String::as_str)
+            .filter(|name| *name != "default")
+            .collect();
+
+        let mut stub = format!(
+            r#"// Chrysalis chunked module stub
+// Locals are prefixed so they cannot clash with the re-exported names
+for (let __chrysalisRetry = 0; !window.ChunkLoader || !window.ChunkLoader.manifest; __chrysalisRetry++) {{
+  if (__chrysalisRetry >= 3) {{
+    throw new Error('ChunkLoader not available after 3 retries');
+  }}
+  await new Promise(resolve => setTimeout(resolve, 100));
+}}
+
+// Load, verify and merge the chunks, then import them as a module
+const __chrysalisModule = await window.ChunkLoader.importModule({file_name});
+"#,
+            file_name = js_string(file_name)
+        );
+        if !named.is_empty() {
+            stub.push_str(&format!(
+                "export const {{ {} }} = __chrysalisModule;\n",
+                named.join(", ")
+            ));
+        }
+        if exports.iter().any(|name| name == "default") {
+            stub.push_str("export default __chrysalisModule.default;\n");
+        }
+
+        stub
+    }
+
+    /// Decide how a file is replaced by its chunks.
+    ///
+    /// Returns None if the file cannot be loaded from chunks.
+    fn replacement(&self, file: &FileInfo) -> Option<Replacement> {
+        let path = to_url_path(&file.relative);
+        match file.ext.as_str() {
+            ".js" => Some(Replacement::Stub(self.generate_stub(&path))),
+            ".mjs" => {
+                let source = file.content_as_str()?;
+                match module_exports(source) {
+                    Ok(exports) => Some(Replacement::Stub(
+                        self.generate_module_stub(&path, &exports),
+                    )),
+                    Err(reason) => {
+                        warn!("  Not chunking {}: {}", file.name, reason);
+                        None
+                    }
+                }
+            }
+            ext if FETCHED_EXTENSIONS.contains(&ext) => Some(Replacement::Removed),
+            _ => {
+                warn!(
+                    "  Not chunking {}: {} files cannot be loaded from chunks",
+                    file.name, file.ext
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Extensions of files the chunk loader serves to `fetch` of their original
+/// URL, so they can be removed once chunked.
+const FETCHED_EXTENSIONS: &[&str] = &[".wasm", ".json", ".bin"];
+
+/// What takes the place of a chunked file.
+enum Replacement {
+    /// A stub that loads the chunks and runs them.
+    Stub(String),
+    /// Nothing: the chunk loader serves the merged chunks to `fetch` of the
+    /// original URL (e.g. WebAssembly for `instantiateStreaming`).
+    Removed,
+}
+
+/// Quote a path as a JavaScript string literal.
+fn js_string(value: &str) -> String {
+    serde_json::to_string(value).expect("strings always serialize")
+}
+
+/// Finds what keeps a module from running from a `blob:` URL.
+#[derive(Default)]
+struct ModuleChecker {
+    problem: Option<&'static str>,
+}
+
+impl Visit for ModuleChecker {
+    fn visit_meta_prop_expr(&mut self, _node: &MetaPropExpr) {
+        self.problem = Some("uses import.meta");
+    }
+
+    fn visit_call_expr(&mut self, node: &CallExpr) {
+        if matches!(node.callee, Callee::Import(_)) {
+            self.problem = Some("uses dynamic import()");
+        }
+        node.visit_children_with(self);
     }
 }
 
+/// Names exported by an ES module.
+///
+/// Fails for modules that import others, re-export from them or use
+/// `import.meta`: relative URLs would resolve against the `blob:` URL the
+/// merged module runs from.
+fn module_exports(source: &str) -> std::result::Result<Vec<String>, String> {
+    GLOBALS.set(&Default::default(), || {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(FileName::Anon.into(), source.to_string());
+        let lexer = Lexer::new(
+            Syntax::Es(Default::default()),
+            Default::default(),
+            StringInput::from(&*fm),
+            None,
+        );
+        let module = Parser::new_from(lexer)
+            .parse_module()
+            .map_err(|e| format!("parse error: {:?}", e.kind()))?;
+
+        let mut checker = ModuleChecker::default();
+        module.visit_with(&mut checker);
+        if let Some(problem) = checker.problem {
+            return Err(problem.to_string());
+        }
+
+        let ident = |name: &ModuleExportName| match name {
+            ModuleExportName::Ident(ident) => Ok(ident.sym.to_string()),
+            _ => Err("exports a name that is not an identifier".to_string()),
+        };
+
+        let mut exports = Vec::new();
+        for item in &module.body {
+            let ModuleItem::ModuleDecl(decl) = item else {
+                continue;
+            };
+            match decl {
+                ModuleDecl::ExportDecl(export) => match &export.decl {
+                    Decl::Fn(f) => exports.push(f.ident.sym.to_string()),
+                    Decl::Class(c) => exports.push(c.ident.sym.to_string()),
+                    Decl::Var(var) => {
+                        for declarator in &var.decls {
+                            match &declarator.name {
+                                Pat::Ident(binding) => exports.push(binding.id.sym.to_string()),
+                                _ => return Err("exports a destructuring pattern".to_string()),
+                            }
+                        }
+                    }
+                    _ => return Err("unsupported export declaration".to_string()),
+                },
+                ModuleDecl::ExportNamed(named) if named.src.is_none() => {
+                    for specifier in &named.specifiers {
+                        match specifier {
+                            ExportSpecifier::Named(named) => {
+                                exports.push(ident(named.exported.as_ref().unwrap_or(&named.orig))?)
+                            }
+                            _ => return Err("unsupported export specifier".to_string()),
+                        }
+                    }
+                }
+                ModuleDecl::ExportDefaultDecl(_) | ModuleDecl::ExportDefaultExpr(_) => {
+                    exports.push("default".to_string())
+                }
+                ModuleDecl::Import(_) => return Err("imports other modules".to_string()),
+                _ => return Err("re-exports other modules".to_string()),
+            }
+        }
+
+        Ok(exports)
+    })
+}
+
+/// Chunks that are counted with the file they were split from.
+///
+/// Scripts keep a stub under their own name and fold their chunks into it;
+/// the parents of other chunked files (e.g. `.wasm`) are removed, so their
+/// chunks stand on their own.
+pub(crate) fn folded_chunks(ctx: &BuildContext) -> HashSet<&Path> {
+    ctx.chunks()
+        .iter()
+        .filter(|(parent, _)| ctx.get_file(parent).is_some())
+        .flat_map(|(_, chunks)| chunks.iter().map(|c| c.as_path()))
+        .collect()
+}
+
 #[async_trait::async_trait]
 impl Plugin for ChunkPlugin {
     fn name(&self) -> &str {
         "chunk"
     }
 
+    fn runs_after(&self) -> &[&str] {
+        // Chunks are cut from minified output
+        &["minify"]
+    }
+
     async fn execute(&self, ctx: &mut BuildContext) -> Result<()> {
         if !self.config.enabled {
             info!("Chunking disabled");
@@ -202,7 +389,7 @@ impl Plugin for ChunkPlugin {
 
         for file_path in files_to_chunk {
             // Load content and split into chunks
-            let (chunks, file_name, parent_dir, build_dir) = {
+            let (chunks, replacement, relative, build_dir) = {
                 let file = ctx.get_file_mut(&file_path).unwrap();
 
                 // Load content
@@ -226,10 +413,14 @@ impl Plugin for ChunkPlugin {
                     continue;
                 }
 
+                let Some(replacement) = self.replacement(file) else {
+                    continue;
+                };
+
                 (
                     chunks,
-                    file.name.clone(),
-                    file.absolute.parent().unwrap().to_path_buf(),
+                    replacement,
+                    file.relative.clone(),
                     ctx.build_dir().to_path_buf(),
                 )
             };
@@ -240,21 +431,24 @@ impl Plugin for ChunkPlugin {
             let mut chunk_paths = Vec::new();
 
             for (i, chunk_content) in chunks.iter().enumerate() {
-                let chunk_name = FileNaming::add_chunk_suffix(&file_name, i);
-                let chunk_path = parent_dir.join(&chunk_name);
+                let chunk_relative = self.naming.chunk_path(&relative, i);
+                let chunk_path = build_dir.join(&chunk_relative);
+                if ctx.get_file(&chunk_path).is_some() || chunk_path.exists() {
+                    return Err(PluginError::ChunkingFailed {
+                        file: file_path.clone(),
+                        reason: format!("{} already exists", chunk_relative.display()),
+                    });
+                }
 
-                // Write chunk file
+                // Write chunk file (templates may put chunks in another directory)
+                if let Some(parent) = chunk_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
                 chrysalis_core::write_file_content(&chunk_path, chunk_content)?;
 
                 // Add to context
-                let relative = pathdiff::diff_paths(&chunk_path, &build_dir).ok_or_else(|| {
-                    PluginError::ChunkingFailed {
-                        file: file_path.clone(),
-                        reason: "Failed to compute relative path".to_string(),
-                    }
-                })?;
-
-                let chunk_file = FileInfo::new(&chunk_path, &relative, chunk_content.len() as u64);
+                let chunk_file =
+                    FileInfo::new(&chunk_path, &chunk_relative, chunk_content.len() as u64);
                 ctx.add_file(chunk_file)?;
                 chunk_paths.push(chunk_path);
             }
@@ -262,17 +456,15 @@ impl Plugin for ChunkPlugin {
             // Record chunk info
             ctx.add_chunk_info(&file_path, chunk_paths.clone());
 
-            // Replace original file with a stub loader (for JS files)
-            if file_name.ends_with(".js") {
-                let stub_content = self.generate_stub(&file_name, &chunk_paths, &build_dir)?;
+            // Replace original file with a stub loader (for scripts and modules)
+            if let Replacement::Stub(stub_content) = replacement {
                 chrysalis_core::write_file_content(&file_path, stub_content.as_bytes())?;
 
                 // Update file info in context
                 let file = ctx.get_file_mut(&file_path).unwrap();
-                file.size = stub_content.len() as u64;
                 file.set_content(stub_content.into_bytes());
             } else {
-                // For non-JS files, delete the original
+                // Other files are served from the chunks by the loader
                 std::fs::remove_file(&file_path).map_err(|e| PluginError::ChunkingFailed {
                     file: file_path.clone(),
                     reason: format!("Failed to delete original file: {}", e),
@@ -288,4 +480,147 @@ impl Plugin for ChunkPlugin {
         );
         Ok(())
     }
+
+    fn plan(&self, plan: &mut Plan) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let mut generated = Vec::new();
+
+        for planned in plan.files_mut() {
+            if !self.should_chunk(&planned.file) {
+                continue;
+            }
+
+            if let Err(e) = planned.file.load_content() {
+                warn!("Failed to load {}: {}", planned.file.name, e);
+                continue;
+            }
+
+            let chunks = self.split_into_chunks(&planned.file)?;
+            if chunks.len() <= 1 {
+                continue;
+            }
+
+            let Some(replacement) = self.replacement(&planned.file) else {
+                continue;
+            };
+
+            for (i, chunk_content) in chunks.into_iter().enumerate() {
+                generated.push((
+                    self.naming.chunk_path(&planned.file.relative, i),
+                    chunk_content,
+                    planned.original.clone(),
+                ));
+            }
+
+            // Scripts and modules are replaced by a stub, others removed
+            if let Replacement::Stub(stub) = replacement {
+                planned.file.set_content(stub.into_bytes());
+            } else {
+                planned.removed = true;
+            }
+        }
+
+        for (relative, content, parent) in generated {
+            plan.add_generated(relative, content, &parent);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Chunks of 1 KB, from files of 1 KB and up, WebAssembly included.
+    fn plugin(include: &[&str]) -> ChunkPlugin {
+        ChunkPlugin::new(ChunkConfig {
+            chunk_size_kb: 1,
+            min_chunk_size_kb: 1,
+            wasm: true,
+            include: include.iter().map(|p| p.to_string()).collect(),
+            ..ChunkConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn context(files: &[(&str, Vec<u8>)]) -> (TempDir, BuildContext) {
+        let temp = TempDir::new().unwrap();
+        for (name, content) in files {
+            let path = temp.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let mut ctx = BuildContext::new(temp.path(), vec![]).unwrap();
+        ctx.scan().unwrap();
+        (temp, ctx)
+    }
+
+    #[tokio::test]
+    async fn test_wasm_is_removed_after_chunking() {
+        let wasm = vec![7u8; 2500];
+        let (temp, mut ctx) = context(&[("canvaskit/canvaskit.wasm", wasm.clone())]);
+        plugin(&[]).execute(&mut ctx).await.unwrap();
+
+        let parent = temp.path().join("canvaskit/canvaskit.wasm");
+        assert!(!parent.exists());
+        assert!(ctx.get_file(&parent).is_none());
+
+        let chunks = ctx.get_chunk_info(&parent).unwrap();
+        assert_eq!(chunks.len(), 3);
+        let merged: Vec<u8> = chunks.iter().flat_map(|c| fs::read(c).unwrap()).collect();
+        assert_eq!(merged, wasm);
+        assert!(chunks[0].ends_with("canvaskit/canvaskit.chunk0.wasm"));
+        assert!(ctx.get_file(&chunks[2]).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_module_is_replaced_by_stub() {
+        let module = format!(
+            "const padding = '{}';\nexport function init() {{}}\nexport default padding;\n",
+            "x".repeat(2500)
+        );
+        let (temp, mut ctx) = context(&[("skwasm.mjs", module.into_bytes())]);
+        plugin(&[]).execute(&mut ctx).await.unwrap();
+
+        let parent = temp.path().join("skwasm.mjs");
+        assert_eq!(ctx.get_chunk_info(&parent).unwrap().len(), 3);
+
+        let stub = fs::read_to_string(&parent).unwrap();
+        assert!(stub.contains(r#"window.ChunkLoader.importModule("skwasm.mjs")"#));
+        assert!(stub.contains("export const { init } = __chrysalisModule;"));
+        assert!(stub.contains("export default __chrysalisModule.default;"));
+        assert_eq!(
+            ctx.get_file(&parent).unwrap().content_as_str(),
+            Some(stub.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_other_types_are_left_whole() {
+        let css = vec![b'a'; 2500];
+        let (temp, mut ctx) = context(&[("styles.css", css.clone())]);
+        plugin(&["*.css"]).execute(&mut ctx).await.unwrap();
+
+        let parent = temp.path().join("styles.css");
+        assert_eq!(fs::read(&parent).unwrap(), css);
+        assert!(ctx.get_file(&parent).is_some());
+        assert!(ctx.chunks().is_empty());
+        assert!(!temp.path().join("styles.chunk0.css").exists());
+    }
+
+    #[test]
+    fn test_stub_quotes_file_name() {
+        let plugin = plugin(&[]);
+        let stub = plugin.generate_stub("it's/\"main\".js");
+        assert!(stub.contains(r#"const fileName = "it's/\"main\".js";"#));
+
+        let module = plugin.generate_module_stub("a'b.mjs", &[]);
+        assert!(module.contains(r#"importModule("a'b.mjs")"#));
+    }
 }
@@ -0,0 +1,344 @@
+//! Precompression plugin for static hosting.
+
+use crate::{Plan, Plugin, PluginError, Result};
+use chrysalis_config::CompressConfig;
+use chrysalis_core::{BuildContext, FileInfo};
+use glob::Pattern;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Extensions of precompressed variants (never compressed again).
+pub(crate) const VARIANT_EXTENSIONS: &[&str] = &[".br", ".gz", ".zst"];
+
+/// Supported content encodings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Brotli (`.br`).
+    Brotli,
+    /// Gzip (`.gz`).
+    Gzip,
+    /// Zstandard (`.zst`).
+    Zstd,
+}
+
+impl Encoding {
+    /// Content-Encoding token for this encoding.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+            Encoding::Zstd => "zstd",
+        }
+    }
+
+    /// File extension appended to the original file name.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => ".br",
+            Encoding::Gzip => ".gz",
+            Encoding::Zstd => ".zst",
+        }
+    }
+
+    /// Path of the precompressed sibling: `main.dart.js` -> `main.dart.js.br`
+    pub fn variant_path(&self, path: &Path) -> PathBuf {
+        let mut variant = path.as_os_str().to_owned();
+        variant.push(self.extension());
+        PathBuf::from(variant)
+    }
+}
+
+/// Compress plugin writes precompressed siblings next to output files.
+///
+/// Variants are not tracked as build files; this plugin must run after every
+/// plugin that renames or rewrites files.
+pub struct CompressPlugin {
+    config: CompressConfig,
+    encodings: Vec<Encoding>,
+    min_size: u64,
+    include_patterns: Vec<Pattern>,
+    exclude_patterns: Vec<Pattern>,
+}
+
+impl CompressPlugin {
+    /// Create a new compress plugin.
+    pub fn new(config: CompressConfig) -> Result<Self> {
+        let include_patterns = config
+            .include
+            .iter()
+            .map(|p| Pattern::new(p))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| anyhow::anyhow!("Invalid include pattern: {}", e))?;
+
+        let exclude_patterns = config
+            .exclude
+            .iter()
+            .map(|p| Pattern::new(p))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| anyhow::anyhow!("Invalid exclude pattern: {}", e))?;
+
+        let mut encodings = Vec::new();
+        if config.brotli {
+            encodings.push(Encoding::Brotli);
+        }
+        if config.gzip {
+            encodings.push(Encoding::Gzip);
+        }
+        if config.zstd {
+            encodings.push(Encoding::Zstd);
+        }
+
+        let min_size = config.min_size_bytes() as u64;
+
+        Ok(Self {
+            config,
+            encodings,
+            min_size,
+            include_patterns,
+            exclude_patterns,
+        })
+    }
+
+    /// Check if file should be compressed.
+    fn should_compress(&self, file: &FileInfo) -> bool {
+        // Never compress an existing variant
+        if VARIANT_EXTENSIONS.contains(&file.ext.as_str()) {
+            return false;
+        }
+
+        // File must be large enough
+        if file.size < self.min_size {
+            return false;
+        }
+
+        // Check exclude patterns first
+        for pattern in &self.exclude_patterns {
+            if pattern.matches_path(&file.relative) {
+                return false;
+            }
+        }
+
+        // Check include patterns
+        for pattern in &self.include_patterns {
+            if pattern.matches_path(&file.relative) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Check if a variant saves enough to be worth serving.
+    fn has_gain(&self, original: usize, compressed: usize) -> bool {
+        let threshold = original as f64 * (1.0 - self.config.min_savings_percent / 100.0);
+        (compressed as f64) < threshold
+    }
+
+    /// Compress content with the given encoding.
+    fn compress(&self, encoding: Encoding, content: &[u8]) -> std::io::Result<Vec<u8>> {
+        match encoding {
+            Encoding::Brotli => {
+                let params = brotli::enc::BrotliEncoderParams {
+                    quality: self.config.brotli_level as i32,
+                    ..Default::default()
+                };
+                let mut output = Vec::new();
+                brotli::BrotliCompress(&mut &content[..], &mut output, &params)?;
+                Ok(output)
+            }
+            Encoding::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(
+                    Vec::new(),
+                    flate2::Compression::new(self.config.gzip_level),
+                );
+                encoder.write_all(content)?;
+                encoder.finish()
+            }
+            Encoding::Zstd => zstd::bulk::compress(content, self.config.zstd_level),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Plugin for CompressPlugin {
+    fn name(&self) -> &str {
+        "compress"
+    }
+
+    fn runs_after(&self) -> &[&str] {
+        // Variants must match the final bytes and names
+        &["minify", "chunk", "hash", "inject", "service_worker"]
+    }
+
+    async fn execute(&self, ctx: &mut BuildContext) -> Result<()> {
+        if !self.config.enabled {
+            info!("Compression disabled");
+            return Ok(());
+        }
+
+        info!("Precompressing files...");
+
+        // Collect files to compress, in a stable order
+        let workers = ctx.workers()?.clone();
+        let mut files_to_compress: Vec<&mut FileInfo> = ctx
+            .files_mut()
+            .filter(|f| self.should_compress(f))
+            .collect();
+        files_to_compress.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+        let results = workers.map(files_to_compress, |file| -> Result<bool> {
+            // Load content
+            if let Err(e) = file.load_content() {
+                warn!("Failed to load {}: {}", file.name, e);
+                return Ok(false);
+            }
+
+            let content = file.content.as_ref().unwrap();
+            let mut sizes = BTreeMap::new();
+
+            for encoding in &self.encodings {
+                let compressed = self.compress(*encoding, content).map_err(|e| {
+                    PluginError::CompressionFailed {
+                        file: file.absolute.clone(),
+                        reason: format!("{}: {}", encoding.name(), e),
+                    }
+                })?;
+
+                if !self.has_gain(content.len(), compressed.len()) {
+                    debug!(
+                        "  Skipping {} for {} (insufficient gain)",
+                        encoding.name(),
+                        file.name
+                    );
+                    continue;
+                }
+
+                chrysalis_core::write_file_content(
+                    encoding.variant_path(&file.absolute),
+                    &compressed,
+                )?;
+                sizes.insert(encoding.name().to_string(), compressed.len() as u64);
+            }
+
+            if sizes.is_empty() {
+                return Ok(false);
+            }
+
+            file.compressed_sizes = sizes;
+            Ok(true)
+        });
+
+        for compressed in results {
+            if compressed? {
+                ctx.stats_mut().record_compression();
+            }
+        }
+
+        let transfer_size = ctx.files().map(|f| f.transfer_size()).sum();
+        ctx.stats_mut().transfer_size = transfer_size;
+
+        info!(
+            "✓ Precompressed {} files ({} transfer)",
+            ctx.stats().compressed_files,
+            chrysalis_core::format_bytes(transfer_size)
+        );
+        Ok(())
+    }
+
+    fn plan(&self, plan: &mut Plan) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        for planned in plan.files_mut() {
+            if self.should_compress(&planned.file) {
+                planned.compress = true;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    fn plugin() -> CompressPlugin {
+        CompressPlugin::new(CompressConfig {
+            enabled: true,
+            zstd: false,
+            ..CompressConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_has_gain() {
+        let plugin = plugin();
+        assert!(plugin.has_gain(1000, 949));
+        assert!(!plugin.has_gain(1000, 950));
+        assert!(!plugin.has_gain(1000, 1200));
+    }
+
+    #[tokio::test]
+    async fn test_writes_variants_with_gain() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let text = "function main() { return 42; }\n".repeat(200);
+        // Bytes of a linear congruential generator do not compress
+        let noise: Vec<u8> = (0..4096u32)
+            .scan(1u32, |state, _| {
+                *state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                Some((*state >> 16) as u8)
+            })
+            .collect();
+        fs::write(root.join("main.dart.js"), &text).unwrap();
+        fs::write(root.join("noise.js"), &noise).unwrap();
+        fs::write(root.join("small.js"), "main();").unwrap();
+
+        let mut ctx = BuildContext::new(root, vec![]).unwrap();
+        ctx.scan().unwrap();
+        plugin().execute(&mut ctx).await.unwrap();
+
+        // Brotli and gzip variants of the text, but not zstd
+        let main = ctx.get_file(root.join("main.dart.js")).unwrap();
+        let sizes: Vec<&str> = main.compressed_sizes.keys().map(String::as_str).collect();
+        assert_eq!(sizes, ["br", "gzip"]);
+        assert!(!root.join("main.dart.js.zst").exists());
+        let br = fs::read(root.join("main.dart.js.br")).unwrap();
+        let gz = fs::read(root.join("main.dart.js.gz")).unwrap();
+        assert_eq!(main.compressed_sizes["br"], br.len() as u64);
+        assert_eq!(main.compressed_sizes["gzip"], gz.len() as u64);
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&gz[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, text);
+
+        // No gain for noise, too small for the last file
+        for name in ["noise.js", "small.js"] {
+            assert!(
+                ctx.get_file(root.join(name))
+                    .unwrap()
+                    .compressed_sizes
+                    .is_empty()
+            );
+            assert!(!root.join(format!("{}.br", name)).exists());
+            assert!(!root.join(format!("{}.gz", name)).exists());
+        }
+
+        assert_eq!(ctx.stats().compressed_files, 1);
+        assert_eq!(
+            ctx.stats().transfer_size,
+            main.transfer_size() + noise.len() as u64 + 7
+        );
+        assert_eq!(main.transfer_size(), br.len().min(gz.len()) as u64);
+    }
+}
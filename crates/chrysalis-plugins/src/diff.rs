@@ -0,0 +1,371 @@
+//! Build-to-build size comparison.
+//!
+//! Hashed names change on every build, so files are matched by their
+//! original name ([`FileNaming::get_original_name`]): hashes and chunk
+//! suffixes are stripped and chunks count toward the file they were split
+//! from.
+
+use crate::Result;
+use crate::compress::VARIANT_EXTENSIONS;
+use crate::manifest::{AssetManifest, to_url_path};
+use crate::report::kind_of;
+use chrysalis_core::{FileNaming, Scanner};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
+
+/// Output sizes of one build, keyed by original name.
+#[derive(Debug, Clone, Default)]
+pub struct BuildSizes {
+    /// Size in bytes per original relative path.
+    pub files: BTreeMap<String, u64>,
+}
+
+impl BuildSizes {
+    /// Load sizes from a build output directory or an `asset-manifest.json`.
+    ///
+    /// Hashes in the names of a build directory are recognised by `naming`.
+    pub fn load<P: AsRef<Path>>(path: P, naming: &FileNaming) -> Result<Self> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Self::from_dir(path, naming)
+        } else {
+            Ok(Self::from_manifest(
+                &AssetManifest::from_file(path)?,
+                naming,
+            ))
+        }
+    }
+
+    /// Collect sizes from a build output directory.
+    ///
+    /// Precompressed variants of files that are present are skipped.
+    pub fn from_dir<P: AsRef<Path>>(dir: P, naming: &FileNaming) -> Result<Self> {
+        let files = Scanner::new(dir)?.scan()?;
+        let paths: HashSet<String> = files.iter().map(|f| to_url_path(&f.relative)).collect();
+
+        let mut sizes = Self::default();
+        for file in &files {
+            let path = to_url_path(&file.relative);
+            let is_variant = VARIANT_EXTENSIONS.iter().any(|ext| {
+                path.strip_suffix(ext)
+                    .is_some_and(|base| paths.contains(base))
+            });
+            if !is_variant {
+                sizes.add(&path, file.size, naming);
+            }
+        }
+
+        Ok(sizes)
+    }
+
+    /// Collect sizes from an asset manifest.
+    ///
+    /// Chunks are listed under their own original paths, which `naming`
+    /// maps to the file they were split from.
+    pub fn from_manifest(manifest: &AssetManifest, naming: &FileNaming) -> Self {
+        let mut sizes = Self::default();
+        for (original, entry) in &manifest.files {
+            sizes.add(original, entry.size, naming);
+        }
+        sizes
+    }
+
+    /// Add a file under its original name.
+    fn add(&mut self, path: &str, size: u64, naming: &FileNaming) {
+        *self
+            .files
+            .entry(naming.get_original_name(path))
+            .or_default() += size;
+    }
+
+    /// Total size in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.files.values().sum()
+    }
+}
+
+/// How a file changed between builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffStatus {
+    /// Only in the new build.
+    Added,
+    /// Only in the old build.
+    Removed,
+    /// Size changed.
+    Changed,
+    /// Same size.
+    Unchanged,
+}
+
+/// Size change of one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiff {
+    /// Original relative path.
+    pub name: String,
+
+    /// File type (e.g. "JavaScript").
+    pub kind: &'static str,
+
+    /// How the file changed.
+    pub status: DiffStatus,
+
+    /// Size in the old build.
+    pub old_size: Option<u64>,
+
+    /// Size in the new build.
+    pub new_size: Option<u64>,
+
+    /// Size change in bytes.
+    pub delta: i64,
+}
+
+/// Size change of one file type.
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeDiff {
+    /// File type (e.g. "JavaScript").
+    pub kind: &'static str,
+
+    /// Total size in the old build.
+    pub old_size: u64,
+
+    /// Total size in the new build.
+    pub new_size: u64,
+
+    /// Size change in bytes.
+    pub delta: i64,
+}
+
+/// Size comparison of two builds.
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeDiff {
+    /// Total size of the old build.
+    pub old_size: u64,
+
+    /// Total size of the new build.
+    pub new_size: u64,
+
+    /// Total size change in bytes.
+    pub delta: i64,
+
+    /// Per-type changes, largest change first.
+    pub types: Vec<TypeDiff>,
+
+    /// Per-file changes, largest change first.
+    pub files: Vec<FileDiff>,
+}
+
+/// Signed difference of two sizes.
+fn delta(old: u64, new: u64) -> i64 {
+    new as i64 - old as i64
+}
+
+impl SizeDiff {
+    /// Compare two builds.
+    pub fn between(old: &BuildSizes, new: &BuildSizes) -> Self {
+        let names: BTreeSet<&String> = old.files.keys().chain(new.files.keys()).collect();
+
+        let mut files: Vec<FileDiff> = names
+            .into_iter()
+            .map(|name| {
+                let old_size = old.files.get(name).copied();
+                let new_size = new.files.get(name).copied();
+                let status = match (old_size, new_size) {
+                    (None, _) => DiffStatus::Added,
+                    (_, None) => DiffStatus::Removed,
+                    (Some(a), Some(b)) if a != b => DiffStatus::Changed,
+                    _ => DiffStatus::Unchanged,
+                };
+                let ext = Path::new(name)
+                    .extension()
+                    .map(|e| format!(".{}", e.to_string_lossy()))
+                    .unwrap_or_default();
+
+                FileDiff {
+                    name: name.clone(),
+                    kind: kind_of(&ext),
+                    status,
+                    old_size,
+                    new_size,
+                    delta: delta(old_size.unwrap_or(0), new_size.unwrap_or(0)),
+                }
+            })
+            .collect();
+        files.sort_by(|a, b| {
+            b.delta
+                .unsigned_abs()
+                .cmp(&a.delta.unsigned_abs())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        let mut types: BTreeMap<&'static str, TypeDiff> = BTreeMap::new();
+        for file in &files {
+            let entry = types.entry(file.kind).or_insert(TypeDiff {
+                kind: file.kind,
+                old_size: 0,
+                new_size: 0,
+                delta: 0,
+            });
+            entry.old_size += file.old_size.unwrap_or(0);
+            entry.new_size += file.new_size.unwrap_or(0);
+            entry.delta += file.delta;
+        }
+        let mut types: Vec<TypeDiff> = types.into_values().collect();
+        types.sort_by(|a, b| {
+            b.delta
+                .unsigned_abs()
+                .cmp(&a.delta.unsigned_abs())
+                .then_with(|| b.new_size.cmp(&a.new_size))
+        });
+
+        Self {
+            old_size: old.total_size(),
+            new_size: new.total_size(),
+            delta: delta(old.total_size(), new.total_size()),
+            types,
+            files,
+        }
+    }
+
+    /// Files that were added, removed or changed size.
+    pub fn changes(&self) -> impl Iterator<Item = &FileDiff> {
+        self.files
+            .iter()
+            .filter(|f| f.status != DiffStatus::Unchanged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A build directory holding `files` (relative path, size).
+    fn build(files: &[(&str, usize)]) -> TempDir {
+        let temp = TempDir::new().unwrap();
+        for (name, size) in files {
+            let path = temp.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![b'x'; *size]).unwrap();
+        }
+        temp
+    }
+
+    fn sizes(files: &[(&str, usize)]) -> BuildSizes {
+        let temp = build(files);
+        BuildSizes::load(temp.path(), &FileNaming::default()).unwrap()
+    }
+
+    #[test]
+    fn test_from_dir_uses_original_names() {
+        let sizes = sizes(&[
+            ("index.html", 50),
+            ("index.html.gz", 20),
+            ("main.dart.0123abcd.js", 100),
+            ("main.dart.0123abcd.js.br", 40),
+            ("main.dart.0123abcd.chunk0.js", 300),
+            ("main.dart.chunk1.89abcdef.js", 200),
+            ("assets/logo.fedcba98.png", 70),
+            ("assets/logo.fedcba98.png.zst", 60),
+            ("notes.txt.br", 5),
+        ]);
+
+        let expected: BTreeMap<String, u64> = [
+            ("assets/logo.png", 70),
+            ("index.html", 50),
+            ("main.dart.js", 600),
+            ("notes.txt.br", 5),
+        ]
+        .into_iter()
+        .map(|(name, size)| (name.to_string(), size))
+        .collect();
+        assert_eq!(sizes.files, expected);
+        assert_eq!(sizes.total_size(), 725);
+    }
+
+    #[test]
+    fn test_from_manifest_folds_chunks() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("asset-manifest.json");
+        fs::write(
+            &path,
+            r#"{"version": 1, "files": {
+                "index.html": {"file": "index.html", "size": 50, "hash": "a"},
+                "main.dart.js": {"file": "main.dart.0123abcd.js", "size": 100, "hash": "b",
+                    "chunks": ["main.dart.chunk0.89abcdef.js"], "compressed": {"br": 40}},
+                "main.dart.chunk0.js": {"file": "main.dart.chunk0.89abcdef.js", "size": 300, "hash": "c"}
+            }}"#,
+        )
+        .unwrap();
+
+        let sizes = BuildSizes::load(&path, &FileNaming::default()).unwrap();
+        assert_eq!(sizes.files.len(), 2);
+        assert_eq!(sizes.files["index.html"], 50);
+        assert_eq!(sizes.files["main.dart.js"], 400);
+    }
+
+    #[test]
+    fn test_between_classifies_files() {
+        let old = sizes(&[
+            ("index.html", 50),
+            ("main.dart.0123abcd.js", 100),
+            ("main.dart.0123abcd.chunk0.js", 300),
+            ("old.11111111.js", 30),
+        ]);
+        let new = sizes(&[
+            ("index.html", 50),
+            ("main.dart.89abcdef.js", 100),
+            ("main.dart.89abcdef.chunk0.js", 350),
+            ("styles.22222222.css", 10),
+        ]);
+
+        let diff = SizeDiff::between(&old, &new);
+        assert_eq!((diff.old_size, diff.new_size, diff.delta), (480, 510, 30));
+
+        let files: Vec<(&str, DiffStatus, i64)> = diff
+            .files
+            .iter()
+            .map(|f| (f.name.as_str(), f.status, f.delta))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                ("main.dart.js", DiffStatus::Changed, 50),
+                ("old.js", DiffStatus::Removed, -30),
+                ("styles.css", DiffStatus::Added, 10),
+                ("index.html", DiffStatus::Unchanged, 0),
+            ]
+        );
+        let sizes: Vec<(Option<u64>, Option<u64>)> = diff
+            .files
+            .iter()
+            .map(|f| (f.old_size, f.new_size))
+            .collect();
+        assert_eq!(
+            sizes,
+            [
+                (Some(400), Some(450)),
+                (Some(30), None),
+                (None, Some(10)),
+                (Some(50), Some(50)),
+            ]
+        );
+        assert_eq!(diff.changes().count(), 3);
+
+        let types: Vec<(&str, u64, u64, i64)> = diff
+            .types
+            .iter()
+            .map(|t| (t.kind, t.old_size, t.new_size, t.delta))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ("JavaScript", 430, 450, 20),
+                ("CSS", 0, 10, 10),
+                ("HTML", 50, 50, 0),
+            ]
+        );
+    }
+}
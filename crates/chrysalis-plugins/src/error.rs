@@ -18,10 +18,26 @@ pub enum PluginError {
     #[error("Chunking failed for {file}: {reason}")]
     ChunkingFailed { file: PathBuf, reason: String },
 
+    /// Compression failed.
+    #[error("Compression failed for {file}: {reason}")]
+    CompressionFailed { file: PathBuf, reason: String },
+
     /// Injection failed.
     #[error("Injection failed: {0}")]
     InjectionFailed(String),
 
+    /// Service worker rewriting failed.
+    #[error("Service worker update failed: {0}")]
+    ServiceWorkerFailed(String),
+
+    /// External plugin failed.
+    #[error("External plugin '{plugin}' failed: {reason}")]
+    ExternalFailed { plugin: String, reason: String },
+
+    /// Invalid plugin pipeline.
+    #[error("Invalid pipeline: {0}")]
+    InvalidPipeline(String),
+
     /// Template error.
     #[error("Template error: {0}")]
     TemplateError(String),
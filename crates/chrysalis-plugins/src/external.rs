@@ -0,0 +1,603 @@
+//! External process plugins.
+//!
+//! An external plugin is any executable that speaks a small JSON protocol:
+//! it receives a [`Request`] describing the build on stdin and writes a
+//! [`Response`] with file [`Operation`]s to stdout. Operations are applied
+//! through the `BuildContext`, so later plugins see the changes. The
+//! executable's stderr is passed through for logging.
+//!
+//! Files are on disk when the plugin runs, so requests carry paths rather
+//! than content. All paths are relative to the build directory.
+
+use crate::manifest::{ManifestPlugin, to_url_path};
+use crate::{Plan, Plugin, PluginError, Result};
+use chrysalis_config::ExternalPluginConfig;
+use chrysalis_core::{BuildContext, FileInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info};
+
+/// Current protocol version.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Build description sent to the plugin.
+#[derive(Debug, Serialize)]
+pub struct Request<'a> {
+    /// Protocol version.
+    pub protocol: u32,
+
+    /// Plugin name (as listed in the pipeline).
+    pub plugin: &'a str,
+
+    /// Absolute build directory.
+    pub build_dir: &'a Path,
+
+    /// Options from the plugin configuration and pipeline step.
+    pub options: &'a serde_json::Map<String, serde_json::Value>,
+
+    /// Files, sorted by path.
+    pub files: Vec<RequestFile>,
+
+    /// Renames so far (old path -> new path).
+    pub file_mapping: BTreeMap<String, String>,
+
+    /// Chunk files per parent file.
+    pub chunks: BTreeMap<String, Vec<String>>,
+
+    /// Dependencies per file.
+    pub dependencies: BTreeMap<String, Vec<String>>,
+}
+
+/// A file in a [`Request`].
+#[derive(Debug, Serialize)]
+pub struct RequestFile {
+    /// Current path.
+    pub path: String,
+
+    /// Path before any renames.
+    pub original: String,
+
+    /// Size in bytes.
+    pub size: u64,
+}
+
+/// Operations returned by the plugin.
+#[derive(Debug, Default, Deserialize)]
+pub struct Response {
+    /// Operations, applied in order.
+    #[serde(default)]
+    pub operations: Vec<Operation>,
+}
+
+/// A change to apply to the build.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    /// Replace the content of an existing file.
+    Write {
+        path: String,
+        #[serde(flatten)]
+        content: Content,
+    },
+
+    /// Create a new file.
+    Add {
+        path: String,
+        #[serde(flatten)]
+        content: Content,
+    },
+
+    /// Rename a file.
+    Rename { from: String, to: String },
+
+    /// Remove a file.
+    Remove { path: String },
+
+    /// Record that `file` references `dependency`.
+    AddDependency { file: String, dependency: String },
+}
+
+/// File content in an [`Operation`], as UTF-8 text or base64.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    /// UTF-8 text.
+    Text { content: String },
+    /// Base64-encoded bytes.
+    Base64 { base64: String },
+}
+
+impl Content {
+    /// Decode to bytes.
+    fn into_bytes(self) -> std::result::Result<Vec<u8>, String> {
+        match self {
+            Content::Text { content } => Ok(content.into_bytes()),
+            Content::Base64 { base64 } => data_encoding::BASE64
+                .decode(base64.as_bytes())
+                .map_err(|e| format!("invalid base64 content: {}", e)),
+        }
+    }
+}
+
+/// External plugin runs a user-supplied executable.
+pub struct ExternalPlugin {
+    name: String,
+    config: ExternalPluginConfig,
+    working_dir: Option<PathBuf>,
+}
+
+impl ExternalPlugin {
+    /// Create a new external plugin.
+    pub fn new(name: impl Into<String>, config: ExternalPluginConfig) -> Self {
+        Self {
+            name: name.into(),
+            config,
+            working_dir: None,
+        }
+    }
+
+    /// Run the executable in `dir` instead of the current directory.
+    pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Error for this plugin.
+    fn error(&self, reason: impl Into<String>) -> PluginError {
+        PluginError::ExternalFailed {
+            plugin: self.name.clone(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Describe the build for the plugin.
+    pub fn request<'a>(&'a self, ctx: &'a BuildContext) -> Request<'a> {
+        let build_dir = ctx.build_dir();
+        let relative = |path: &Path| {
+            to_url_path(
+                pathdiff::diff_paths(path, build_dir)
+                    .as_deref()
+                    .unwrap_or(path),
+            )
+        };
+        let originals = ManifestPlugin::original_paths(ctx);
+
+        let mut files: Vec<RequestFile> = ctx
+            .files()
+            .map(|f| RequestFile {
+                path: to_url_path(&f.relative),
+                original: to_url_path(originals.get(&f.relative).unwrap_or(&f.relative)),
+                size: f.size,
+            })
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let dependencies = ctx
+            .files()
+            .filter_map(|f| {
+                let mut deps: Vec<String> = ctx
+                    .get_dependencies(&f.absolute)?
+                    .iter()
+                    .map(|d| relative(d))
+                    .collect();
+                deps.sort();
+                Some((to_url_path(&f.relative), deps))
+            })
+            .collect();
+
+        Request {
+            protocol: PROTOCOL_VERSION,
+            plugin: &self.name,
+            build_dir,
+            options: &self.config.options,
+            files,
+            file_mapping: ctx
+                .file_mapping()
+                .iter()
+                .map(|(old, new)| (to_url_path(old), to_url_path(new)))
+                .collect(),
+            chunks: ctx
+                .chunks()
+                .iter()
+                .map(|(parent, chunks)| {
+                    (
+                        relative(parent),
+                        chunks.iter().map(|c| relative(c)).collect(),
+                    )
+                })
+                .collect(),
+            dependencies,
+        }
+    }
+
+    /// Run the executable with a request and parse its response.
+    async fn run(&self, request: Vec<u8>) -> Result<Response> {
+        let mut command = tokio::process::Command::new(&self.config.command);
+        command
+            .args(&self.config.args)
+            .envs(&self.config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| self.error(format!("failed to start '{}': {}", self.config.command, e)))?;
+
+        // Write the request concurrently, so a plugin that replies before
+        // reading all of stdin cannot deadlock
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let writer = tokio::spawn(async move {
+            let _ = stdin.write_all(&request).await;
+        });
+
+        let output = child.wait_with_output();
+        let output = if self.config.timeout_secs > 0 {
+            tokio::time::timeout(Duration::from_secs(self.config.timeout_secs), output)
+                .await
+                .map_err(|_| self.error(format!("timed out after {}s", self.config.timeout_secs)))?
+        } else {
+            output.await
+        }
+        .map_err(|e| self.error(e.to_string()))?;
+        let _ = writer.await;
+
+        if !output.status.success() {
+            return Err(self.error(format!("exited with {}", output.status)));
+        }
+
+        if output.stdout.iter().all(|b| b.is_ascii_whitespace()) {
+            return Ok(Response::default());
+        }
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| self.error(format!("invalid response: {}", e)))
+    }
+
+    /// Resolve a plugin-supplied path inside the build directory.
+    fn resolve(&self, ctx: &BuildContext, path: &str) -> Result<(PathBuf, PathBuf)> {
+        let relative = PathBuf::from(path);
+        let inside = relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if !inside || path.is_empty() {
+            return Err(self.error(format!("path '{}' is outside the build directory", path)));
+        }
+        Ok((ctx.build_dir().join(&relative), relative))
+    }
+
+    /// Apply one operation to the build.
+    fn apply(&self, ctx: &mut BuildContext, operation: Operation) -> Result<()> {
+        match operation {
+            Operation::Write { path, content } => {
+                let (absolute, _) = self.resolve(ctx, &path)?;
+                let bytes = content.into_bytes().map_err(|e| self.error(e))?;
+                let file = ctx
+                    .get_file_mut(&absolute)
+                    .ok_or_else(|| self.error(format!("cannot write unknown file '{}'", path)))?;
+                chrysalis_core::write_file_content(&absolute, &bytes)?;
+                file.set_content(bytes);
+                debug!("  Wrote {}", path);
+            }
+            Operation::Add { path, content } => {
+                let (absolute, relative) = self.resolve(ctx, &path)?;
+                if ctx.get_file(&absolute).is_some() {
+                    return Err(self.error(format!("cannot add existing file '{}'", path)));
+                }
+                let bytes = content.into_bytes().map_err(|e| self.error(e))?;
+                if let Some(parent) = absolute.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                chrysalis_core::write_file_content(&absolute, &bytes)?;
+
+                let mut file = FileInfo::new(absolute, relative, bytes.len() as u64);
+                file.set_content(bytes);
+                ctx.add_file(file)?;
+                debug!("  Added {}", path);
+            }
+            Operation::Rename { from, to } => {
+                let (old_path, _) = self.resolve(ctx, &from)?;
+                let (new_path, _) = self.resolve(ctx, &to)?;
+                if ctx.get_file(&old_path).is_none() {
+                    return Err(self.error(format!("cannot rename unknown file '{}'", from)));
+                }
+                if ctx.get_file(&new_path).is_some() {
+                    return Err(self.error(format!("cannot rename onto existing file '{}'", to)));
+                }
+                ctx.rename_file(&old_path, &new_path)?;
+                debug!("  Renamed {} -> {}", from, to);
+            }
+            Operation::Remove { path } => {
+                let (absolute, _) = self.resolve(ctx, &path)?;
+                if ctx.get_file(&absolute).is_none() {
+                    return Err(self.error(format!("cannot remove unknown file '{}'", path)));
+                }
+                // Unlink first, so a failure leaves the context matching the disk
+                std::fs::remove_file(&absolute)?;
+                ctx.remove_file(&absolute);
+                debug!("  Removed {}", path);
+            }
+            Operation::AddDependency { file, dependency } => {
+                let (file, _) = self.resolve(ctx, &file)?;
+                let (dependency, _) = self.resolve(ctx, &dependency)?;
+                ctx.add_dependency(file, dependency);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Plugin for ExternalPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn execute(&self, ctx: &mut BuildContext) -> Result<()> {
+        info!("Running external plugin: {}", self.config.command);
+
+        let request = serde_json::to_vec(&self.request(ctx))
+            .map_err(|e| self.error(format!("failed to serialize request: {}", e)))?;
+        let response = self.run(request).await?;
+
+        let count = response.operations.len();
+        for operation in response.operations {
+            self.apply(ctx, operation)?;
+        }
+
+        info!(
+            "✓ External plugin '{}' applied {} operations",
+            self.name, count
+        );
+        Ok(())
+    }
+
+    fn plan(&self, _plan: &mut Plan) -> Result<()> {
+        info!(
+            "External plugin '{}' is not simulated in dry runs",
+            self.name
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn plugin() -> ExternalPlugin {
+        ExternalPlugin::new("test", ExternalPluginConfig::default())
+    }
+
+    fn context() -> (TempDir, BuildContext) {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("js")).unwrap();
+        fs::write(temp.path().join("index.html"), "<html></html>").unwrap();
+        fs::write(temp.path().join("js/app.js"), "app()").unwrap();
+        let mut ctx = BuildContext::new(temp.path(), vec![]).unwrap();
+        ctx.scan().unwrap();
+        (temp, ctx)
+    }
+
+    fn operations(json: &str) -> Vec<Operation> {
+        serde_json::from_str::<Response>(json).unwrap().operations
+    }
+
+    #[test]
+    fn test_request_shape() {
+        let (temp, mut ctx) = context();
+        ctx.rename_file(
+            temp.path().join("js/app.js"),
+            temp.path().join("js/app.1234.js"),
+        )
+        .unwrap();
+        ctx.add_dependency(
+            temp.path().join("index.html"),
+            temp.path().join("js/app.1234.js"),
+        );
+        let mut plugin = plugin();
+        plugin.config.options.insert("level".into(), 2.into());
+
+        let request = serde_json::to_value(plugin.request(&ctx)).unwrap();
+        assert_eq!(
+            request,
+            serde_json::json!({
+                "protocol": PROTOCOL_VERSION,
+                "plugin": "test",
+                "build_dir": temp.path(),
+                "options": {"level": 2},
+                "files": [
+                    {"path": "index.html", "original": "index.html", "size": 13},
+                    {"path": "js/app.1234.js", "original": "js/app.js", "size": 5},
+                ],
+                "file_mapping": {"js/app.js": "js/app.1234.js"},
+                "chunks": {},
+                "dependencies": {"index.html": ["js/app.1234.js"]},
+            })
+        );
+    }
+
+    #[test]
+    fn test_deserialize_operations() {
+        let operations = operations(
+            r#"{"operations": [
+                {"op": "write", "path": "a.js", "content": "text"},
+                {"op": "add", "path": "b.bin", "base64": "AAE="},
+                {"op": "rename", "from": "a.js", "to": "c.js"},
+                {"op": "remove", "path": "d.js"},
+                {"op": "add_dependency", "file": "a.js", "dependency": "b.bin"}
+            ]}"#,
+        );
+        assert!(matches!(
+            &operations[0],
+            Operation::Write { path, content: Content::Text { content } }
+                if path == "a.js" && content == "text"
+        ));
+        assert!(matches!(
+            &operations[1],
+            Operation::Add { path, content: Content::Base64 { base64 } }
+                if path == "b.bin" && base64 == "AAE="
+        ));
+        assert!(
+            matches!(&operations[2], Operation::Rename { from, to } if from == "a.js" && to == "c.js")
+        );
+        assert!(matches!(&operations[3], Operation::Remove { path } if path == "d.js"));
+        assert!(matches!(
+            &operations[4],
+            Operation::AddDependency { file, dependency } if file == "a.js" && dependency == "b.bin"
+        ));
+
+        // No operations, unknown ops and missing content
+        assert!(
+            serde_json::from_str::<Response>("{}")
+                .unwrap()
+                .operations
+                .is_empty()
+        );
+        assert!(serde_json::from_str::<Response>(r#"{"operations": [{"op": "copy"}]}"#).is_err());
+        assert!(
+            serde_json::from_str::<Response>(r#"{"operations": [{"op": "write", "path": "a"}]}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_content_decoding() {
+        let text = Content::Text {
+            content: "hé".into(),
+        };
+        assert_eq!(text.into_bytes().unwrap(), "hé".as_bytes());
+        let bytes = Content::Base64 {
+            base64: "AAH/".into(),
+        };
+        assert_eq!(bytes.into_bytes().unwrap(), [0, 1, 255]);
+        let invalid = Content::Base64 {
+            base64: "not base64!".into(),
+        };
+        assert!(invalid.into_bytes().unwrap_err().contains("invalid base64"));
+    }
+
+    #[test]
+    fn test_paths_outside_the_build_are_rejected() {
+        let (temp, mut ctx) = context();
+        let outside = temp.path().parent().unwrap().join("outside.js");
+        for path in [
+            "../outside.js",
+            "js/../../outside.js",
+            "",
+            outside.to_str().unwrap(),
+        ] {
+            let operation = Operation::Add {
+                path: path.to_string(),
+                content: Content::Text {
+                    content: "x".into(),
+                },
+            };
+            let error = plugin().apply(&mut ctx, operation).unwrap_err();
+            assert!(
+                error.to_string().contains("outside the build directory"),
+                "{}",
+                path
+            );
+        }
+        assert!(!outside.exists());
+
+        let operation = Operation::Remove {
+            path: "/etc/passwd".into(),
+        };
+        assert!(plugin().apply(&mut ctx, operation).is_err());
+    }
+
+    #[test]
+    fn test_apply_operations() {
+        let (temp, mut ctx) = context();
+        let root = temp.path();
+        let plugin = plugin();
+        let json = r#"{"operations": [
+            {"op": "write", "path": "index.html", "content": "<html>new</html>"},
+            {"op": "add", "path": "img/dot.bin", "base64": "AAE="},
+            {"op": "rename", "from": "js/app.js", "to": "js/app.v2.js"},
+            {"op": "add_dependency", "file": "index.html", "dependency": "js/app.v2.js"},
+            {"op": "remove", "path": "img/dot.bin"}
+        ]}"#;
+        for operation in operations(json) {
+            plugin.apply(&mut ctx, operation).unwrap();
+        }
+
+        assert_eq!(
+            fs::read_to_string(root.join("index.html")).unwrap(),
+            "<html>new</html>"
+        );
+        let index = ctx.get_file(root.join("index.html")).unwrap();
+        assert_eq!(index.size, 16);
+        assert!(root.join("js/app.v2.js").is_file() && !root.join("js/app.js").exists());
+        assert_eq!(
+            ctx.file_mapping().get(Path::new("js/app.js")),
+            Some(&PathBuf::from("js/app.v2.js"))
+        );
+        assert!(
+            ctx.get_dependencies(root.join("index.html"))
+                .unwrap()
+                .contains(&root.join("js/app.v2.js"))
+        );
+        assert!(!root.join("img/dot.bin").exists());
+        assert!(ctx.get_file(root.join("img/dot.bin")).is_none());
+    }
+
+    #[test]
+    fn test_invalid_operations() {
+        let (_temp, mut ctx) = context();
+        let plugin = plugin();
+        for (json, reason) in [
+            (
+                r#"{"op": "write", "path": "missing.js", "content": ""}"#,
+                "unknown file",
+            ),
+            (
+                r#"{"op": "add", "path": "index.html", "content": ""}"#,
+                "existing file",
+            ),
+            (
+                r#"{"op": "rename", "from": "missing.js", "to": "a.js"}"#,
+                "unknown file",
+            ),
+            (
+                r#"{"op": "rename", "from": "js/app.js", "to": "index.html"}"#,
+                "onto existing file",
+            ),
+            (r#"{"op": "remove", "path": "missing.js"}"#, "unknown file"),
+        ] {
+            let operation = serde_json::from_str(json).unwrap();
+            let error = plugin.apply(&mut ctx, operation).unwrap_err();
+            assert!(error.to_string().contains(reason), "{}: {}", json, error);
+        }
+
+        // Failed operations leave files alone
+        let index = ctx.build_dir().join("index.html");
+        assert_eq!(fs::read_to_string(&index).unwrap(), "<html></html>");
+        assert!(ctx.get_file(ctx.build_dir().join("js/app.js")).is_some());
+    }
+
+    #[test]
+    fn test_failed_remove_keeps_the_file() {
+        let (temp, mut ctx) = context();
+        let path = temp.path().join("js/app.js");
+        // Deleted behind the context's back: the unlink fails
+        fs::remove_file(&path).unwrap();
+        let operation = Operation::Remove {
+            path: "js/app.js".into(),
+        };
+        assert!(plugin().apply(&mut ctx, operation).is_err());
+        assert!(ctx.get_file(&path).is_some());
+    }
+}
@@ -1,15 +1,18 @@
 //! Hashing plugin for content-based filenames.
 
-use crate::{Plugin, Result};
+use crate::references::{Resolver, chunk_groups, group_chunks, has_references, update_references};
+use crate::{Plan, PlannedFile, Plugin, PluginError, Result};
 use chrysalis_config::HashConfig;
-use chrysalis_core::{BuildContext, FileNaming};
+use chrysalis_core::{BuildContext, FileInfo, FileNaming, HashScheme};
 use glob::Pattern;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
 /// Hash plugin adds content hashes to filenames.
 pub struct HashPlugin {
     config: HashConfig,
-    hash_length: usize,
+    naming: FileNaming,
     include_patterns: Vec<Pattern>,
     exclude_patterns: Vec<Pattern>,
 }
@@ -31,11 +34,12 @@ impl HashPlugin {
             .collect::<std::result::Result<Vec<_>, _>>()
             .map_err(|e| anyhow::anyhow!("Invalid exclude pattern: {}", e))?;
 
-        let hash_length = config.hash_length;
+        let naming =
+            FileNaming::new(HashScheme::from(&config)).with_file_name(&config.file_name)?;
 
         Ok(Self {
             config,
-            hash_length,
+            naming,
             include_patterns,
             exclude_patterns,
         })
@@ -68,66 +72,124 @@ impl HashPlugin {
         false
     }
 
-    /// Replace file references in content using the file mapping.
-    fn replace_references(&self, content: &str, ctx: &BuildContext) -> String {
-        let mut result = content.to_string();
-
-        // Get file mapping (old relative path -> new relative path)
-        let file_mapping = ctx.file_mapping();
-
-        // Sort by length (longest first) to avoid partial replacements
-        let mut mappings: Vec<_> = file_mapping.iter().collect();
-        mappings.sort_by(|a, b| b.0.as_os_str().len().cmp(&a.0.as_os_str().len()));
-
-        for (old_path, new_path) in mappings {
-            let old_str = old_path.to_string_lossy();
-            let new_str = new_path.to_string_lossy();
-
-            // Extract just the filename for both
-            let old_filename = old_path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_default();
-            let new_filename = new_path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_default();
-
-            if old_filename == new_filename {
-                continue;
+    /// Group files into levels, each file after the hashed files it references.
+    ///
+    /// Processing the levels in order hashes every file after the references
+    /// in it point to the final names of its dependencies.
+    fn hash_levels(&self, ctx: &BuildContext) -> Result<Vec<Vec<PathBuf>>> {
+        let hashed: HashSet<&Path> = ctx
+            .files()
+            .filter(|f| self.should_hash(&f.relative))
+            .map(|f| f.absolute.as_path())
+            .collect();
+
+        // Only references to renamed files constrain the order
+        let mut pending: BTreeMap<PathBuf, BTreeSet<PathBuf>> = ctx
+            .files()
+            .map(|f| {
+                let dependencies = ctx
+                    .get_dependencies(&f.absolute)
+                    .into_iter()
+                    .flatten()
+                    .filter(|d| **d != f.absolute && hashed.contains(d.as_path()))
+                    .cloned()
+                    .collect();
+                (f.absolute.clone(), dependencies)
+            })
+            .collect();
+
+        let mut levels = Vec::new();
+        while !pending.is_empty() {
+            let ready: Vec<PathBuf> = pending
+                .iter()
+                .filter(|(_, deps)| deps.iter().all(|d| !pending.contains_key(d)))
+                .map(|(file, _)| file.clone())
+                .collect();
+            if ready.is_empty() {
+                return Err(Self::cycle_error(ctx, &pending));
             }
 
-            // Replace patterns:
-            // 1. Quoted filename: "main.dart.js" -> "main.dart.abc123.js"
-            result = result.replace(
-                &format!("\"{}\"", old_filename),
-                &format!("\"{}\"", new_filename),
-            );
-            result = result.replace(
-                &format!("'{}'", old_filename),
-                &format!("'{}'", new_filename),
-            );
-            result = result.replace(
-                &format!("`{}`", old_filename),
-                &format!("`{}`", new_filename),
-            );
-
-            // 2. Quoted full path
-            result = result.replace(&format!("\"{}\"", old_str), &format!("\"{}\"", new_str));
-            result = result.replace(&format!("'{}'", old_str), &format!("'{}'", new_str));
-
-            // 3. src/href attributes: src=filename or src="filename"
-            result = result.replace(
-                &format!("src={}", old_filename),
-                &format!("src={}", new_filename),
-            );
-            result = result.replace(
-                &format!("href={}", old_filename),
-                &format!("href={}", new_filename),
-            );
+            for file in &ready {
+                pending.remove(file);
+            }
+            levels.push(ready);
         }
 
-        result
+        Ok(levels)
+    }
+
+    /// Fail if a different content already got the same hash in this build.
+    ///
+    /// Identical files share a hash; different contents sharing one means
+    /// the hash is too short to tell them apart.
+    fn check_collision(
+        &self,
+        hashes: &mut HashMap<String, (PathBuf, String)>,
+        relative: PathBuf,
+        hash: String,
+        full_hash: String,
+    ) -> Result<()> {
+        if let Some((other, other_full_hash)) = hashes.get(&hash) {
+            if *other_full_hash == full_hash {
+                return Ok(());
+            }
+            return Err(PluginError::HashingFailed {
+                file: relative,
+                reason: format!(
+                    "hash collision with {} ({}); increase the hash length (currently {})",
+                    other.display(),
+                    hash,
+                    self.naming.scheme().length
+                ),
+            });
+        }
+
+        hashes.insert(hash, (relative, full_hash));
+        Ok(())
+    }
+
+    /// Whether the file at `path` was hashed in this build to `full_hash`.
+    fn is_identical(&self, ctx: &BuildContext, path: &Path, full_hash: &str) -> bool {
+        ctx.get_file(path)
+            .and_then(|f| f.content.as_deref())
+            .is_some_and(|content| self.naming.scheme().full_hash(content) == full_hash)
+    }
+
+    /// Describe one reference cycle among files that could not be ordered.
+    fn cycle_error(
+        ctx: &BuildContext,
+        pending: &BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    ) -> PluginError {
+        let relative = |path: &Path| {
+            pathdiff::diff_paths(path, ctx.build_dir()).unwrap_or_else(|| path.to_path_buf())
+        };
+
+        // Every pending file references another pending file, so this walk
+        // must come back to a file it has seen
+        let mut path: Vec<&PathBuf> = Vec::new();
+        let mut current = pending.keys().next().expect("pending files");
+        while !path.contains(&current) {
+            path.push(current);
+            current = pending[current]
+                .iter()
+                .find(|d| pending.contains_key(*d))
+                .expect("pending dependency");
+        }
+
+        let start = path.iter().position(|p| *p == current).unwrap_or(0);
+        let cycle: Vec<String> = path[start..]
+            .iter()
+            .chain(std::iter::once(&current))
+            .map(|p| relative(p).display().to_string())
+            .collect();
+
+        PluginError::HashingFailed {
+            file: relative(current),
+            reason: format!(
+                "cyclic references ({}); content hashes of files that reference each other cannot be computed",
+                cycle.join(" -> ")
+            ),
+        }
     }
 }
 
@@ -137,6 +199,11 @@ impl Plugin for HashPlugin {
         "hash"
     }
 
+    fn runs_after(&self) -> &[&str] {
+        // Stub and chunks are hashed together, after their final minification
+        &["minify", "chunk"]
+    }
+
     async fn execute(&self, ctx: &mut BuildContext) -> Result<()> {
         if !self.config.enabled {
             info!("Hashing disabled");
@@ -144,86 +211,125 @@ impl Plugin for HashPlugin {
         }
 
         info!("Adding content hashes to filenames...");
-        let hash_length = self.hash_length;
 
-        // Phase 1: Rename files with hash suffix
-        info!("  Phase 1: Adding hash suffixes...");
-        let files_to_hash: Vec<_> = ctx
-            .files()
-            .filter(|f| self.should_hash(&f.relative))
-            .map(|f| f.absolute.clone())
-            .collect();
+        // Phase 1: Record the reference graph
+        info!("  Phase 1: Finding file references...");
+        update_references(ctx)?;
+        let levels = self.hash_levels(ctx)?;
+
+        // Phase 2: Rewrite references, then hash and rename, level by level
+        info!(
+            "  Phase 2: Hashing in dependency order ({} levels)...",
+            levels.len()
+        );
+        let workers = ctx.workers()?.clone();
+        let build_dir = ctx.build_dir().to_path_buf();
+        let mut updated_count = 0;
+        let mut hashes: HashMap<String, (PathBuf, String)> = HashMap::new();
+        let groups = chunk_groups(ctx);
+        let chunk_paths: HashSet<PathBuf> = ctx.chunks().values().flatten().cloned().collect();
 
-        for file_path in files_to_hash {
-            // Load content and calculate hash
-            let (new_path, file_name) = {
-                let file = ctx.get_file_mut(&file_path).unwrap();
+        for level in levels {
+            let resolver = Resolver::new(ctx);
+            let level: HashSet<PathBuf> = level.into_iter().collect();
 
-                // Load content for hashing
-                if let Err(e) = file.load_content() {
-                    warn!("Failed to load {}: {}", file.name, e);
+            // Chunks are rewritten together, from where their stub moves to
+            for group in &groups {
+                if !group.chunks.iter().any(|c| level.contains(c)) {
                     continue;
                 }
-
-                let content = file.content.as_ref().unwrap();
-                let hash = chrysalis_core::calculate_hash(content, hash_length);
-
-                // Generate new filename
-                let new_name = FileNaming::add_hash(&file.name, &hash);
-                let new_path = file.absolute.parent().unwrap().join(&new_name);
-
-                (new_path, file.name.clone())
-            };
-
-            // Rename file
-            if let Err(e) = ctx.rename_file(&file_path, &new_path) {
-                warn!("Failed to rename {}: {}", file_name, e);
-                continue;
+                let dir = if self.should_hash(&group.parent.relative) {
+                    self.naming.hashed_dir(&group.parent.relative)
+                } else {
+                    group.parent.dir.clone()
+                };
+                let mut chunks = group_chunks(ctx.files_mut(), group);
+                if resolver
+                    .update_chunks_from(&group.parent, &mut chunks, &dir)?
+                    .0
+                {
+                    updated_count += 1;
+                }
             }
 
-            ctx.stats_mut().record_hash();
-        }
-
-        info!("  ✓ Renamed {} files", ctx.stats().hashed_files);
-
-        // Phase 2: Update references in text files
-        info!("  Phase 2: Updating file references...");
-        let text_files: Vec<_> = ctx
-            .files()
-            .filter(|f| f.is_js() || f.is_html() || f.is_css() || f.is_json())
-            .map(|f| f.absolute.clone())
-            .collect();
-
-        let mut updated_count = 0;
-        for file_path in text_files {
-            // Load content first
-            let content = {
-                let file = ctx.get_file_mut(&file_path).unwrap();
+            let mut files: Vec<&mut FileInfo> = ctx
+                .files_mut()
+                .filter(|f| level.contains(&f.absolute))
+                .collect();
+            files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+            // Hashes are computed on the worker pool, renames applied in order
+            let results = workers.map(files, |file| -> Result<_> {
+                // Hashed files may move, so their references are written
+                // from the directory they move to
+                let hashed = self.should_hash(&file.relative);
+                let dir = if hashed {
+                    self.naming.hashed_dir(&file.relative)
+                } else {
+                    file.dir.clone()
+                };
+                let updated = has_references(file)
+                    && !chunk_paths.contains(&file.absolute)
+                    && resolver.update_file_from(file, &dir)?.0;
+                if !hashed {
+                    return Ok((updated, None));
+                }
 
-                // Load content
+                // Load content for hashing
                 if let Err(e) = file.load_content() {
                     warn!("Failed to load {}: {}", file.name, e);
-                    continue;
+                    return Ok((updated, None));
                 }
 
-                match file.content_as_str() {
-                    Some(s) => s.to_string(),
-                    None => continue,
+                let content = file.content.as_ref().unwrap();
+                let scheme = self.naming.scheme();
+                let full_hash = scheme.full_hash(content);
+                let hash = full_hash[..scheme.length.min(full_hash.len())].to_string();
+
+                // Generate new path
+                let new_path = build_dir.join(self.naming.hashed_path(&file.relative, &hash));
+
+                Ok((
+                    updated,
+                    Some((
+                        file.absolute.clone(),
+                        new_path,
+                        file.name.clone(),
+                        (file.relative.clone(), hash, full_hash),
+                    )),
+                ))
+            });
+
+            for result in results {
+                let (updated, rename) = result?;
+                if updated {
+                    updated_count += 1;
+                }
+                let Some((file_path, new_path, file_name, (relative, hash, full_hash))) = rename
+                else {
+                    continue;
+                };
+                self.check_collision(&mut hashes, relative.clone(), hash, full_hash.clone())?;
+                if new_path.exists() {
+                    // Name templates without [dir] give identical files one name
+                    if self.is_identical(ctx, &new_path, &full_hash) {
+                        info!("  {} is identical to {}", file_name, new_path.display());
+                        ctx.merge_file(&file_path, &new_path)?;
+                        continue;
+                    }
+                    return Err(PluginError::HashingFailed {
+                        file: relative,
+                        reason: format!("{} already exists", new_path.display()),
+                    });
                 }
-            };
-
-            // Replace references (now ctx is not borrowed)
-            let new_content = self.replace_references(&content, ctx);
-
-            if new_content != content {
-                let new_bytes = new_content.into_bytes();
 
-                // Write back
-                chrysalis_core::write_file_content(&file_path, &new_bytes)?;
+                // Rename file
+                if let Err(e) = ctx.rename_file(&file_path, &new_path) {
+                    warn!("Failed to rename {}: {}", file_name, e);
+                    continue;
+                }
 
-                let file = ctx.get_file_mut(&file_path).unwrap();
-                file.set_content(new_bytes);
-                updated_count += 1;
+                ctx.stats_mut().record_hash();
             }
         }
 
@@ -231,4 +337,86 @@ impl Plugin for HashPlugin {
         info!("✓ Hashed {} files", ctx.stats().hashed_files);
         Ok(())
     }
+
+    fn plan(&self, plan: &mut Plan) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let build_dir = plan.build_dir.clone();
+        let workers = plan.workers().clone();
+        let files: Vec<&mut PlannedFile> = plan
+            .files_mut()
+            .filter(|p| self.should_hash(&p.file.relative))
+            .collect();
+
+        workers.map(files, |planned| {
+            if let Err(e) = planned.file.load_content() {
+                warn!("Failed to load {}: {}", planned.file.name, e);
+                return;
+            }
+
+            let content = planned.file.content.as_ref().unwrap();
+            let hash = self.naming.scheme().hash(content);
+            let relative = self.naming.hashed_path(&planned.file.relative, &hash);
+
+            planned.rename(&build_dir, relative);
+            planned.hash = true;
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_identical_files_share_a_hashed_path() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("js/a")).unwrap();
+        fs::create_dir_all(root.join("js/b")).unwrap();
+        fs::write(root.join("js/a/util.js"), "console.log(1);").unwrap();
+        fs::write(root.join("js/b/util.js"), "console.log(1);").unwrap();
+        fs::write(
+            root.join("index.html"),
+            r#"<script src="js/a/util.js"></script><script src="js/b/util.js"></script>"#,
+        )
+        .unwrap();
+
+        let mut ctx = BuildContext::new(root, vec![]).unwrap();
+        ctx.scan().unwrap();
+        let config = HashConfig {
+            file_name: "static/[hash].[ext]".to_string(),
+            ..HashConfig::default()
+        };
+        HashPlugin::new(config)
+            .unwrap()
+            .execute(&mut ctx)
+            .await
+            .unwrap();
+
+        let hashed: Vec<PathBuf> = fs::read_dir(root.join("static"))
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(hashed.len(), 1);
+        assert!(!root.join("js/a/util.js").exists());
+        assert!(!root.join("js/b/util.js").exists());
+
+        // Both names map to the kept file, and both references point to it
+        let kept = pathdiff::diff_paths(&hashed[0], root).unwrap();
+        assert_eq!(ctx.file_mapping()[Path::new("js/a/util.js")], kept);
+        assert_eq!(ctx.file_mapping()[Path::new("js/b/util.js")], kept);
+        let html = fs::read_to_string(root.join("index.html")).unwrap();
+        let url = kept.to_string_lossy().replace('\\', "/");
+        assert_eq!(
+            html,
+            format!(r#"<script src="{url}"></script><script src="{url}"></script>"#)
+        );
+    }
 }
@@ -0,0 +1,383 @@
+//! HTML tokenizing with lol_html.
+//!
+//! Documents are tokenized once into start tags, end tags and raw text
+//! (script and style contents) with their byte ranges, so callers can read
+//! and splice the original text without re-serializing it. Comments,
+//! `<!-- </head> -->` included, never produce tags.
+
+use crate::{PluginError, Result};
+use chrysalis_config::InjectPosition;
+use lol_html::html_content::{Element as LolElement, TextChunk};
+use lol_html::{HtmlRewriter, Selector, Settings, element, text};
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// A start tag and, for raw text elements, the range of their content.
+#[derive(Debug, Clone)]
+pub(crate) struct Element {
+    /// Lowercased tag name.
+    pub name: String,
+
+    /// Byte range of the start tag, `<` to `>` included.
+    pub tag: Range<usize>,
+
+    /// Byte range of the content of `<script>` and `<style>` elements.
+    pub content: Option<Range<usize>>,
+
+    /// Attributes: lowercased name and value as written, without quotes.
+    pub attributes: Vec<(String, String)>,
+}
+
+impl Element {
+    /// Text of the start tag without the closing `>` (or `/>`).
+    pub fn tag_text<'a>(&self, html: &'a str) -> &'a str {
+        let tag = &html[self.tag.clone()];
+        let tag = tag.strip_suffix('>').unwrap_or(tag);
+        tag.strip_suffix('/').unwrap_or(tag)
+    }
+
+    /// Value of the attribute `name`, if present.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A tokenized HTML document.
+#[derive(Debug, Default)]
+pub(crate) struct Document {
+    /// Start tags in document order.
+    pub elements: Vec<Element>,
+
+    /// End tags in document order: lowercased name and byte range.
+    pub end_tags: Vec<(String, Range<usize>)>,
+}
+
+/// Map lol_html errors to plugin errors.
+fn html_error(e: impl std::fmt::Display) -> PluginError {
+    PluginError::InjectionFailed(format!("Failed to parse HTML: {}", e))
+}
+
+/// Run lol_html over `html` with the given handlers, discarding the output.
+fn tokenize(html: &str, settings: Settings<'_, '_>) -> Result<()> {
+    let mut rewriter = HtmlRewriter::new(settings, |_: &[u8]| {});
+    rewriter.write(html.as_bytes()).map_err(html_error)?;
+    rewriter.end().map_err(html_error)
+}
+
+impl Document {
+    /// Tokenize a document.
+    pub fn parse(html: &str) -> Result<Self> {
+        let document = Rc::new(RefCell::new(Document::default()));
+
+        let on_element = {
+            let document = document.clone();
+            move |el: &mut LolElement| {
+                let name = el.tag_name();
+                let content = matches!(name.as_str(), "script" | "style")
+                    .then(|| el.source_location().bytes().end)
+                    .map(|end| end..end);
+                let attributes = el
+                    .attributes()
+                    .iter()
+                    .map(|a| (a.name(), a.value()))
+                    .collect();
+                document.borrow_mut().elements.push(Element {
+                    name,
+                    tag: el.source_location().bytes(),
+                    content,
+                    attributes,
+                });
+
+                if let Some(handlers) = el.end_tag_handlers() {
+                    let document = document.clone();
+                    handlers.push(Box::new(move |end| {
+                        document
+                            .borrow_mut()
+                            .end_tags
+                            .push((end.name(), end.source_location().bytes()));
+                        Ok(())
+                    }));
+                }
+                Ok(())
+            }
+        };
+
+        // Raw text belongs to the last script or style start tag
+        let on_text = {
+            let document = document.clone();
+            move |chunk: &mut TextChunk| {
+                let range = chunk.source_location().bytes();
+                let mut document = document.borrow_mut();
+                if let Some(content) = document
+                    .elements
+                    .iter_mut()
+                    .rev()
+                    .find_map(|e| e.content.as_mut())
+                    && !range.is_empty()
+                {
+                    if content.start == content.end {
+                        *content = range;
+                    } else {
+                        content.end = range.end;
+                    }
+                }
+                Ok(())
+            }
+        };
+
+        tokenize(
+            html,
+            Settings {
+                element_content_handlers: vec![
+                    element!("*", on_element),
+                    text!("script, style", on_text),
+                ],
+                strict: false,
+                ..Settings::new()
+            },
+        )?;
+
+        Ok(Rc::try_unwrap(document)
+            .map(RefCell::into_inner)
+            .unwrap_or_default())
+    }
+
+    /// First start tag named `name`.
+    pub fn first(&self, name: &str) -> Option<&Element> {
+        self.elements.iter().find(|e| e.name == name)
+    }
+
+    /// Byte range of the first end tag named `name`.
+    pub fn end_tag(&self, name: &str) -> Option<Range<usize>> {
+        self.end_tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, range)| range.clone())
+    }
+}
+
+/// Parse a CSS selector as supported by lol_html.
+pub(crate) fn parse_selector(selector: &str) -> Result<Selector> {
+    Selector::from_str(selector).map_err(|e| {
+        PluginError::InjectionFailed(format!("Invalid selector '{}': {}", selector, e))
+    })
+}
+
+/// Byte offset of the start tag of the first element matching `selector`.
+pub(crate) fn select_first(html: &str, selector: &str) -> Result<Option<usize>> {
+    let selector = parse_selector(selector)?;
+    let found = Rc::new(RefCell::new(None));
+
+    let on_match = {
+        let found = found.clone();
+        move |el: &mut LolElement| {
+            found
+                .borrow_mut()
+                .get_or_insert(el.source_location().bytes().start);
+            Ok(())
+        }
+    };
+    tokenize(
+        html,
+        Settings {
+            element_content_handlers: vec![(
+                std::borrow::Cow::Owned(selector),
+                lol_html::ElementContentHandlers::default().element(on_match),
+            )],
+            strict: false,
+            ..Settings::new()
+        },
+    )?;
+
+    Ok(*found.borrow())
+}
+
+/// Byte offset where content goes for an injection position.
+///
+/// Missing elements fall back in turn: head start to body start, head end to
+/// before `<body>` and then body start, body start to head end; the document
+/// start is the last resort. A selector matching nothing falls back to head
+/// end.
+pub(crate) fn insertion_point(
+    html: &str,
+    position: InjectPosition,
+    selector: Option<&str>,
+) -> Result<usize> {
+    let document = Document::parse(html)?;
+    let after = |name: &str| document.first(name).map(|e| e.tag.end);
+    let before = |name: &str| document.first(name).map(|e| e.tag.start);
+    let head_end = || document.end_tag("head").map(|r| r.start);
+
+    let point = match position {
+        InjectPosition::HeadStart => after("head").or_else(|| after("body")),
+        InjectPosition::HeadEnd => head_end()
+            .or_else(|| before("body"))
+            .or_else(|| after("body")),
+        InjectPosition::BodyStart => after("body").or_else(head_end),
+        InjectPosition::Before => {
+            let selector = selector.unwrap_or_default();
+            match select_first(html, selector)? {
+                Some(point) => Some(point),
+                None => {
+                    tracing::warn!("No element matches '{}'; injecting at head end", selector);
+                    return insertion_point(html, InjectPosition::HeadEnd, None);
+                }
+            }
+        }
+    };
+
+    Ok(point.unwrap_or_else(|| after("html").unwrap_or(0)))
+}
+
+/// Insert `content` at byte offset `point`.
+pub(crate) fn insert_at(html: &str, point: usize, content: &str) -> String {
+    let mut result = String::with_capacity(html.len() + content.len());
+    result.push_str(&html[..point]);
+    result.push_str(content);
+    result.push_str(&html[point..]);
+    result
+}
+
+/// Set attributes on the start tags of elements matching `selector`.
+///
+/// `attributes` returns the attributes to add for an element given its
+/// current attributes; tags it returns nothing for are left byte for byte.
+pub(crate) fn set_attributes<F>(html: &str, selector: &str, mut attributes: F) -> Result<String>
+where
+    F: FnMut(&dyn Fn(&str) -> Option<String>) -> Vec<(String, String)>,
+{
+    let selector = parse_selector(selector)?;
+    let mut output = Vec::with_capacity(html.len());
+    let mut rewriter = HtmlRewriter::new(
+        Settings {
+            element_content_handlers: vec![(
+                std::borrow::Cow::Owned(selector),
+                lol_html::ElementContentHandlers::default().element(|el: &mut LolElement| {
+                    let get = |name: &str| el.get_attribute(name);
+                    for (name, value) in attributes(&get) {
+                        el.set_attribute(&name, &value)?;
+                    }
+                    Ok(())
+                }),
+            )],
+            strict: false,
+            ..Settings::new()
+        },
+        |chunk: &[u8]| output.extend_from_slice(chunk),
+    );
+    rewriter.write(html.as_bytes()).map_err(html_error)?;
+    rewriter.end().map_err(html_error)?;
+
+    String::from_utf8(output).map_err(html_error)
+}
+
+/// Contents of inline `<script>` elements (those without `src`).
+pub(crate) fn inline_scripts(html: &str) -> Result<Vec<&str>> {
+    let document = Document::parse(html)?;
+    Ok(document
+        .elements
+        .iter()
+        .filter(|e| e.name == "script" && e.attribute("src").is_none())
+        .filter_map(|e| e.content.clone())
+        .map(|range| &html[range])
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text following the insertion point.
+    fn after(html: &str, position: InjectPosition, selector: Option<&str>) -> String {
+        let point = insertion_point(html, position, selector).unwrap();
+        html[point..].to_string()
+    }
+
+    #[test]
+    fn test_insertion_points() {
+        let html = "<html><head><title>t</title></head><body><div id=app></div></body></html>";
+        assert!(after(html, InjectPosition::HeadStart, None).starts_with("<title>"));
+        assert!(after(html, InjectPosition::HeadEnd, None).starts_with("</head>"));
+        assert!(after(html, InjectPosition::BodyStart, None).starts_with("<div"));
+        assert!(after(html, InjectPosition::Before, Some("#app")).starts_with("<div"));
+    }
+
+    #[test]
+    fn test_insertion_point_fallbacks() {
+        // No head: head positions move to the body
+        let html = "<html><body><p>x</p></body></html>";
+        assert!(after(html, InjectPosition::HeadStart, None).starts_with("<p>"));
+        assert!(after(html, InjectPosition::HeadEnd, None).starts_with("<body>"));
+
+        // No body: body start moves to head end
+        let html = "<html><head><title>t</title></head></html>";
+        assert!(after(html, InjectPosition::BodyStart, None).starts_with("</head>"));
+
+        // No selector match: head end
+        let html = "<head></head><body><p>x</p></body>";
+        assert!(after(html, InjectPosition::Before, Some("#missing")).starts_with("</head>"));
+
+        // Neither head nor body: after <html>, then the document start
+        let html = "<html><p>x</p></html>";
+        assert!(after(html, InjectPosition::HeadEnd, None).starts_with("<p>"));
+        assert_eq!(
+            insertion_point("<p>x</p>", InjectPosition::HeadEnd, None).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_insertion_point_ignores_comments() {
+        let html = "<HTML><Head><!-- </head> --><script>var s = '</head>';</script></HEAD>\
+                    <body></body></HTML>";
+        assert!(after(html, InjectPosition::HeadEnd, None).starts_with("</HEAD>"));
+        assert!(after(html, InjectPosition::HeadStart, None).starts_with("<!--"));
+    }
+
+    #[test]
+    fn test_invalid_selector() {
+        let html = "<head></head>";
+        assert!(insertion_point(html, InjectPosition::Before, Some("[[")).is_err());
+        assert!(set_attributes(html, "[[", |_| Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_set_attributes() {
+        let html = r#"<head><!-- <script src="c.js"> --><script src="a.js"></script>
+<SCRIPT SRC='b.js' defer></SCRIPT><script>inline()</script></head>"#;
+        let result = set_attributes(html, "script[src]", |get| match get("src").as_deref() {
+            Some("a.js") => vec![("integrity".to_string(), "sha384-x".to_string())],
+            _ => Vec::new(),
+        })
+        .unwrap();
+        assert_eq!(
+            result,
+            r#"<head><!-- <script src="c.js"> --><script src="a.js" integrity="sha384-x"></script>
+<SCRIPT SRC='b.js' defer></SCRIPT><script>inline()</script></head>"#
+        );
+    }
+
+    #[test]
+    fn test_inline_scripts() {
+        let html = r#"<script src="a.js"></script><script>one()</script>
+<!-- <script>commented()</script> --><Script type="module">two("</div>")</Script>
+<script SRC=b.js></script><script></script>"#;
+        assert_eq!(
+            inline_scripts(html).unwrap(),
+            ["one()", r#"two("</div>")"#, ""]
+        );
+    }
+
+    #[test]
+    fn test_document_attributes() {
+        let document = Document::parse(r#"<meta CharSet="utf-8"><a href=x&amp;y>"#).unwrap();
+        assert_eq!(document.elements[0].attribute("charset"), Some("utf-8"));
+        assert_eq!(document.elements[1].attribute("href"), Some("x&amp;y"));
+        assert_eq!(document.elements[1].attribute("src"), None);
+    }
+}
@@ -1,73 +1,433 @@
 //! Injection plugin for chunk loader.
 
-use crate::minify::minify_html;
-use crate::{Plugin, PluginError, Result};
-use chrysalis_config::InjectConfig;
-use chrysalis_core::BuildContext;
-use std::collections::HashMap;
+use crate::html;
+use crate::manifest::{ManifestPlugin, to_url_path};
+use crate::minify::{minify_html, minify_js};
+use crate::references::Resolver;
+use crate::{Plan, Plugin, PluginError, Result};
+use chrysalis_config::{
+    CspMode, FetchPriority, HashConfig, HintRel, InjectConfig, ResourceHintRule, ScriptExecution,
+    SriAlgorithm,
+};
+use chrysalis_core::{BuildContext, FileInfo, FileNaming, HashScheme};
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
+/// Directives browsers ignore when the policy is delivered via `<meta>`.
+const META_IGNORED_DIRECTIVES: &[&str] = &["frame-ancestors", "report-uri", "sandbox"];
+
+/// Name of the loader runtime file when the manifest is not inlined, before
+/// hashing.
+const LOADER_FILE_NAME: &str = "chrysalis-loader.js";
+
+/// Name of the chunk manifest file when it is not inlined, before hashing.
+const MANIFEST_FILE_NAME: &str = "chunk-manifest.json";
+
 /// Chunk loader template - based on JS version's approach.
 const CHUNK_LOADER_TEMPLATE: &str = r#"
 (function() {
   'use strict';
 
-  // Chunk manifest
-  const MANIFEST = {{manifest}};
+  // Chunk manifest: original file path -> chunks ({ url, size, integrity }); empty
+  // until loaded when it is a separate file
+  const MANIFEST = {{manifest}} || {};
+  // Separate manifest file named by the loader's <script> tag, if any
+  const SCRIPT = document.currentScript;
+  const MANIFEST_FILE = SCRIPT && SCRIPT.dataset.manifest ? {
+    url: SCRIPT.dataset.manifest,
+    integrity: SCRIPT.dataset.manifestIntegrity,
+  } : null;
+  // Loading options
+  const OPTIONS = {{options}};
+  const DIGEST_ALGORITHMS = { sha256: 'SHA-256', sha384: 'SHA-384', sha512: 'SHA-512' };
   const BASE_URL = window.location.origin + window.location.pathname.replace(/\/[^\/]*$/, '/');
+  // Content types of chunked files served through fetch
+  const CONTENT_TYPES = { wasm: 'application/wasm', json: 'application/json' };
+  // Base URLs, tried in turn on retries
+  const ORIGINS = [BASE_URL].concat(OPTIONS.fallbackOrigins.map(function(origin) {
+    return origin.replace(/\/?$/, '/');
+  }));
 
   // Cache for loaded chunks
   const chunkCache = new Map();
   const loadingPromises = new Map();
 
+  // Bytes received per chunk URL, for progress reporting
+  const received = new Map();
+
+  // Requests waiting for a free slot
+  const queue = [];
+  let active = 0;
+
   /**
-   * Load a single chunk using XHR (returns Uint8Array)
+   * Start queued requests while under the concurrency limit
    */
-  function loadChunk(url) {
-    const fullUrl = BASE_URL + url;
+  function runQueue() {
+    while (queue.length > 0 && (OPTIONS.concurrency <= 0 || active < OPTIONS.concurrency)) {
+      const task = queue.shift();
+      active++;
+      task().finally(function() {
+        active--;
+        runQueue();
+      });
+    }
+  }
 
-    // Check cache
-    if (chunkCache.has(url)) {
-      return Promise.resolve(chunkCache.get(url));
+  /**
+   * Run a request once a slot is free
+   */
+  function limit(task) {
+    return new Promise(function(resolve, reject) {
+      queue.push(function() {
+        return task().then(resolve, reject);
+      });
+      runQueue();
+    });
+  }
+
+  /**
+   * Bytes received and expected across all chunks in the manifest
+   */
+  function progress() {
+    let loaded = 0;
+    let total = 0;
+    for (const fileName in MANIFEST) {
+      for (const chunk of MANIFEST[fileName]) {
+        loaded += received.get(chunk.url) || 0;
+        total += chunk.size || 0;
+      }
     }
+    return { loaded: loaded, total: total };
+  }
 
-    // Check if already loading
-    if (loadingPromises.has(url)) {
-      return loadingPromises.get(url);
+  /**
+   * Dispatch a `chrysalis:<type>` event on window
+   */
+  function dispatch(type, detail) {
+    window.dispatchEvent(new CustomEvent('chrysalis:' + type, { detail: detail }));
+  }
+
+  /**
+   * Record the bytes received for a chunk and report overall progress
+   */
+  function track(chunk, bytes) {
+    // Compressed responses may report encoded bytes; never exceed the size
+    received.set(chunk.url, chunk.size ? Math.min(bytes, chunk.size) : bytes);
+    const detail = progress();
+    detail.url = chunk.url;
+    dispatch('progress', detail);
+  }
+
+  /**
+   * Verify chunk bytes against the build-time digest (if any)
+   */
+  function verifyChunk(chunk, data) {
+    const expected = chunk.integrity;
+    if (!expected) {
+      return Promise.resolve(data);
+    }
+
+    const separator = expected.indexOf('-');
+    const algorithm = DIGEST_ALGORITHMS[expected.slice(0, separator)];
+    if (!algorithm || !window.crypto || !window.crypto.subtle) {
+      if (!OPTIONS.requireIntegrity) {
+        return Promise.resolve(data);
+      }
+      return Promise.reject(new Error(`Cannot verify integrity of chunk: ${chunk.url}`));
     }
 
-    const promise = new Promise((resolve, reject) => {
+    return window.crypto.subtle.digest(algorithm, data).then(function(digest) {
+      const actual = btoa(String.fromCharCode.apply(null, new Uint8Array(digest)));
+      if (actual !== expected.slice(separator + 1)) {
+        throw new Error(`Integrity check failed for chunk: ${chunk.url}`);
+      }
+      return data;
+    });
+  }
+
+  /**
+   * Fetch a URL once using XHR (returns Uint8Array), reporting bytes received
+   */
+  function request(url, onProgress) {
+    return new Promise(function(resolve, reject) {
       const xhr = new XMLHttpRequest();
-      xhr.open('GET', fullUrl, true);
+      xhr.open('GET', url, true);
       xhr.responseType = 'arraybuffer';
+      xhr.timeout = OPTIONS.timeout;
 
       xhr.onload = function() {
         if (xhr.status === 200) {
-          const data = new Uint8Array(xhr.response);
-          chunkCache.set(url, data);
-          loadingPromises.delete(url);
-          resolve(data);
+          resolve(new Uint8Array(xhr.response));
         } else {
-          loadingPromises.delete(url);
           reject(new Error(`Failed to load chunk: ${url} (status: ${xhr.status})`));
         }
       };
-
       xhr.onerror = function() {
-        loadingPromises.delete(url);
         reject(new Error(`Network error loading chunk: ${url}`));
       };
+      xhr.ontimeout = function() {
+        reject(new Error(`Timed out loading chunk: ${url}`));
+      };
+      if (onProgress) {
+        xhr.onprogress = function(event) {
+          onProgress(event.loaded);
+        };
+      }
 
       xhr.send();
     });
+  }
+
+  /**
+   * Wait before retry `attempt`: exponential backoff with jitter
+   */
+  function backoff(attempt) {
+    const delay = Math.min(OPTIONS.maxRetryDelay, OPTIONS.retryDelay * Math.pow(2, attempt));
+    return new Promise(function(resolve) {
+      setTimeout(resolve, delay / 2 + Math.random() * delay / 2);
+    });
+  }
+
+  /**
+   * Fetch and verify a chunk, retrying failures on the next origin
+   */
+  function fetchChunk(chunk, attempt, onProgress) {
+    const url = ORIGINS[attempt % ORIGINS.length] + chunk.url;
+    return limit(function() {
+      return request(url, onProgress);
+    }).then(function(data) {
+      return verifyChunk(chunk, data);
+    }).catch(function(error) {
+      if (attempt >= OPTIONS.retries) {
+        throw error;
+      }
+      console.warn(`[Chrysalis] ${error.message}; retrying (${attempt + 1}/${OPTIONS.retries})`);
+      if (onProgress) {
+        onProgress(0);
+      }
+      return backoff(attempt).then(function() {
+        return fetchChunk(chunk, attempt + 1, onProgress);
+      });
+    });
+  }
+
+  /**
+   * Load a single chunk (a manifest entry or a URL; returns Uint8Array)
+   */
+  function loadChunk(chunk) {
+    if (typeof chunk === 'string') {
+      chunk = { url: chunk };
+    }
+    const url = chunk.url;
+
+    // Check cache
+    if (chunkCache.has(url)) {
+      return Promise.resolve(chunkCache.get(url));
+    }
+
+    // Check if already loading
+    if (loadingPromises.has(url)) {
+      return loadingPromises.get(url);
+    }
+
+    const promise = fetchChunk(chunk, 0, function(bytes) {
+      track(chunk, bytes);
+    }).then(function(data) {
+      chunkCache.set(url, data);
+      loadingPromises.delete(url);
+      track(chunk, data.length);
+      return data;
+    }, function(error) {
+      loadingPromises.delete(url);
+      throw error;
+    });
 
     loadingPromises.set(url, promise);
     return promise;
   }
 
+  /**
+   * Load, verify and merge all chunks of a file (returns Uint8Array)
+   */
+  function loadFile(fileName) {
+    return ready.then(function() {
+      const chunks = MANIFEST[fileName];
+      if (!chunks || chunks.length === 0) {
+        throw new Error('No chunks found in manifest for: ' + fileName);
+      }
+      return Promise.all(chunks.map(loadChunk));
+    }).then(function(chunkData) {
+      const totalLength = chunkData.reduce(function(sum, data) {
+        return sum + data.length;
+      }, 0);
+      const merged = new Uint8Array(totalLength);
+      let offset = 0;
+      for (const data of chunkData) {
+        merged.set(data, offset);
+        offset += data.length;
+      }
+
+      const detail = progress();
+      detail.file = fileName;
+      dispatch('loaded', detail);
+      return merged;
+    }, function(error) {
+      dispatch('error', { file: fileName, error: error });
+      throw error;
+    });
+  }
+
+  /**
+   * Load the chunks of an ES module and import it (returns the module namespace)
+   */
+  function importModule(fileName) {
+    return loadFile(fileName).then(function(merged) {
+      const url = URL.createObjectURL(new Blob([merged], { type: 'text/javascript' }));
+      return import(url).finally(function() {
+        URL.revokeObjectURL(url);
+      });
+    });
+  }
+
+  /**
+   * Manifest entry served by the loader for a fetch of `input`, if any
+   */
+  function chunkedFile(input) {
+    // A string, URL or Request
+    const href = input && typeof input.url === 'string' ? input.url : String(input);
+    let url;
+    try {
+      url = new URL(href, document.baseURI);
+    } catch (e) {
+      return null;
+    }
+    const path = url.origin + url.pathname;
+    if (path.indexOf(BASE_URL) !== 0) {
+      return null;
+    }
+
+    // Scripts and modules are loaded by their stubs
+    const fileName = decodeURIComponent(path.slice(BASE_URL.length));
+    if (!MANIFEST[fileName] || /\.m?js$/.test(fileName)) {
+      return null;
+    }
+    return fileName;
+  }
+
+  // Load the separate manifest file (retried and verified like a chunk)
+  let loaded = !MANIFEST_FILE;
+  const ready = MANIFEST_FILE ? fetchChunk(MANIFEST_FILE, 0).then(function(data) {
+    Object.assign(MANIFEST, JSON.parse(new TextDecoder().decode(data)));
+    loaded = true;
+  }, function(error) {
+    console.error('[Chrysalis] Failed to load chunk manifest:', error);
+    dispatch('error', { file: MANIFEST_FILE.url, error: error });
+    loaded = true;
+    throw error;
+  }) : Promise.resolve();
+  ready.catch(function() {});
+
+  // Serve other chunked files (e.g. WebAssembly) to fetch as if they were
+  // never split, so WebAssembly.instantiateStreaming keeps working
+  if (window.fetch && (MANIFEST_FILE || Object.keys(MANIFEST).some(function(fileName) {
+    return !/\.m?js$/.test(fileName);
+  }))) {
+    const originalFetch = window.fetch;
+    window.fetch = function(input, init) {
+      const self = this;
+      const args = arguments;
+      // Requests made before the manifest file arrived wait for it
+      if (!loaded) {
+        const retry = function() {
+          return window.fetch.apply(self, args);
+        };
+        return ready.then(retry, retry);
+      }
+
+      const fileName = chunkedFile(input);
+      if (!fileName) {
+        return originalFetch.apply(this, arguments);
+      }
+
+      const extension = fileName.slice(fileName.lastIndexOf('.') + 1);
+      return loadFile(fileName).then(function(merged) {
+        return new Response(merged, {
+          status: 200,
+          headers: { 'Content-Type': CONTENT_TYPES[extension] || 'application/octet-stream' },
+        });
+      });
+    };
+  }
+
+  // Trusted Types policy for chunk scripts, created on first use
+  let policy = null;
+
+  /**
+   * Pass a script (or script URL) through the Trusted Types policy, if any
+   */
+  function trusted(value, kind) {
+    if (!OPTIONS.trustedTypesPolicy || !window.trustedTypes) {
+      return value;
+    }
+    if (!policy) {
+      policy = window.trustedTypes.createPolicy(OPTIONS.trustedTypesPolicy, {
+        createScript: function(script) {
+          return script;
+        },
+        createScriptURL: function(url) {
+          return url;
+        },
+      });
+    }
+    return kind === 'url' ? policy.createScriptURL(value) : policy.createScript(value);
+  }
+
+  /**
+   * Load the chunks of a classic script and run it (resolves once it ran)
+   */
+  function runScript(fileName) {
+    return loadFile(fileName).then(function(merged) {
+      let text = new TextDecoder().decode(merged);
+      if (OPTIONS.sourceUrl) {
+        // Relative, so the text (and its CSP hash) is known at build time
+        text += '\n//# sourceURL=' + fileName;
+      }
+
+      const script = document.createElement('script');
+      if (OPTIONS.execution !== 'blob') {
+        // Inline scripts run synchronously, allowed by their CSP hash
+        script.text = trusted(text, 'script');
+        document.head.appendChild(script);
+        return;
+      }
+
+      const url = URL.createObjectURL(new Blob([text], { type: 'text/javascript' }));
+      return new Promise(function(resolve, reject) {
+        script.onload = resolve;
+        script.onerror = function() {
+          reject(new Error('Failed to run chunked file: ' + fileName));
+        };
+        // Keep the order of execution of dynamically inserted scripts
+        script.async = false;
+        script.src = trusted(url, 'url');
+        document.head.appendChild(script);
+      }).finally(function() {
+        URL.revokeObjectURL(url);
+      });
+    });
+  }
+
   // Export public API for stub files to use
   window.ChunkLoader = {
     loadChunk: loadChunk,
+    loadFile: loadFile,
+    runScript: runScript,
+    importModule: importModule,
+    progress: progress,
+    ready: ready,
     manifest: MANIFEST,
     cache: chunkCache,
   };
@@ -75,229 +435,793 @@ const CHUNK_LOADER_TEMPLATE: &str = r#"
   // Export for debugging
   window.__CHRYSALIS__ = {
     manifest: MANIFEST,
+    options: OPTIONS,
     chunkCache: chunkCache,
     loadChunk: loadChunk,
   };
 })();
 "#;
 
+/// A chunk in the loader's manifest.
+#[derive(Debug, Clone, Serialize)]
+struct ManifestChunk {
+    /// Relative URL of the chunk.
+    url: String,
+
+    /// Size in bytes, for progress totals before any response arrives.
+    size: u64,
+
+    /// Digest checked before the chunks are merged (SRI format).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    integrity: Option<String>,
+}
+
+/// Options of the loader runtime.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LoaderOptions<'a> {
+    retries: u32,
+    retry_delay: u64,
+    max_retry_delay: u64,
+    concurrency: usize,
+    timeout: u64,
+    fallback_origins: &'a [String],
+    require_integrity: bool,
+    execution: ScriptExecution,
+    trusted_types_policy: Option<&'a str>,
+    source_url: bool,
+}
+
+/// How the chunk loader reaches index.html.
+enum Loader {
+    /// Loader script with the manifest inlined.
+    Inline(String),
+    /// Separate loader and manifest files, relative to the build directory.
+    External {
+        loader: PathBuf,
+        manifest: PathBuf,
+        loader_integrity: Option<String>,
+        manifest_integrity: Option<String>,
+    },
+}
+
+/// What a hinted file is fetched as.
+#[derive(Debug, Clone, Copy)]
+enum HintKind {
+    /// A classic `<script>`.
+    Script,
+    /// XHR or `fetch()` (chunks, WebAssembly).
+    Fetch,
+    /// A web font.
+    Font,
+}
+
+impl HintKind {
+    /// Value of the `as` attribute.
+    fn destination(&self) -> &'static str {
+        match self {
+            HintKind::Script => "script",
+            HintKind::Fetch => "fetch",
+            HintKind::Font => "font",
+        }
+    }
+}
+
+/// A `<link>` resource hint.
+#[derive(Debug, Clone)]
+struct ResourceHint {
+    /// Current path relative to the build directory.
+    path: PathBuf,
+    rel: HintRel,
+    priority: FetchPriority,
+    kind: HintKind,
+    /// Integrity of scripts, which preloads must repeat to be reused.
+    integrity: Option<String>,
+}
+
+impl ResourceHint {
+    fn new(file: &FileInfo, rule: &ResourceHintRule, kind: HintKind) -> Self {
+        Self {
+            path: file.relative.clone(),
+            rel: rule.rel,
+            priority: rule.priority,
+            kind,
+            integrity: None,
+        }
+    }
+}
+
+/// A font family in `FontManifest.json`.
+#[derive(Debug, Deserialize)]
+struct FontFamily {
+    fonts: Vec<FontAsset>,
+}
+
+/// A font file in `FontManifest.json`, relative to `assets/`.
+#[derive(Debug, Deserialize)]
+struct FontAsset {
+    asset: String,
+}
+
 /// Inject plugin adds chunk loader to HTML.
 pub struct InjectPlugin {
     config: InjectConfig,
+    naming: FileNaming,
+    hint_rules: Vec<(Pattern, ResourceHintRule)>,
 }
 
 impl InjectPlugin {
     /// Create a new inject plugin.
-    pub fn new(config: InjectConfig) -> Self {
-        Self { config }
+    pub fn new(config: InjectConfig) -> Result<Self> {
+        let hint_rules = config
+            .hints
+            .rules
+            .iter()
+            .map(|rule| Ok((Pattern::new(&rule.pattern)?, rule.clone())))
+            .collect::<std::result::Result<Vec<_>, glob::PatternError>>()
+            .map_err(|e| anyhow::anyhow!("Invalid resource hint pattern: {}", e))?;
+        if let Some(selector) = &config.selector {
+            html::parse_selector(selector)?;
+        }
+
+        Ok(Self {
+            config,
+            naming: FileNaming::default(),
+            hint_rules,
+        })
+    }
+
+    /// Name the loader and manifest files like hashed files.
+    pub fn with_hash(mut self, hash: &HashConfig) -> Result<Self> {
+        self.naming = FileNaming::new(HashScheme::from(hash)).with_file_name(&hash.file_name)?;
+        Ok(self)
     }
 
     /// Generate chunk manifest.
-    /// Maps parent file names (with hash) to their chunk file names (with hash).
-    fn generate_manifest(&self, ctx: &BuildContext) -> HashMap<String, Vec<String>> {
-        let mut manifest = HashMap::new();
+    /// Maps parent file names (as written in their stubs, before hashing) to
+    /// the relative paths of their chunks (with hash) and their digests, so
+    /// stubs never change after hashing.
+    fn generate_manifest(&self, ctx: &BuildContext) -> BTreeMap<String, Vec<ManifestChunk>> {
+        let originals = ManifestPlugin::original_paths(ctx);
+        let verify = self.config.sri.enabled || self.config.loader.verify;
+        let mut manifest = BTreeMap::new();
 
         for (parent_path, chunk_paths) in ctx.chunks().iter() {
-            if let Some(parent_file) = ctx.get_file(parent_path) {
-                let parent_name = parent_file.name.clone();
+            let Some(parent_name) = Self::chunked_file_name(ctx, &originals, parent_path) else {
+                continue;
+            };
 
-                // Get chunk names from the chunk paths (already in correct order)
-                let chunk_names: Vec<String> = chunk_paths
-                    .iter()
-                    .filter_map(|chunk_path| ctx.get_file(chunk_path).map(|f| f.name.clone()))
-                    .collect();
+            // Get chunks from the chunk paths (already in correct order)
+            let chunks: Vec<ManifestChunk> = chunk_paths
+                .iter()
+                .filter_map(|chunk_path| ctx.get_file(chunk_path))
+                .map(|file| ManifestChunk {
+                    url: to_url_path(&file.relative),
+                    size: file.size,
+                    integrity: if verify {
+                        self.file_integrity(file)
+                    } else {
+                        None
+                    },
+                })
+                .collect();
 
-                if !chunk_names.is_empty() {
-                    manifest.insert(parent_name, chunk_names);
-                }
+            if !chunks.is_empty() {
+                manifest.insert(parent_name, chunks);
             }
         }
 
         manifest
     }
 
-    /// Extract base name from a file name (removing hash and extension).
-    /// e.g., "main.dart.abc123.js" -> "main.dart"
-    ///       "main.dart.js" -> "main.dart"
-    fn extract_base_name(name: &str) -> String {
-        // Split by dots
-        let parts: Vec<&str> = name.split('.').collect();
-        if parts.len() < 2 {
-            return name.to_string();
+    /// Name of a chunked file in the loader's manifest.
+    ///
+    /// Stubs and fetch look chunks up by the original path; files without a
+    /// stub were removed and never renamed.
+    fn chunked_file_name(
+        ctx: &BuildContext,
+        originals: &HashMap<PathBuf, PathBuf>,
+        parent_path: &Path,
+    ) -> Option<String> {
+        match ctx.get_file(parent_path) {
+            Some(parent_file) => Some(to_url_path(
+                originals
+                    .get(&parent_file.relative)
+                    .unwrap_or(&parent_file.relative),
+            )),
+            None => parent_path
+                .strip_prefix(ctx.build_dir())
+                .ok()
+                .map(to_url_path),
         }
+    }
 
-        // Remove extension
-        let without_ext = &parts[..parts.len() - 1];
+    /// Generate chunk loader script, with the manifest inlined or (if None)
+    /// loaded from the file named by its `<script>` tag.
+    fn generate_loader(
+        &self,
+        manifest: Option<&BTreeMap<String, Vec<ManifestChunk>>>,
+    ) -> Result<String> {
+        let loader = &self.config.loader;
+        let options = LoaderOptions {
+            retries: loader.retries,
+            retry_delay: loader.retry_delay_ms,
+            max_retry_delay: loader.max_retry_delay_ms,
+            concurrency: loader.concurrency,
+            timeout: loader.timeout_ms,
+            fallback_origins: &loader.fallback_origins,
+            require_integrity: self.config.sri.enabled,
+            execution: loader.execution,
+            trusted_types_policy: loader.trusted_types_policy.as_deref(),
+            source_url: loader.source_url,
+        };
 
-        // Check if second-to-last part is a hash (8 hex chars)
-        if without_ext.len() >= 2 {
-            let potential_hash = without_ext[without_ext.len() - 1];
-            if potential_hash.len() == 8 && potential_hash.chars().all(|c| c.is_ascii_hexdigit()) {
-                // Remove hash part
-                return without_ext[..without_ext.len() - 1].join(".");
-            }
-        }
+        let manifest_json = serde_json::to_string(&manifest).map_err(|e| {
+            PluginError::InjectionFailed(format!("Failed to serialize manifest: {}", e))
+        })?;
+        let options_json = serde_json::to_string(&options).map_err(|e| {
+            PluginError::InjectionFailed(format!("Failed to serialize loader options: {}", e))
+        })?;
 
-        without_ext.join(".")
+        let loader = CHUNK_LOADER_TEMPLATE
+            .replace("{{manifest}}", &manifest_json)
+            .replace("{{options}}", &options_json);
+        Ok(loader)
     }
 
-    /// Generate chunk loader script.
-    fn generate_loader(&self, manifest: &HashMap<String, Vec<String>>) -> Result<String> {
-        let manifest_json = serde_json::to_string(manifest).map_err(|e| {
+    /// Write the loader runtime and the manifest as separate hashed files.
+    ///
+    /// The loader only changes with the configuration, so browsers keep it
+    /// cached across deployments; the manifest changes with every build.
+    fn write_loader_files(
+        &self,
+        ctx: &mut BuildContext,
+        manifest: &BTreeMap<String, Vec<ManifestChunk>>,
+    ) -> Result<Loader> {
+        let loader = self.generate_loader(None)?;
+        let loader = match minify_js(loader.as_bytes()) {
+            Ok(minified) => minified,
+            Err(e) => {
+                warn!("Failed to minify chunk loader: {}", e);
+                loader.into_bytes()
+            }
+        };
+        let manifest = serde_json::to_vec(manifest).map_err(|e| {
             PluginError::InjectionFailed(format!("Failed to serialize manifest: {}", e))
         })?;
 
-        let loader = CHUNK_LOADER_TEMPLATE.replace("{{manifest}}", &manifest_json);
-        Ok(loader)
+        let loader_path = self.add_generated_file(ctx, LOADER_FILE_NAME, loader)?;
+        let manifest_path = self.add_generated_file(ctx, MANIFEST_FILE_NAME, manifest)?;
+
+        // The loader is checked by the browser (SRI), the manifest by the loader
+        let integrity = |relative: &Path, enabled: bool| {
+            ctx.get_file(ctx.build_dir().join(relative))
+                .filter(|_| enabled)
+                .and_then(|file| self.file_integrity(file))
+        };
+        Ok(Loader::External {
+            loader_integrity: integrity(&loader_path, self.config.sri.enabled),
+            manifest_integrity: integrity(
+                &manifest_path,
+                self.config.sri.enabled || self.config.loader.verify,
+            ),
+            loader: loader_path,
+            manifest: manifest_path,
+        })
     }
 
-    /// Update file references in HTML to use hashed versions.
-    fn update_file_references(&self, html_content: &str, ctx: &BuildContext) -> String {
-        let mut result = html_content.to_string();
-
-        // Build a map of original names to hashed names
-        let mut name_map = HashMap::new();
-        for file in ctx.files() {
-            // If the file has been hashed, it will have a hash in its name
-            if file.name.contains('.') {
-                // Extract the base name without hash
-                // e.g., "flutter_bootstrap.e9a99a30.js" -> "flutter_bootstrap.js"
-                let parts: Vec<&str> = file.name.split('.').collect();
-                if parts.len() >= 3 {
-                    // Check if the second-to-last part looks like a hash (8 hex chars)
-                    let potential_hash = parts[parts.len() - 2];
-                    if potential_hash.len() == 8
-                        && potential_hash.chars().all(|c| c.is_ascii_hexdigit())
-                    {
-                        // Reconstruct original name without hash
-                        let mut original_parts = parts.clone();
-                        original_parts.remove(parts.len() - 2);
-                        let original_name = original_parts.join(".");
-                        name_map.insert(original_name, file.name.clone());
+    /// Write a file named `name` as a hashed file would be (see
+    /// `plugins.hash.file_name`), and add it to the context.
+    fn add_generated_file(
+        &self,
+        ctx: &mut BuildContext,
+        name: &str,
+        content: Vec<u8>,
+    ) -> Result<PathBuf> {
+        let hash = self.naming.scheme().hash(&content);
+        let relative = self.naming.hashed_path(Path::new(name), &hash);
+        let path = ctx.build_dir().join(&relative);
+
+        chrysalis_core::write_file_content(&path, &content)?;
+        match ctx.get_file_mut(&path) {
+            Some(file) => file.set_content(content),
+            None => {
+                let mut file = FileInfo::new(&path, &relative, content.len() as u64);
+                file.set_content(content);
+                ctx.add_generated_file(file)?;
+            }
+        }
+
+        info!("  Wrote {}", relative.display());
+        Ok(relative)
+    }
+
+    /// URL of a file (relative to the build directory) from `html_path`.
+    fn html_url(relative: &Path, html_path: &Path, ctx: &BuildContext) -> String {
+        let html_dir = html_path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(ctx.build_dir()).ok())
+            .unwrap_or(Path::new(""));
+        to_url_path(&pathdiff::diff_paths(relative, html_dir).unwrap_or(relative.to_path_buf()))
+    }
+
+    /// Rule deciding the hint for an original path, if any.
+    fn hint_rule(&self, original: &str) -> Option<&ResourceHintRule> {
+        self.hint_rules
+            .iter()
+            .find(|(pattern, _)| pattern.matches(original))
+            .map(|(_, rule)| rule)
+            .filter(|rule| rule.rel != HintRel::None)
+    }
+
+    /// Resource hints for the chunks of chunked files, CanvasKit/Skwasm
+    /// WebAssembly, `flutter_bootstrap.js` and fonts from `FontManifest.json`.
+    fn resource_hints(&self, ctx: &BuildContext) -> Vec<ResourceHint> {
+        let mut hints = Vec::new();
+        if !self.config.hints.enabled {
+            return hints;
+        }
+
+        let originals = ManifestPlugin::original_paths(ctx);
+        let original_of =
+            |file: &FileInfo| to_url_path(originals.get(&file.relative).unwrap_or(&file.relative));
+        let mut files: Vec<&FileInfo> = ctx.files().collect();
+        files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+        // Bootstrap script and renderer WebAssembly
+        for file in &files {
+            let original = original_of(file);
+            let kind = match original.rsplit('/').next() {
+                Some("flutter_bootstrap.js") => HintKind::Script,
+                Some("canvaskit.wasm" | "skwasm.wasm") => HintKind::Fetch,
+                _ => continue,
+            };
+            if let Some(rule) = self.hint_rule(&original) {
+                hints.push(ResourceHint::new(file, rule, kind));
+            }
+        }
+
+        // Chunks, fetched by the loader, by the original path of their file
+        let chunked: BTreeMap<String, &Vec<PathBuf>> = ctx
+            .chunks()
+            .iter()
+            .filter_map(|(parent, chunks)| {
+                Some((Self::chunked_file_name(ctx, &originals, parent)?, chunks))
+            })
+            .collect();
+        for (original, chunks) in chunked {
+            let Some(rule) = self.hint_rule(&original) else {
+                continue;
+            };
+            for chunk in chunks.iter().filter_map(|path| ctx.get_file(path)) {
+                hints.push(ResourceHint::new(chunk, rule, HintKind::Fetch));
+            }
+        }
+
+        // Fonts the engine loads at startup
+        let current: HashMap<String, &FileInfo> = files
+            .iter()
+            .map(|file| (original_of(file), *file))
+            .collect();
+        let Some(font_manifest) = current.get("assets/FontManifest.json") else {
+            return hints;
+        };
+        let families: Vec<FontFamily> = match font_manifest
+            .content
+            .clone()
+            .map_or_else(|| std::fs::read(&font_manifest.absolute), Ok)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_slice(&content).map_err(|e| e.to_string()))
+        {
+            Ok(families) => families,
+            Err(e) => {
+                warn!("Failed to read FontManifest.json: {}", e);
+                return hints;
+            }
+        };
+        for font in families.iter().flat_map(|family| &family.fonts) {
+            let original = format!("assets/{}", font.asset);
+            if let Some(rule) = self.hint_rule(&original)
+                && let Some(file) = current.get(&original)
+            {
+                hints.push(ResourceHint::new(file, rule, HintKind::Font));
+            }
+        }
+
+        hints
+    }
+
+    /// The `<link>` tags of `hints` in `html_path`.
+    fn hint_tags(&self, hints: &[ResourceHint], html_path: &Path, ctx: &BuildContext) -> String {
+        let mut tags = String::new();
+        for hint in hints {
+            tags.push_str(&format!(
+                r#"<link rel="{}" href="{}""#,
+                hint.rel.as_str(),
+                Self::html_url(&hint.path, html_path, ctx)
+            ));
+            if hint.rel != HintRel::Modulepreload {
+                tags.push_str(&format!(r#" as="{}""#, hint.kind.destination()));
+            }
+            match hint.kind {
+                // Must match the request of the <script> tag, which SRI may make CORS
+                HintKind::Script => {
+                    if let Some(integrity) = &hint.integrity {
+                        tags.push_str(&format!(
+                            r#" integrity="{}" crossorigin="{}""#,
+                            integrity, self.config.sri.crossorigin
+                        ));
                     }
                 }
+                // XHR, fetch() and fonts are CORS requests
+                HintKind::Fetch | HintKind::Font => tags.push_str(r#" crossorigin="anonymous""#),
+            }
+            match hint.priority {
+                FetchPriority::Auto => {}
+                FetchPriority::High => tags.push_str(r#" fetchpriority="high""#),
+                FetchPriority::Low => tags.push_str(r#" fetchpriority="low""#),
             }
+            tags.push('>');
         }
+        tags
+    }
 
-        // Update references in HTML - handle quoted, unquoted, and compressed formats
-        for (original, hashed) in name_map.iter() {
-            // Pattern 1: src=filename (no quotes, compressed HTML)
-            result = result.replace(&format!("src={}", original), &format!("src={}", hashed));
-            // Pattern 2: src="filename"
-            result = result.replace(
-                &format!("src=\"{}\"", original),
-                &format!("src=\"{}\"", hashed),
-            );
-            // Pattern 3: src='filename'
-            result = result.replace(&format!("src='{}'", original), &format!("src='{}'", hashed));
-            // Pattern 4: href=filename (no quotes, compressed HTML)
-            result = result.replace(&format!("href={}", original), &format!("href={}", hashed));
-            // Pattern 5: href="filename"
-            result = result.replace(
-                &format!("href=\"{}\"", original),
-                &format!("href=\"{}\"", hashed),
-            );
-            // Pattern 6: href='filename'
-            result = result.replace(
-                &format!("href='{}'", original),
-                &format!("href='{}'", hashed),
-            );
+    /// The `<script>` tag running the loader in `html_path`.
+    fn loader_tag(&self, loader: &Loader, html_path: &Path, ctx: &BuildContext) -> String {
+        let (loader, manifest, loader_integrity, manifest_integrity) = match loader {
+            Loader::Inline(script) => return format!("<script>{}</script>", script),
+            Loader::External {
+                loader,
+                manifest,
+                loader_integrity,
+                manifest_integrity,
+            } => (loader, manifest, loader_integrity, manifest_integrity),
+        };
+
+        let url = |relative: &Path| Self::html_url(relative, html_path, ctx);
+        let mut tag = format!(
+            r#"<script src="{}" data-manifest="{}""#,
+            url(loader),
+            url(manifest)
+        );
+        if let Some(integrity) = manifest_integrity {
+            tag.push_str(&format!(r#" data-manifest-integrity="{}""#, integrity));
         }
+        if let Some(integrity) = loader_integrity {
+            tag.push_str(&format!(
+                r#" integrity="{}" crossorigin="{}""#,
+                integrity, self.config.sri.crossorigin
+            ));
+        }
+        tag.push_str("></script>");
+        tag
+    }
 
-        result
+    /// Compute the integrity value of a file from its final bytes.
+    fn file_integrity(&self, file: &FileInfo) -> Option<String> {
+        let algorithm = self.config.sri.algorithm;
+        match &file.content {
+            Some(content) => Some(chrysalis_core::calculate_integrity(content, algorithm)),
+            None => match chrysalis_core::read_file_content(&file.absolute) {
+                Ok(content) => Some(chrysalis_core::calculate_integrity(&content, algorithm)),
+                Err(e) => {
+                    warn!("Failed to read {} for integrity: {}", file.name, e);
+                    None
+                }
+            },
+        }
     }
 
-    /// Inject loader into HTML file.
-    fn inject_into_html(&self, html_content: &str, loader_script: &str) -> String {
-        // Find </head> tag and inject before it
-        if let Some(pos) = html_content.find("</head>") {
-            let mut result = String::with_capacity(html_content.len() + loader_script.len() + 20);
-            result.push_str(&html_content[..pos]);
-            result.push_str("<script>");
-            result.push_str(loader_script);
-            result.push_str("</script>");
-            result.push_str(&html_content[pos..]);
-            result
-        } else {
-            // If no </head>, inject at beginning of <body>
-            if let Some(pos) = html_content.find("<body")
-                && let Some(end) = html_content[pos..].find('>')
-            {
-                let insert_pos = pos + end + 1;
-                let mut result =
-                    String::with_capacity(html_content.len() + loader_script.len() + 20);
-                result.push_str(&html_content[..insert_pos]);
-                result.push_str("<script>");
-                result.push_str(loader_script);
-                result.push_str("</script>");
-                result.push_str(&html_content[insert_pos..]);
-                return result;
-            }
+    /// Resolve a `src`/`href` value to a file in the build context.
+    ///
+    /// Remote URLs (`https://`, `//`, `data:`) are never resolved.
+    fn resolve_local_file<'a>(value: &str, ctx: &'a BuildContext) -> Option<&'a FileInfo> {
+        if value.contains("://") || value.starts_with("//") || value.starts_with("data:") {
+            return None;
+        }
 
-            // Fallback: just prepend
-            format!("<script>{}</script>{}", loader_script, html_content)
+        let path = value.split(['?', '#']).next()?;
+        let path = path.trim_start_matches("./").trim_start_matches('/');
+        if path.is_empty() {
+            return None;
         }
+
+        ctx.get_file(ctx.build_dir().join(path))
     }
 
-    /// Update stub files to use correct hashed file names.
-    fn update_stub_references(&self, ctx: &mut BuildContext) -> Result<()> {
-        info!("  Updating stub file references...");
+    /// Add `integrity` and `crossorigin` attributes to local scripts and stylesheets.
+    fn add_integrity_attributes(&self, html: &str, ctx: &BuildContext) -> Result<String> {
+        html::set_attributes(html, "script[src], link[href]", |get| {
+            if get("integrity").is_some() {
+                return Vec::new();
+            }
 
-        // Find all stub files (files that were chunked)
-        let stub_files: Vec<_> = ctx
-            .chunks()
-            .keys()
-            .filter_map(|parent_path| {
-                ctx.get_file(parent_path)
-                    .map(|f| (f.absolute.clone(), f.name.clone()))
-            })
-            .collect();
+            // Only stylesheets and preloads support integrity on <link>
+            let url = match get("src") {
+                Some(src) => src,
+                None => {
+                    let rel = get("rel").unwrap_or_default().to_ascii_lowercase();
+                    if !rel
+                        .split_whitespace()
+                        .any(|r| matches!(r, "stylesheet" | "preload" | "modulepreload"))
+                    {
+                        return Vec::new();
+                    }
+                    get("href").unwrap_or_default()
+                }
+            };
 
-        for (stub_path, hashed_name) in stub_files {
-            // Extract the original name without hash
-            let original_name = Self::extract_base_name(&hashed_name);
-            let original_full = format!("{}.js", original_name);
+            let Some(integrity) =
+                Self::resolve_local_file(&url, ctx).and_then(|file| self.file_integrity(file))
+            else {
+                return Vec::new();
+            };
 
-            // Update the stub file content
-            let updated_content = {
-                let file = ctx.get_file_mut(&stub_path).unwrap();
+            let mut attributes = vec![("integrity".to_string(), integrity)];
+            if get("crossorigin").is_none() {
+                attributes.push((
+                    "crossorigin".to_string(),
+                    self.config.sri.crossorigin.to_string(),
+                ));
+            }
+            attributes
+        })
+    }
 
-                // Load content
-                if let Err(e) = file.load_content() {
-                    warn!("Failed to load stub {}: {}", file.name, e);
-                    continue;
+    /// CSP hash source for a script body: `'sha256-...'`
+    fn csp_hash_source(content: &[u8]) -> String {
+        format!(
+            "'{}'",
+            chrysalis_core::calculate_integrity(content, SriAlgorithm::Sha256)
+        )
+    }
+
+    /// Hash sources for the scripts chunk stubs execute at runtime.
+    ///
+    /// With the inline execution strategy, stubs run the merged chunks as an
+    /// inline script, so its text (the concatenated chunks and the
+    /// `sourceURL` comment) must be allowed by the policy as well.
+    fn stub_script_hashes(&self, ctx: &BuildContext) -> Vec<String> {
+        let mut hashes = Vec::new();
+        if self.config.loader.execution != ScriptExecution::Inline {
+            return hashes;
+        }
+        let originals = ManifestPlugin::original_paths(ctx);
+
+        for (parent, chunks) in ctx.chunks() {
+            // Only classic scripts run as inline scripts
+            if parent.extension().is_none_or(|ext| ext != "js") {
+                continue;
+            }
+
+            let mut merged = Vec::new();
+            for chunk in chunks {
+                match ctx.get_file(chunk).and_then(|f| f.content.as_ref()) {
+                    Some(content) => merged.extend_from_slice(content),
+                    None => match std::fs::read(chunk) {
+                        Ok(content) => merged.extend_from_slice(&content),
+                        Err(e) => {
+                            warn!("Failed to read chunk {}: {}", chunk.display(), e);
+                            continue;
+                        }
+                    },
                 }
+            }
+            if self.config.loader.source_url
+                && let Some(name) = Self::chunked_file_name(ctx, &originals, parent)
+            {
+                merged.extend_from_slice(format!("\n//# sourceURL={}", name).as_bytes());
+            }
+            hashes.push(Self::csp_hash_source(&merged));
+        }
 
-                let mut content = match file.content_as_str() {
-                    Some(s) => s.to_string(),
-                    None => continue,
+        hashes.sort();
+        hashes
+    }
+
+    /// Whether chunk stubs run `blob:` URLs: chunked ES modules always do,
+    /// classic scripts with the blob execution strategy.
+    fn runs_blob_scripts(&self, ctx: &BuildContext) -> bool {
+        ctx.chunks().keys().any(|parent| match parent.extension() {
+            Some(ext) if ext == "mjs" => true,
+            Some(ext) if ext == "js" => self.config.loader.execution == ScriptExecution::Blob,
+            _ => false,
+        })
+    }
+
+    /// Build the Content-Security-Policy from configured directives and script hashes.
+    fn build_csp(&self, script_hashes: &[String], blob_scripts: bool) -> String {
+        let mut directives = self.config.csp.directives.clone();
+
+        // Scripts fall back to default-src when no script-src is configured
+        let fallback = directives
+            .get("default-src")
+            .cloned()
+            .unwrap_or_else(|| vec!["'self'".to_string()]);
+        let script_src = directives
+            .entry("script-src".to_string())
+            .or_insert(fallback);
+        for hash in script_hashes {
+            if !script_src.contains(hash) {
+                script_src.push(hash.clone());
+            }
+        }
+        if blob_scripts && !script_src.iter().any(|v| v == "blob:") {
+            script_src.push("blob:".to_string());
+        }
+
+        // Allow the loader's Trusted Types policy where policies are restricted
+        if let Some(policy) = &self.config.loader.trusted_types_policy
+            && let Some(allowed) = directives.get_mut("trusted-types")
+            && !allowed.contains(policy)
+        {
+            allowed.push(policy.clone());
+        }
+
+        // The chunk loader fetches from fallback origins
+        if !self.config.loader.fallback_origins.is_empty() {
+            let fallback = directives
+                .get("default-src")
+                .cloned()
+                .unwrap_or_else(|| vec!["'self'".to_string()]);
+            let connect_src = directives
+                .entry("connect-src".to_string())
+                .or_insert(fallback);
+            for url in &self.config.loader.fallback_origins {
+                let path_start = url.find("://").map_or(0, |i| i + 3);
+                let origin = match url[path_start..].find('/') {
+                    Some(i) => &url[..path_start + i],
+                    None => url.as_str(),
                 };
+                if !connect_src.iter().any(|v| v == origin) {
+                    connect_src.push(origin.to_string());
+                }
+            }
+        }
 
-                // Update the fileName variable to use the hashed name
-                // Old: const fileName = 'main.dart.js';
-                // New: const fileName = 'main.dart.abc123.js';
-                content = content.replace(
-                    &format!("const fileName = '{}';", original_full),
-                    &format!("const fileName = '{}';", hashed_name),
-                );
-                content = content.replace(
-                    &format!("const fileName = \"{}\";", original_full),
-                    &format!("const fileName = \"{}\";", hashed_name),
-                );
+        directives
+            .iter()
+            .map(|(name, values)| {
+                if values.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{} {}", name, values.join(" "))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
 
-                content.into_bytes()
-            };
+    /// Insert a CSP `<meta>` tag before any script in the document.
+    ///
+    /// It goes right after `<meta charset>`, `<head>`, `<html>` or the
+    /// doctype, whichever comes first in that order of preference.
+    fn inject_csp_meta(html: &str, policy: &str) -> Result<String> {
+        let document = html::Document::parse(html)?;
+        let charset = document
+            .elements
+            .iter()
+            .find(|e| e.name == "meta" && e.attribute("charset").is_some());
+        let point = charset
+            .or_else(|| document.first("head"))
+            .or_else(|| document.first("html"))
+            .map(|e| e.tag.end)
+            .or_else(|| {
+                // Minified doctypes have no space (`<!doctypehtml>`)
+                let trimmed = html.trim_start();
+                trimmed
+                    .get(..9)
+                    .filter(|start| start.eq_ignore_ascii_case("<!doctype"))
+                    .and_then(|_| trimmed.find('>'))
+                    .map(|end| html.len() - trimmed.len() + end + 1)
+            })
+            .unwrap_or(0);
 
-            // Write back
-            chrysalis_core::write_file_content(&stub_path, &updated_content)?;
+        let content = policy.replace('&', "&amp;").replace('"', "&quot;");
+        Ok(html::insert_at(
+            html,
+            point,
+            &format!(
+                "<meta http-equiv=Content-Security-Policy content=\"{}\">",
+                content
+            ),
+        ))
+    }
 
-            let file = ctx.get_file_mut(&stub_path).unwrap();
-            file.set_content(updated_content);
-            info!("    Updated stub: {}", file.name);
+    /// Write the policy to the headers file, keeping any existing rules.
+    fn write_csp_headers(&self, ctx: &mut BuildContext, policy: &str) -> Result<()> {
+        let headers_path = ctx.build_dir().join(&self.config.csp.headers_file);
+
+        let content = std::fs::read_to_string(&headers_path).unwrap_or_default();
+        let bytes = Self::set_csp_header(&content, policy).into_bytes();
+        chrysalis_core::write_file_content(&headers_path, &bytes)?;
+
+        // Track the headers file so later steps see it as an output file
+        if ctx.get_file(&headers_path).is_none() {
+            let file = FileInfo::new(
+                headers_path,
+                PathBuf::from(&self.config.csp.headers_file),
+                bytes.len() as u64,
+            );
+            ctx.add_file(file)?;
+        } else if let Some(file) = ctx.get_file_mut(&headers_path) {
+            file.set_content(bytes);
         }
 
+        info!("  Wrote policy to {}", self.config.csp.headers_file);
         Ok(())
     }
+
+    /// Set the policy of the `/*` entry in a `_headers` file.
+    ///
+    /// Any `Content-Security-Policy` of `/*` entries is replaced, so
+    /// rebuilding into the same directory does not add another policy; the
+    /// entry is appended if there is none.
+    fn set_csp_header(content: &str, policy: &str) -> String {
+        let header = format!("  Content-Security-Policy: {}", policy);
+        let mut lines = Vec::new();
+        let mut in_root = false;
+        let mut written = false;
+
+        for line in content.lines() {
+            // Header lines are indented below the path they apply to
+            if !line.starts_with([' ', '\t']) {
+                let trimmed = line.trim();
+                if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                    in_root = trimmed == "/*";
+                }
+                lines.push(line.to_string());
+                if in_root && !written && trimmed == "/*" {
+                    lines.push(header.clone());
+                    written = true;
+                }
+                continue;
+            }
+
+            let is_csp = line.split_once(':').is_some_and(|(name, _)| {
+                name.trim().eq_ignore_ascii_case("Content-Security-Policy")
+            });
+            if !(in_root && is_csp) {
+                lines.push(line.to_string());
+            }
+        }
+
+        if !written {
+            lines.push("/*".to_string());
+            lines.push(header);
+        }
+
+        let mut updated = lines.join("\n");
+        updated.push('\n');
+        updated
+    }
+
+    /// Update file references in HTML to use hashed versions.
+    fn update_file_references(
+        &self,
+        html_content: &str,
+        html_path: &Path,
+        ctx: &BuildContext,
+    ) -> String {
+        let Some(file) = ctx.get_file(html_path) else {
+            return html_content.to_string();
+        };
+
+        Resolver::new(ctx)
+            .rewrite(file, html_content)
+            .0
+            .unwrap_or_else(|| html_content.to_string())
+    }
+
+    /// Inject the resource hints and the loader's `<script>` tag into HTML.
+    fn inject_into_html(&self, html_content: &str, tags: &str) -> Result<String> {
+        let point = html::insertion_point(
+            html_content,
+            self.config.position,
+            self.config.selector.as_deref(),
+        )?;
+        Ok(html::insert_at(html_content, point, tags))
+    }
 }
 
 #[async_trait::async_trait]
@@ -306,6 +1230,11 @@ impl Plugin for InjectPlugin {
         "inject"
     }
 
+    fn runs_after(&self) -> &[&str] {
+        // Rewrites references to hashed and chunked names
+        &["minify", "chunk", "hash"]
+    }
+
     async fn execute(&self, ctx: &mut BuildContext) -> Result<()> {
         if !self.config.enabled {
             info!("Injection disabled");
@@ -313,27 +1242,50 @@ impl Plugin for InjectPlugin {
         }
 
         // Check if there are any chunks
-        if ctx.chunks().is_empty() {
+        let has_chunks = !ctx.chunks().is_empty();
+        let mut hints = self.resource_hints(ctx);
+        if !has_chunks && hints.is_empty() && !self.config.sri.enabled && !self.config.csp.enabled {
             info!("No chunks to inject loader for");
             return Ok(());
         }
+        if self.config.sri.enabled {
+            for hint in hints
+                .iter_mut()
+                .filter(|h| matches!(h.kind, HintKind::Script))
+            {
+                hint.integrity = ctx
+                    .get_file(ctx.build_dir().join(&hint.path))
+                    .and_then(|file| self.file_integrity(file));
+            }
+        }
+        if !hints.is_empty() {
+            info!("  Resource hints: {}", hints.len());
+        }
 
-        info!("Injecting chunk loader...");
+        let loader = if has_chunks {
+            info!("Injecting chunk loader...");
 
-        // Generate manifest
-        let manifest = self.generate_manifest(ctx);
-        info!("  Manifest entries: {}", manifest.len());
+            // Generate manifest
+            let manifest = self.generate_manifest(ctx);
+            info!("  Manifest entries: {}", manifest.len());
 
-        // Generate loader script
-        let loader_script = self.generate_loader(&manifest)?;
+            if self.config.inline_manifest {
+                // Inlined, and minified with index.html
+                Some(Loader::Inline(self.generate_loader(Some(&manifest))?))
+            } else {
+                Some(self.write_loader_files(ctx, &manifest)?)
+            }
+        } else {
+            None
+        };
 
-        // Minify loader if possible
-        let loader_script = if self.config.inline_manifest {
-            // Already minified by template
-            loader_script
+        // Scripts executed by chunk stubs, allowed by the policy alongside inline scripts
+        let stub_hashes = if self.config.csp.enabled {
+            self.stub_script_hashes(ctx)
         } else {
-            loader_script
+            Vec::new()
         };
+        let mut header_policy = None;
 
         // Find HTML files and inject
         // Only inject into index.html (the main entry point)
@@ -364,10 +1316,23 @@ impl Plugin for InjectPlugin {
             };
 
             // Update file references to use hashed versions
-            let updated_html = self.update_file_references(&html_content, ctx);
+            let mut updated_html = self.update_file_references(&html_content, &html_path, ctx);
 
-            // Inject loader
-            let injected_html = self.inject_into_html(&updated_html, &loader_script);
+            // Add Subresource Integrity attributes
+            if self.config.sri.enabled {
+                updated_html = self.add_integrity_attributes(&updated_html, ctx)?;
+            }
+
+            // Inject resource hints and loader
+            let mut tags = self.hint_tags(&hints, &html_path, ctx);
+            if let Some(loader) = &loader {
+                tags.push_str(&self.loader_tag(loader, &html_path, ctx));
+            }
+            let injected_html = if tags.is_empty() {
+                updated_html
+            } else {
+                self.inject_into_html(&updated_html, &tags)?
+            };
 
             // Minify HTML (index.html was skipped by minify plugin, so this is the first minification)
             let new_html = match minify_html(injected_html.as_bytes()) {
@@ -378,6 +1343,50 @@ impl Plugin for InjectPlugin {
                 }
             };
 
+            // Content-Security-Policy (hashes must cover the final, minified scripts)
+            let new_html = if self.config.csp.enabled {
+                let html = String::from_utf8_lossy(&new_html).into_owned();
+                let mut script_hashes: Vec<String> = html::inline_scripts(&html)?
+                    .iter()
+                    .map(|script| Self::csp_hash_source(script.as_bytes()))
+                    .collect();
+                script_hashes.extend(stub_hashes.iter().cloned());
+
+                let policy = self.build_csp(&script_hashes, self.runs_blob_scripts(ctx));
+                info!(
+                    "  Content-Security-Policy: {} script hashes",
+                    script_hashes.len()
+                );
+
+                match self.config.csp.mode {
+                    CspMode::Meta => {
+                        for directive in META_IGNORED_DIRECTIVES {
+                            if self.config.csp.directives.contains_key(*directive) {
+                                warn!(
+                                    "CSP directive '{}' is ignored in a <meta> policy; use headers mode",
+                                    directive
+                                );
+                            }
+                        }
+                        if html
+                            .to_ascii_lowercase()
+                            .contains("http-equiv=content-security-policy")
+                        {
+                            warn!(
+                                "index.html already declares a Content-Security-Policy; both will be enforced"
+                            );
+                        }
+                        Self::inject_csp_meta(&html, &policy)?.into_bytes()
+                    }
+                    CspMode::Headers => {
+                        header_policy = Some(policy);
+                        new_html
+                    }
+                }
+            } else {
+                new_html
+            };
+
             // Write back
             chrysalis_core::write_file_content(&html_path, &new_html)?;
 
@@ -387,10 +1396,181 @@ impl Plugin for InjectPlugin {
             info!("  Injected into: {}", file.name);
         }
 
-        // Update chunk references in stub files (after hashing)
-        self.update_stub_references(ctx)?;
+        if let Some(policy) = header_policy {
+            self.write_csp_headers(ctx, &policy)?;
+        }
+
+        if !has_chunks {
+            info!("✓ index.html updated");
+            return Ok(());
+        }
 
         info!("✓ Chunk loader injected");
         Ok(())
     }
+
+    fn plan(&self, plan: &mut Plan) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let has_chunks = plan.files().any(|f| f.parent.is_some());
+        let has_hints = self.config.hints.enabled && !self.hint_rules.is_empty();
+        if !has_chunks && !has_hints && !self.config.sri.enabled && !self.config.csp.enabled {
+            return Ok(());
+        }
+
+        for planned in plan.files_mut() {
+            if planned.file.is_html() && planned.file.name == "index.html" {
+                planned.inject = true;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChunkPlugin, ServiceWorkerPlugin};
+    use chrysalis_config::{CspConfig, HashAlgorithm, HashEncoding, PluginsConfig};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_external_loader_follows_hash_scheme() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let script: String = (0..40000)
+            .map(|i| format!("var a{} = {};\n", i, i))
+            .collect();
+        fs::write(root.join("main.dart.js"), script).unwrap();
+        fs::write(
+            root.join("index.html"),
+            r#"<html><head></head><body><script src="main.dart.js"></script></body></html>"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("flutter_service_worker.js"),
+            r#"const RESOURCES = {"main.dart.js": "0"}; const CORE = ["main.dart.js"];"#,
+        )
+        .unwrap();
+
+        let mut ctx = BuildContext::new(root, vec![]).unwrap();
+        ctx.scan().unwrap();
+        let config = PluginsConfig::default();
+        let hash = HashConfig {
+            algorithm: HashAlgorithm::Sha256,
+            encoding: HashEncoding::Base64url,
+            hash_length: 12,
+            ..HashConfig::default()
+        };
+        let inject = InjectConfig {
+            inline_manifest: false,
+            ..config.inject.clone()
+        };
+
+        ChunkPlugin::new(config.chunk.clone())
+            .unwrap()
+            .execute(&mut ctx)
+            .await
+            .unwrap();
+        InjectPlugin::new(inject)
+            .unwrap()
+            .with_hash(&hash)
+            .unwrap()
+            .execute(&mut ctx)
+            .await
+            .unwrap();
+        ServiceWorkerPlugin::new(config.service_worker.clone())
+            .execute(&mut ctx)
+            .await
+            .unwrap();
+
+        let scheme = HashScheme::from(&hash);
+        let mut generated: Vec<String> = ctx
+            .files()
+            .filter(|f| ctx.is_generated(&f.absolute))
+            .map(|f| f.name.clone())
+            .collect();
+        generated.sort();
+        assert_eq!(generated.len(), 2);
+        for (name, prefix) in generated
+            .iter()
+            .zip(["chrysalis-loader.", "chunk-manifest."])
+        {
+            let hash = name
+                .strip_prefix(prefix)
+                .unwrap()
+                .split('.')
+                .next()
+                .unwrap();
+            assert_eq!(hash.len(), 12);
+            assert!(scheme.matches(hash));
+        }
+
+        // Both are cached by the service worker
+        let worker = fs::read_to_string(root.join("flutter_service_worker.js")).unwrap();
+        for name in &generated {
+            assert_eq!(worker.matches(name.as_str()).count(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_csp_headers_are_replaced_on_rebuild() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(
+            root.join("index.html"),
+            r#"<html><head></head><body><script src="main.dart.js"></script></body></html>"#,
+        )
+        .unwrap();
+        fs::write(root.join("main.dart.js"), "main()").unwrap();
+        fs::write(
+            root.join("_headers"),
+            "/assets/*\n  Cache-Control: max-age=31536000\n/*\n  X-Frame-Options: DENY\n  Content-Security-Policy: default-src 'none'",
+        )
+        .unwrap();
+
+        let mut ctx = BuildContext::new(root, vec![]).unwrap();
+        ctx.scan().unwrap();
+        let defaults = InjectConfig::default();
+        let plugin = InjectPlugin::new(InjectConfig {
+            csp: CspConfig {
+                enabled: true,
+                mode: CspMode::Headers,
+                ..defaults.csp.clone()
+            },
+            ..defaults
+        })
+        .unwrap();
+
+        plugin.execute(&mut ctx).await.unwrap();
+        let first = fs::read_to_string(root.join("_headers")).unwrap();
+        plugin.execute(&mut ctx).await.unwrap();
+        let headers = fs::read_to_string(root.join("_headers")).unwrap();
+
+        assert_eq!(headers, first);
+        assert_eq!(headers.matches("Content-Security-Policy").count(), 1);
+        assert!(!headers.contains("default-src 'none'"));
+        assert!(headers.starts_with(
+            "/assets/*\n  Cache-Control: max-age=31536000\n/*\n  Content-Security-Policy: "
+        ));
+        assert!(headers.ends_with("\n  X-Frame-Options: DENY\n"));
+        let file = ctx.get_file(root.join("_headers")).unwrap();
+        assert_eq!(file.content_as_str(), Some(headers.as_str()));
+    }
+
+    #[test]
+    fn test_csp_header_entry_is_appended() {
+        let headers = InjectPlugin::set_csp_header(
+            "/app/*\n  Content-Security-Policy: default-src 'none'\n",
+            "default-src 'self'",
+        );
+        assert_eq!(
+            headers,
+            "/app/*\n  Content-Security-Policy: default-src 'none'\n/*\n  Content-Security-Policy: default-src 'self'\n"
+        );
+    }
 }
@@ -5,20 +5,51 @@
 //! - Hash: Content-based hashing
 //! - Chunk: Large file chunking
 //! - Inject: Chunk loader injection
+//! - Service worker: Flutter service worker resource rewriting
+//! - Compress: Brotli/gzip/zstd precompression
+//! - Manifest: Original -> final asset mapping
+//! - External: user-supplied executables speaking a JSON protocol
+//!
+//! [`process`] runs the whole pipeline, or plans it in dry-run mode, and
+//! [`write_report`] renders an HTML bundle size report of the result.
+//! [`check_budgets`] checks the result against configured size budgets, and
+//! [`SizeDiff`] compares the output sizes of two builds, and
+//! [`update_references`] records (and rewrites) references between files.
 
+mod budget;
+mod diff;
 mod error;
+mod html;
+mod pipeline;
+mod plan;
 mod plugin;
+mod report;
 
 pub mod chunk;
+pub mod compress;
+pub mod external;
 pub mod hash;
 pub mod inject;
+pub mod manifest;
 pub mod minify;
+pub mod references;
+pub mod service_worker;
 
+pub use budget::{BudgetCheck, BudgetUnit, check_budgets};
+pub use diff::{BuildSizes, DiffStatus, FileDiff, SizeDiff, TypeDiff};
 pub use error::{PluginError, Result};
+pub use pipeline::{ProcessOptions, pipeline, process};
+pub use plan::{Plan, PlannedFile};
 pub use plugin::{Plugin, PluginContext};
+pub use references::{ReferenceGraph, update_references};
+pub use report::{render_report, write_report};
 
 pub use chunk::ChunkPlugin;
+pub use compress::CompressPlugin;
+pub use external::ExternalPlugin;
 pub use hash::HashPlugin;
 pub use inject::InjectPlugin;
+pub use manifest::{AssetManifest, ManifestPlugin};
 /// Re-export all plugins.
 pub use minify::MinifyPlugin;
+pub use service_worker::ServiceWorkerPlugin;
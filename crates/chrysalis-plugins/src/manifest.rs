@@ -0,0 +1,228 @@
+//! Asset manifest plugin.
+
+use crate::{Plugin, PluginError, Result};
+use chrysalis_config::ManifestConfig;
+use chrysalis_core::{BuildContext, FileInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Current manifest format version.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Asset manifest written to the output directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssetManifest {
+    /// Manifest format version.
+    pub version: u32,
+
+    /// Entries keyed by original relative path.
+    pub files: BTreeMap<String, AssetEntry>,
+}
+
+/// A single output file in the asset manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetEntry {
+    /// Final relative path (after hashing).
+    pub file: String,
+
+    /// Final size in bytes.
+    pub size: u64,
+
+    /// Content hash of the final bytes.
+    pub hash: String,
+
+    /// Chunk files (final relative paths, in load order).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<String>,
+
+    /// Sizes of precompressed variants, keyed by encoding.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub compressed: BTreeMap<String, u64>,
+}
+
+impl AssetManifest {
+    /// Load a manifest from disk.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = chrysalis_core::read_file_content(path)?;
+        serde_json::from_slice(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid asset manifest: {}", e).into())
+    }
+}
+
+/// Convert a relative path to a URL-style string.
+pub(crate) fn to_url_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Manifest plugin writes the original -> final asset mapping to disk.
+pub struct ManifestPlugin {
+    config: ManifestConfig,
+}
+
+impl ManifestPlugin {
+    /// Create a new manifest plugin.
+    pub fn new(config: ManifestConfig) -> Self {
+        Self { config }
+    }
+
+    /// Build a lookup of final relative path -> original relative paths.
+    ///
+    /// Files merged by hashing because their content is identical share a
+    /// final path, so it can have several originals (sorted).
+    fn all_original_paths(ctx: &BuildContext) -> HashMap<PathBuf, Vec<PathBuf>> {
+        let mapping = ctx.file_mapping();
+        let renamed_to: HashSet<&PathBuf> = mapping.values().collect();
+        let mut originals: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+
+        for original in mapping.keys().filter(|old| !renamed_to.contains(old)) {
+            // Follow rename chains to the final name (bounded in case of cycles)
+            let mut current = original;
+            for _ in 0..mapping.len() {
+                match mapping.get(current) {
+                    Some(new) => current = new,
+                    None => break,
+                }
+            }
+            originals
+                .entry(current.clone())
+                .or_default()
+                .push(original.clone());
+        }
+
+        for paths in originals.values_mut() {
+            paths.sort();
+        }
+        originals
+    }
+
+    /// Build a lookup of final relative path -> original relative path (the
+    /// first one, for files merged by hashing).
+    pub(crate) fn original_paths(ctx: &BuildContext) -> HashMap<PathBuf, PathBuf> {
+        Self::all_original_paths(ctx)
+            .into_iter()
+            .filter_map(|(current, originals)| Some((current, originals.into_iter().next()?)))
+            .collect()
+    }
+
+    /// Create a manifest entry for a file.
+    fn entry_for(file: &mut FileInfo, chunks: Vec<String>) -> std::io::Result<AssetEntry> {
+        let content = file.load_content()?;
+        let hash = chrysalis_core::calculate_hash(content, 32);
+        let size = content.len() as u64;
+
+        Ok(AssetEntry {
+            file: to_url_path(&file.relative),
+            size,
+            hash,
+            chunks,
+            compressed: file.compressed_sizes.clone(),
+        })
+    }
+
+    /// Generate the asset manifest from the build context.
+    pub fn generate_manifest(&self, ctx: &mut BuildContext) -> AssetManifest {
+        let originals = Self::all_original_paths(ctx);
+        let build_dir = ctx.build_dir().to_path_buf();
+
+        let chunk_lists: HashMap<PathBuf, Vec<String>> = ctx
+            .chunks()
+            .iter()
+            .map(|(parent, chunk_paths)| {
+                let chunks = chunk_paths
+                    .iter()
+                    .filter_map(|p| pathdiff::diff_paths(p, &build_dir))
+                    .map(|p| to_url_path(&p))
+                    .collect();
+                (parent.clone(), chunks)
+            })
+            .collect();
+
+        let file_paths: Vec<_> = ctx
+            .files()
+            .filter(|f| to_url_path(&f.relative) != self.config.file_name)
+            .map(|f| f.absolute.clone())
+            .collect();
+
+        let mut manifest = AssetManifest {
+            version: MANIFEST_VERSION,
+            files: BTreeMap::new(),
+        };
+
+        for file_path in file_paths {
+            let file = ctx.get_file_mut(&file_path).unwrap();
+            let file_originals = originals
+                .get(&file.relative)
+                .cloned()
+                .unwrap_or_else(|| vec![file.relative.clone()]);
+            let chunks = chunk_lists.get(&file_path).cloned().unwrap_or_default();
+
+            match Self::entry_for(file, chunks) {
+                Ok(entry) => {
+                    for original in file_originals {
+                        manifest.files.insert(to_url_path(&original), entry.clone());
+                    }
+                }
+                Err(e) => warn!("Failed to load {}: {}", file.name, e),
+            }
+        }
+
+        manifest
+    }
+}
+
+#[async_trait::async_trait]
+impl Plugin for ManifestPlugin {
+    fn name(&self) -> &str {
+        "manifest"
+    }
+
+    fn runs_after(&self) -> &[&str] {
+        // Records final names, sizes and compressed variants
+        &[
+            "minify",
+            "chunk",
+            "hash",
+            "inject",
+            "service_worker",
+            "compress",
+        ]
+    }
+
+    async fn execute(&self, ctx: &mut BuildContext) -> Result<()> {
+        if !self.config.enabled {
+            info!("Asset manifest disabled");
+            return Ok(());
+        }
+
+        info!("Writing asset manifest...");
+
+        let manifest = self.generate_manifest(ctx);
+        let content = serde_json::to_vec_pretty(&manifest).map_err(|e| {
+            PluginError::Other(anyhow::anyhow!("Failed to serialize manifest: {}", e))
+        })?;
+
+        let manifest_path = ctx.build_dir().join(&self.config.file_name);
+        chrysalis_core::write_file_content(&manifest_path, &content)?;
+
+        // Track the manifest so later steps see it as an output file
+        if ctx.get_file(&manifest_path).is_none() {
+            let file = FileInfo::new(
+                manifest_path.clone(),
+                PathBuf::from(&self.config.file_name),
+                content.len() as u64,
+            );
+            ctx.add_file(file)?;
+        } else if let Some(file) = ctx.get_file_mut(&manifest_path) {
+            file.set_content(content);
+        }
+
+        info!(
+            "✓ Asset manifest written: {} ({} entries)",
+            self.config.file_name,
+            manifest.files.len()
+        );
+        Ok(())
+    }
+}
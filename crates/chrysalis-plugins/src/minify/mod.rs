@@ -5,9 +5,9 @@ mod html;
 mod js;
 mod json;
 
-use crate::{Plugin, Result};
+use crate::{Plan, PlannedFile, Plugin, Result};
 use chrysalis_config::MinifyConfig;
-use chrysalis_core::BuildContext;
+use chrysalis_core::{BuildContext, Cache, FileInfo};
 use tracing::{info, warn};
 
 pub use css::minify_css;
@@ -15,10 +15,15 @@ pub use html::minify_html;
 pub use js::minify_js;
 pub use json::minify_json;
 
+/// A minifier for one file type: (kind, function).
+type Minifier = (&'static str, fn(&[u8]) -> Result<Vec<u8>>);
+
 /// Minification plugin.
 pub struct MinifyPlugin {
     config: MinifyConfig,
     skip_index_html: bool,
+    cache: Option<Cache>,
+    fingerprint: String,
 }
 
 impl MinifyPlugin {
@@ -28,9 +33,75 @@ impl MinifyPlugin {
     /// * `config` - Minification configuration
     /// * `skip_index_html` - Whether to skip index.html (true if inject plugin will handle it)
     pub fn new(config: MinifyConfig, skip_index_html: bool) -> Self {
+        // Settings that affect the output are part of every cache key
+        let fingerprint = serde_json::to_string(&config).unwrap_or_default();
+
         Self {
             config,
             skip_index_html,
+            cache: None,
+            fingerprint,
+        }
+    }
+
+    /// Reuse minification results from a persistent cache.
+    pub fn with_cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Check if index.html is left to the inject plugin.
+    fn skips(&self, file: &FileInfo) -> bool {
+        self.skip_index_html && file.is_html() && file.name == "index.html"
+    }
+
+    /// Minifier for the file's type, if enabled.
+    fn minifier(&self, file: &FileInfo) -> Option<Minifier> {
+        if file.is_js() && self.config.minify_js {
+            Some(("JS", minify_js))
+        } else if file.is_css() && self.config.minify_css {
+            Some(("CSS", minify_css))
+        } else if file.is_html() && self.config.minify_html {
+            Some(("HTML", minify_html))
+        } else if file.is_json() && self.config.minify_json {
+            Some(("JSON", minify_json))
+        } else {
+            None
+        }
+    }
+
+    /// Minify a loaded file, reusing the cached result if its bytes are unchanged.
+    ///
+    /// Returns the minified content and whether it came from the cache.
+    fn minify_file(&self, file: &FileInfo, cache: Option<&Cache>) -> Option<(Vec<u8>, bool)> {
+        let (kind, minify) = self.minifier(file)?;
+        let content = file.content.as_ref()?;
+
+        // Keyed by minifier identity, settings and input bytes
+        let cached = cache.map(|cache| {
+            let namespace = format!("minify-{}@{}", kind, env!("CARGO_PKG_VERSION"));
+            (cache, Cache::key(&namespace, &self.fingerprint, content))
+        });
+
+        if let Some((cache, key)) = &cached
+            && let Some(minified) = cache.get(key)
+        {
+            return Some((minified, true));
+        }
+
+        match minify(content) {
+            Ok(minified) => {
+                if let Some((cache, key)) = &cached
+                    && let Err(e) = cache.put(key, &minified)
+                {
+                    warn!("Failed to cache {}: {}", file.name, e);
+                }
+                Some((minified, false))
+            }
+            Err(e) => {
+                warn!("Failed to minify {} {}: {}", kind, file.name, e);
+                None
+            }
         }
     }
 }
@@ -48,82 +119,91 @@ impl Plugin for MinifyPlugin {
         }
 
         info!("Minifying files...");
-        let mut minified_count = 0;
-
-        // Collect files to process
-        let files: Vec<_> = ctx.files().map(|f| f.absolute.clone()).collect();
 
-        for file_path in files {
-            let file = ctx.get_file_mut(&file_path).unwrap();
-
-            // Skip index.html if inject plugin will handle it
-            if self.skip_index_html && file.is_html() && file.name == "index.html" {
-                info!("  Skipping index.html (will be minified after injection)");
-                continue;
-            }
+        // Collect files to process, in a stable order
+        let workers = ctx.workers()?.clone();
+        let mut files: Vec<&mut FileInfo> = ctx
+            .files_mut()
+            .filter(|file| {
+                // Skip index.html if inject plugin will handle it
+                if self.skips(file) {
+                    info!("  Skipping index.html (will be minified after injection)");
+                    return false;
+                }
+                self.minifier(file).is_some()
+            })
+            .collect();
+        files.sort_by(|a, b| a.relative.cmp(&b.relative));
 
+        // Minify and write back on the worker pool
+        let results = workers.map(files, |file| -> Result<Option<(u64, u64, bool)>> {
             // Load content
             if let Err(e) = file.load_content() {
                 warn!("Failed to load {}: {}", file.name, e);
-                continue;
+                return Ok(None);
             }
 
             let original_size = file.size;
-            let content = file.content.as_ref().unwrap();
-
-            let minified = if file.is_js() && self.config.minify_js {
-                match minify_js(content) {
-                    Ok(m) => Some(m),
-                    Err(e) => {
-                        warn!("Failed to minify JS {}: {}", file.name, e);
-                        None
-                    }
-                }
-            } else if file.is_css() && self.config.minify_css {
-                match minify_css(content) {
-                    Ok(m) => Some(m),
-                    Err(e) => {
-                        warn!("Failed to minify CSS {}: {}", file.name, e);
-                        None
-                    }
-                }
-            } else if file.is_html() && self.config.minify_html {
-                match minify_html(content) {
-                    Ok(m) => Some(m),
-                    Err(e) => {
-                        warn!("Failed to minify HTML {}: {}", file.name, e);
-                        None
-                    }
-                }
-            } else if file.is_json() && self.config.minify_json {
-                match minify_json(content) {
-                    Ok(m) => Some(m),
-                    Err(e) => {
-                        warn!("Failed to minify JSON {}: {}", file.name, e);
-                        None
-                    }
-                }
-            } else {
-                None
+            let Some((minified_content, cached)) = self.minify_file(file, self.cache.as_ref())
+            else {
+                return Ok(None);
             };
+            let new_size = minified_content.len() as u64;
 
-            if let Some(minified_content) = minified {
-                let new_size = minified_content.len() as u64;
+            // Write to disk
+            chrysalis_core::write_file_content(&file.absolute, &minified_content)?;
 
-                // Write to disk
-                chrysalis_core::write_file_content(&file_path, &minified_content)?;
+            // Update file
+            file.set_content(minified_content);
+            file.minified_size = Some(new_size);
 
-                // Update file
-                let file = ctx.get_file_mut(&file_path).unwrap();
-                file.set_content(minified_content);
+            Ok(Some((original_size, new_size, cached)))
+        });
 
-                // Record stats
+        // Record stats
+        let mut minified_count = 0;
+        for result in results {
+            if let Some((original_size, new_size, cached)) = result? {
                 ctx.stats_mut().record_minification(original_size, new_size);
+                if cached {
+                    ctx.stats_mut().record_cache_hit();
+                }
                 minified_count += 1;
             }
         }
 
-        info!("✓ Minified {} files", minified_count);
+        info!(
+            "✓ Minified {} files ({} from cache)",
+            minified_count,
+            ctx.stats().cached_files
+        );
+        Ok(())
+    }
+
+    fn plan(&self, plan: &mut Plan) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        // Minify in memory for accurate size estimates (the cache is not used,
+        // so nothing is written)
+        let workers = plan.workers().clone();
+        let files: Vec<&mut PlannedFile> = plan
+            .files_mut()
+            .filter(|p| !self.skips(&p.file) && self.minifier(&p.file).is_some())
+            .collect();
+
+        workers.map(files, |planned| {
+            if let Err(e) = planned.file.load_content() {
+                warn!("Failed to load {}: {}", planned.file.name, e);
+                return;
+            }
+            if let Some((minified, _)) = self.minify_file(&planned.file, None) {
+                planned.file.set_content(minified);
+                planned.minify = true;
+            }
+        });
+
         Ok(())
     }
 }
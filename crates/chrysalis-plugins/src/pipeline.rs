@@ -0,0 +1,231 @@
+//! Web post-processing pipeline.
+
+use crate::{
+    ChunkPlugin, CompressPlugin, ExternalPlugin, HashPlugin, InjectPlugin, ManifestPlugin,
+    MinifyPlugin, Plan, Plugin, PluginError, Result, ServiceWorkerPlugin,
+};
+use chrysalis_config::{PipelineStep, PluginsConfig, WebConfig};
+use chrysalis_core::{BuildContext, Cache};
+use std::path::PathBuf;
+use std::time::Instant;
+use tracing::{error, info};
+
+/// Options for [`process`].
+#[derive(Debug, Clone, Default)]
+pub struct ProcessOptions {
+    /// Plan the pipeline without touching disk.
+    pub dry_run: bool,
+
+    /// Persistent cache shared across builds.
+    pub cache: Option<Cache>,
+
+    /// Directory external plugins run in (defaults to the current directory).
+    pub working_dir: Option<PathBuf>,
+}
+
+/// Build the plugin pipeline, in execution order.
+///
+/// Steps come from `pipeline` in the web configuration, or are derived from
+/// the enabled plugins. The ordering constraints each plugin declares are
+/// checked before anything runs.
+pub fn pipeline(config: &WebConfig, options: &ProcessOptions) -> Result<Vec<Box<dyn Plugin>>> {
+    let steps = config.pipeline_steps();
+    let step_config = |step: &PipelineStep| -> Result<PluginsConfig> {
+        Ok(config
+            .plugins
+            .with_step(step)
+            .map_err(anyhow::Error::from)?)
+    };
+
+    // Inject rewrites (and minifies) index.html when it has something to add
+    let has_chunk = steps.iter().any(|s| s.name == "chunk");
+    let rewrites_html = match steps.iter().find(|s| s.name == "inject") {
+        Some(step) => {
+            let inject = step_config(step)?.inject;
+            has_chunk || inject.sri.enabled || inject.csp.enabled
+        }
+        None => false,
+    };
+
+    // Generated files are named like every other hashed file
+    let hash = match steps.iter().find(|s| s.name == "hash") {
+        Some(step) => step_config(step)?.hash,
+        None => config.plugins.hash.clone(),
+    };
+
+    let mut plugins: Vec<Box<dyn Plugin>> = Vec::new();
+    for step in &steps {
+        let plugins_config = step_config(step)?;
+        let plugin: Box<dyn Plugin> = match step.name.as_str() {
+            "minify" => {
+                let mut minify = MinifyPlugin::new(plugins_config.minify, rewrites_html);
+                if let Some(cache) = &options.cache {
+                    minify = minify.with_cache(cache.clone());
+                }
+                Box::new(minify)
+            }
+            "chunk" => Box::new(ChunkPlugin::new(plugins_config.chunk)?),
+            "hash" => Box::new(HashPlugin::new(plugins_config.hash)?),
+            "inject" => Box::new(InjectPlugin::new(plugins_config.inject)?.with_hash(&hash)?),
+            "service_worker" => Box::new(ServiceWorkerPlugin::new(plugins_config.service_worker)),
+            "compress" => Box::new(CompressPlugin::new(plugins_config.compress)?),
+            "manifest" => Box::new(ManifestPlugin::new(plugins_config.manifest)),
+            name => match plugins_config.external.get(name) {
+                Some(external) => {
+                    let mut plugin = ExternalPlugin::new(name, external.clone());
+                    if let Some(dir) = &options.working_dir {
+                        plugin = plugin.with_working_dir(dir);
+                    }
+                    Box::new(plugin)
+                }
+                None => {
+                    return Err(PluginError::InvalidPipeline(format!(
+                        "unknown plugin '{}'",
+                        name
+                    )));
+                }
+            },
+        };
+        plugins.push(plugin);
+    }
+
+    check_order(&plugins)?;
+    Ok(plugins)
+}
+
+/// Check that no plugin appears twice and that every plugin runs after the
+/// plugins it declares in [`Plugin::runs_after`].
+fn check_order(plugins: &[Box<dyn Plugin>]) -> Result<()> {
+    for (i, plugin) in plugins.iter().enumerate() {
+        if plugins[..i].iter().any(|p| p.name() == plugin.name()) {
+            return Err(PluginError::InvalidPipeline(format!(
+                "'{}' is listed more than once",
+                plugin.name()
+            )));
+        }
+
+        for before in plugin.runs_after() {
+            if plugins[i + 1..].iter().any(|p| p.name() == *before) {
+                return Err(PluginError::InvalidPipeline(format!(
+                    "'{}' must run after '{}'",
+                    plugin.name(),
+                    before
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Post-process a scanned build directory.
+///
+/// In dry-run mode nothing is written and the plan is returned; otherwise
+/// every plugin is executed and `None` is returned.
+pub async fn process(
+    ctx: &mut BuildContext,
+    config: &WebConfig,
+    options: &ProcessOptions,
+) -> Result<Option<Plan>> {
+    let plugins = pipeline(config, options)?;
+
+    if options.dry_run {
+        let mut plan = Plan::new(ctx)?;
+        for plugin in &plugins {
+            info!("Planning plugin: {}", plugin.name());
+            plan.plugins.push(plugin.name().to_string());
+            plugin.plan(&mut plan)?;
+        }
+        plan.finish();
+        return Ok(Some(plan));
+    }
+
+    for plugin in &plugins {
+        info!("Running plugin: {}", plugin.name());
+        let start = Instant::now();
+        if let Err(e) = plugin.execute(ctx).await {
+            error!("Plugin '{}' failed: {}", plugin.name(), e);
+            return Err(e);
+        }
+        ctx.stats_mut()
+            .record_plugin_timing(plugin.name(), start.elapsed());
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::to_url_path;
+    use chrysalis_config::HashConfig;
+    use chrysalis_core::HashScheme;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_generated_files_follow_hash_step_options() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let script: String = (0..40000)
+            .map(|i| format!("var a{} = {};\n", i, i))
+            .collect();
+        fs::write(root.join("main.dart.js"), script).unwrap();
+        fs::write(
+            root.join("index.html"),
+            r#"<html><head></head><body><script src="main.dart.js"></script></body></html>"#,
+        )
+        .unwrap();
+
+        let config = WebConfig {
+            pipeline: serde_json::from_value(serde_json::json!([
+                "chunk",
+                {
+                    "name": "hash",
+                    "options": {
+                        "algorithm": "sha256",
+                        "encoding": "base32",
+                        "hash_length": 12,
+                        "file_name": "static/[name].[hash].[ext]",
+                    },
+                },
+                {"name": "inject", "options": {"inline_manifest": false}},
+            ]))
+            .unwrap(),
+            ..WebConfig::default()
+        };
+
+        let mut ctx = BuildContext::new(root, vec![]).unwrap();
+        ctx.scan().unwrap();
+        process(&mut ctx, &config, &ProcessOptions::default())
+            .await
+            .unwrap();
+
+        let scheme = HashScheme::from(&HashConfig {
+            algorithm: chrysalis_config::HashAlgorithm::Sha256,
+            encoding: chrysalis_config::HashEncoding::Base32,
+            hash_length: 12,
+            ..HashConfig::default()
+        });
+        let mut generated: Vec<String> = ctx
+            .files()
+            .filter(|f| ctx.is_generated(&f.absolute))
+            .map(|f| to_url_path(&f.relative))
+            .collect();
+        generated.sort();
+        assert_eq!(generated.len(), 2, "{:?}", generated);
+        for (path, prefix) in generated
+            .iter()
+            .zip(["static/chrysalis-loader.", "static/chunk-manifest."])
+        {
+            let hash = path
+                .strip_prefix(prefix)
+                .unwrap()
+                .split('.')
+                .next()
+                .unwrap();
+            assert_eq!(hash.len(), 12, "{}", path);
+            assert!(scheme.matches(hash), "{}", path);
+        }
+    }
+}
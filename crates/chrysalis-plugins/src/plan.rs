@@ -0,0 +1,177 @@
+//! Dry-run planning.
+//!
+//! A [`Plan`] simulates the pipeline in memory: each plugin applies its own
+//! decision logic to the simulated files in pipeline order, so later plugins
+//! see the names and sizes earlier ones would produce. Nothing is written.
+
+use crate::Result;
+use crate::manifest::to_url_path;
+use chrysalis_core::{BuildContext, FileInfo, WorkerPool};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// What the pipeline would do to one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedFile {
+    /// Path before processing (relative to the build directory).
+    pub original: String,
+
+    /// Path after processing.
+    pub path: String,
+
+    /// Size before processing (0 for generated files).
+    pub size: u64,
+
+    /// Estimated size after processing.
+    pub estimated_size: u64,
+
+    /// Whether the file would be minified.
+    pub minify: bool,
+
+    /// Chunk files (final paths) the file would be split into.
+    pub chunks: Vec<String>,
+
+    /// Whether the file would be renamed with a content hash.
+    pub hash: bool,
+
+    /// Whether the chunk loader or integrity/CSP data would be injected.
+    pub inject: bool,
+
+    /// Whether precompressed variants would be written.
+    pub compress: bool,
+
+    /// Original path of the file this one was generated from (chunks).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// Whether the file would be removed (non-JS chunk parents).
+    pub removed: bool,
+
+    /// Simulated file state used for plugin decisions.
+    #[serde(skip)]
+    pub(crate) file: FileInfo,
+}
+
+impl PlannedFile {
+    /// Plan an existing file.
+    fn existing(file: &FileInfo) -> Self {
+        let path = to_url_path(&file.relative);
+        Self {
+            original: path.clone(),
+            path,
+            size: file.size,
+            estimated_size: file.size,
+            minify: false,
+            chunks: Vec::new(),
+            hash: false,
+            inject: false,
+            compress: false,
+            parent: None,
+            removed: false,
+            file: file.clone(),
+        }
+    }
+
+    /// Simulate a rename to a new path relative to the build directory.
+    pub(crate) fn rename(&mut self, build_dir: &Path, relative: PathBuf) {
+        let renamed = FileInfo::new(build_dir.join(&relative), relative, self.file.size);
+        self.file.absolute = renamed.absolute;
+        self.file.relative = renamed.relative;
+        self.file.name = renamed.name;
+        self.file.dir = renamed.dir;
+        self.file.ext = renamed.ext;
+    }
+}
+
+/// Dry-run plan of the whole pipeline.
+#[derive(Debug, Clone, Serialize)]
+pub struct Plan {
+    /// Build directory that was scanned.
+    pub build_dir: PathBuf,
+
+    /// Plugins in pipeline order.
+    pub plugins: Vec<String>,
+
+    /// Files, in scan order (by original path), followed by generated files.
+    pub files: Vec<PlannedFile>,
+
+    #[serde(skip)]
+    workers: WorkerPool,
+}
+
+impl Plan {
+    /// Create a plan from a scanned build context.
+    pub fn new(ctx: &BuildContext) -> Result<Self> {
+        let mut files: Vec<_> = ctx.files().map(PlannedFile::existing).collect();
+        files.sort_by(|a, b| a.original.cmp(&b.original));
+
+        Ok(Self {
+            build_dir: ctx.build_dir().to_path_buf(),
+            plugins: Vec::new(),
+            files,
+            workers: ctx.workers()?.clone(),
+        })
+    }
+
+    /// Worker pool for per-file planning work.
+    pub(crate) fn workers(&self) -> &WorkerPool {
+        &self.workers
+    }
+
+    /// Files that still exist at this point of the pipeline.
+    pub(crate) fn files_mut(&mut self) -> impl Iterator<Item = &mut PlannedFile> {
+        self.files.iter_mut().filter(|f| !f.removed)
+    }
+
+    /// Files that still exist at this point of the pipeline.
+    pub(crate) fn files(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.files.iter().filter(|f| !f.removed)
+    }
+
+    /// Simulate a file created by the pipeline.
+    pub(crate) fn add_generated(&mut self, relative: PathBuf, content: Vec<u8>, parent: &str) {
+        let absolute = self.build_dir.join(&relative);
+        let mut file = FileInfo::new(absolute, relative, 0);
+        file.set_content(content);
+
+        let mut planned = PlannedFile::existing(&file);
+        planned.size = 0;
+        planned.parent = Some(parent.to_string());
+        self.files.push(planned);
+    }
+
+    /// Record final paths and sizes once every plugin has planned.
+    pub(crate) fn finish(&mut self) {
+        for planned in &mut self.files {
+            planned.path = to_url_path(&planned.file.relative);
+            planned.estimated_size = if planned.removed {
+                0
+            } else {
+                planned.file.size
+            };
+        }
+
+        let chunk_paths: Vec<(String, String)> = self
+            .files
+            .iter()
+            .filter_map(|f| Some((f.parent.clone()?, f.path.clone())))
+            .collect();
+        for planned in &mut self.files {
+            planned.chunks = chunk_paths
+                .iter()
+                .filter(|(parent, _)| *parent == planned.original)
+                .map(|(_, path)| path.clone())
+                .collect();
+        }
+    }
+
+    /// Total size before processing.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    /// Estimated total size after processing.
+    pub fn estimated_total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.estimated_size).sum()
+    }
+}
@@ -1,6 +1,6 @@
 //! Plugin trait and context.
 
-use crate::Result;
+use crate::{Plan, Result};
 use chrysalis_core::BuildContext;
 
 /// Plugin trait for build transformations.
@@ -9,8 +9,18 @@ pub trait Plugin: Send + Sync {
     /// Plugin name.
     fn name(&self) -> &str;
 
+    /// Plugins that must run before this one when both are in the pipeline.
+    fn runs_after(&self) -> &[&str] {
+        &[]
+    }
+
     /// Execute the plugin.
     async fn execute(&self, ctx: &mut BuildContext) -> Result<()>;
+
+    /// Record what `execute` would do in a dry run, without touching disk.
+    fn plan(&self, _plan: &mut Plan) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Plugin execution context with progress tracking.
@@ -0,0 +1,172 @@
+//! CSS references using lightningcss.
+
+use super::Reference;
+use lightningcss::dependencies::Location;
+use lightningcss::rules::CssRule;
+use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+use lightningcss::values::url::Url;
+use lightningcss::visit_types;
+use lightningcss::visitor::{Visit, VisitTypes, Visitor};
+use std::convert::Infallible;
+use tracing::debug;
+
+/// Collects the URLs of `url()` values and `@import` rules.
+#[derive(Default)]
+struct UrlCollector {
+    /// URL and where its token starts (1-based line, UTF-16 column).
+    urls: Vec<(String, u32, u32)>,
+}
+
+impl<'i> Visitor<'i> for UrlCollector {
+    type Error = Infallible;
+
+    fn visit_types(&self) -> VisitTypes {
+        visit_types!(URLS | RULES)
+    }
+
+    fn visit_url(&mut self, url: &mut Url<'i>) -> Result<(), Self::Error> {
+        self.urls
+            .push((url.url.to_string(), url.loc.line, url.loc.column));
+        Ok(())
+    }
+
+    fn visit_rule(&mut self, rule: &mut CssRule<'i>) -> Result<(), Self::Error> {
+        if let CssRule::Import(import) = rule {
+            // Rule locations are 0-based lines
+            self.urls.push((
+                import.url.to_string(),
+                import.loc.line + 1,
+                import.loc.column,
+            ));
+        }
+        rule.visit_children(self)
+    }
+}
+
+/// Byte offset of a 1-based line and UTF-16 column.
+fn offset_of(source: &str, location: Location) -> Option<usize> {
+    let line_start = if location.line <= 1 {
+        0
+    } else {
+        source
+            .match_indices('\n')
+            .nth(location.line as usize - 2)
+            .map(|(i, _)| i + 1)?
+    };
+
+    let mut units = 1;
+    for (i, c) in source[line_start..].char_indices() {
+        if units >= location.column {
+            return Some(line_start + i);
+        }
+        units += c.len_utf16() as u32;
+    }
+    None
+}
+
+/// Locate the URL text of a `url(..)`, string or `@import` token at `start`.
+fn url_span(source: &str, start: usize, url: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut i = start;
+
+    // `@import` followed by a string or url()
+    if bytes.get(i) == Some(&b'@') {
+        i += "@import".len();
+    }
+    while bytes.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+        i += 1;
+    }
+    if source
+        .get(i..i + 4)
+        .is_some_and(|s| s.eq_ignore_ascii_case("url("))
+    {
+        i += 4;
+        while bytes.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+            i += 1;
+        }
+    }
+    if matches!(bytes.get(i), Some(b'"' | b'\'')) {
+        i += 1;
+    }
+
+    // Escaped URLs would need re-escaping; leave them alone
+    source
+        .get(i..i + url.len())
+        .filter(|s| *s == url)
+        .map(|_| i)
+}
+
+/// Find references in a stylesheet: `url()` values and `@import` rules.
+pub fn css_references(source: &str) -> Vec<Reference> {
+    let mut stylesheet = match StyleSheet::parse(source, ParserOptions::default()) {
+        Ok(stylesheet) => stylesheet,
+        Err(e) => {
+            debug!("Skipping references, parse error: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut collector = UrlCollector::default();
+    let Ok(()) = stylesheet.visit(&mut collector);
+
+    collector
+        .urls
+        .into_iter()
+        .filter_map(|(url, line, column)| {
+            let start = offset_of(source, Location { line, column })?;
+            let offset = url_span(source, start, &url)?;
+            Reference::from_url(&url, offset)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(source: &str) -> Vec<String> {
+        css_references(source)
+            .into_iter()
+            .map(|r| {
+                assert_eq!(&source[r.span.clone()], r.path);
+                r.path
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_css_references() {
+        let source = r#"@import "base.css";
+@import url(theme.css);
+.a { background: url("img/a.png"); }
+.b { background: url( 'img/b.png?v=2' ); }
+.c { background: url(img/c.png); }
+@font-face { src: url(fonts/x.woff2) format("woff2"); }"#;
+        assert_eq!(
+            paths(source),
+            [
+                "base.css",
+                "theme.css",
+                "img/a.png",
+                "img/b.png",
+                "img/c.png",
+                "fonts/x.woff2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_css_untouched() {
+        let source = r#"/* url(commented.png) */
+.a { content: "img/a.png"; background: url(data:image/png;base64,AAAA); }
+.b { background: url(https://example.com/b.png); }
+.c { background: url("img/\61.png"); }"#;
+        assert!(paths(source).is_empty());
+    }
+
+    #[test]
+    fn test_css_multibyte_offsets() {
+        let source = ".é::before { content: \"→\"; background: url(img/ü.png); }";
+        assert_eq!(paths(source), ["img/ü.png"]);
+    }
+}
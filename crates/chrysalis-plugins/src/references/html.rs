@@ -0,0 +1,249 @@
+//! HTML references from the tokenized document.
+//!
+//! Attributes are read from start tags; inline scripts and styles are handed
+//! to the JavaScript and CSS extractors. Comments and raw text are skipped.
+
+use super::Reference;
+use super::css::css_references;
+use super::js::{js_references, json_references};
+use crate::html::Document;
+use std::ops::Range;
+
+/// Attributes that hold a single URL.
+const URL_ATTRIBUTES: &[&str] = &["src", "href", "poster", "data", "action", "formaction"];
+
+/// An attribute of a start tag.
+#[derive(Debug, Clone)]
+struct Attribute {
+    /// Lowercased name.
+    pub name: String,
+
+    /// Byte range of the value within the tag, without quotes.
+    pub value: Range<usize>,
+}
+
+/// Parse the attributes of a start tag (without the closing `>`).
+///
+/// lol_html does not report where attribute values are, so the spans to
+/// rewrite come from scanning the tag it tokenized.
+fn parse_attributes(tag: &str) -> Vec<Attribute> {
+    let bytes = tag.as_bytes();
+    let mut attributes = Vec::new();
+
+    // Skip `<` and the tag name
+    let mut i = 1;
+    while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+
+    while i < bytes.len() {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            i += 1;
+        }
+        let name_start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !b"=/".contains(&bytes[i]) {
+            i += 1;
+        }
+        if name_start == i {
+            break;
+        }
+        let name = tag[name_start..i].to_ascii_lowercase();
+
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+
+        let mut value = i..i;
+        if bytes.get(i) == Some(&b'=') {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            match bytes.get(i) {
+                Some(&q) if q == b'"' || q == b'\'' => {
+                    let value_start = i + 1;
+                    let value_end = tag[value_start..]
+                        .find(q as char)
+                        .map(|e| value_start + e)
+                        .unwrap_or(tag.len());
+                    value = value_start..value_end;
+                    i = value_end + 1;
+                }
+                _ => {
+                    let value_start = i;
+                    while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                        i += 1;
+                    }
+                    value = value_start..i;
+                }
+            }
+        }
+
+        attributes.push(Attribute { name, value });
+    }
+
+    attributes
+}
+
+/// References in a `srcset` value: the URL of each candidate.
+fn srcset_references(value: &str, offset: usize) -> Vec<Reference> {
+    let mut references = Vec::new();
+    let mut pos = 0;
+
+    for candidate in value.split(',') {
+        let leading = candidate.len() - candidate.trim_start().len();
+        let url = candidate.split_whitespace().next().unwrap_or("");
+        if let Some(reference) = Reference::from_url(url, offset + pos + leading) {
+            references.push(reference);
+        }
+        pos += candidate.len() + 1;
+    }
+
+    references
+}
+
+/// Shift references found in embedded content by its offset.
+fn shifted(references: Vec<Reference>, offset: usize) -> impl Iterator<Item = Reference> {
+    references.into_iter().map(move |r| Reference {
+        span: r.span.start + offset..r.span.end + offset,
+        path: r.path,
+    })
+}
+
+/// Whether a `content` or `data-*` value is worth reading as a URL.
+///
+/// These attributes mostly hold plain text, so only values that look like a
+/// path to a file (a slash or an extension, and no spaces) are taken.
+fn looks_like_url(value: &str) -> bool {
+    let value = value.trim();
+    !value.is_empty()
+        && !value.contains(char::is_whitespace)
+        && (value.contains('/')
+            || value.rsplit_once('.').is_some_and(|(_, ext)| {
+                !ext.is_empty() && ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric())
+            }))
+}
+
+/// Find references in an HTML document.
+pub fn html_references(html: &str) -> Vec<Reference> {
+    let document = match Document::parse(html) {
+        Ok(document) => document,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return Vec::new();
+        }
+    };
+    let mut references = Vec::new();
+
+    for element in &document.elements {
+        let start = element.tag.start;
+        let attributes = parse_attributes(element.tag_text(html));
+        let get = |name: &str| {
+            attributes
+                .iter()
+                .find(|a| a.name == name)
+                .map(|a| &html[start + a.value.start..start + a.value.end])
+        };
+
+        for attribute in &attributes {
+            let value = &html[start + attribute.value.start..start + attribute.value.end];
+            let offset = start + attribute.value.start;
+            let name = attribute.name.as_str();
+            if URL_ATTRIBUTES.contains(&name) {
+                references.extend(Reference::from_url(value, offset));
+            } else if name == "srcset" || name == "imagesrcset" {
+                references.extend(srcset_references(value, offset));
+            } else if (name == "content" || name.starts_with("data-")) && looks_like_url(value) {
+                let leading = value.len() - value.trim_start().len();
+                references.extend(Reference::from_url(value.trim(), offset + leading));
+            }
+        }
+
+        // Raw text elements: scan their content with the matching parser
+        let Some(range) = element.content.clone() else {
+            continue;
+        };
+        let content = &html[range.clone()];
+        if element.name == "style" {
+            references.extend(shifted(css_references(content), range.start));
+        } else if get("src").is_none() {
+            let script_type = get("type").unwrap_or_default().to_ascii_lowercase();
+            match script_type.as_str() {
+                "" | "module" | "text/javascript" | "application/javascript" => {
+                    references.extend(shifted(js_references(content), range.start));
+                }
+                "importmap" | "application/json" | "application/ld+json" => {
+                    references.extend(shifted(json_references(content), range.start));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    references
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(html: &str) -> Vec<String> {
+        html_references(html)
+            .into_iter()
+            .map(|r| {
+                assert_eq!(&html[r.span.clone()], r.path);
+                r.path
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_html_attributes() {
+        let html = r#"<HTML><HEAD>
+<LINK REL=stylesheet HREF = 'style.css'>
+<link rel=preload imagesrcset="a-1x.png 1x, a-2x.png 2x">
+<meta property=og:image content="/img/og.png">
+</HEAD><BODY>
+<img src=logo.png srcset=" s1.png 1x,s2.png   2x" data-src="img/lazy.png">
+<script src="main.dart.js?v=1"></script>
+</BODY></HTML>"#;
+        assert_eq!(
+            paths(html),
+            [
+                "style.css",
+                "a-1x.png",
+                "a-2x.png",
+                "/img/og.png",
+                "logo.png",
+                "s1.png",
+                "s2.png",
+                "img/lazy.png",
+                "main.dart.js",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_html_inline_scripts_and_styles() {
+        let html = r#"<style>a { background: url(bg.png) }</style>
+<script>fetch("data.json"); var s = "</div>";</script>
+<script type="importmap">{"imports": {"app": "./app.js"}}</script>
+<script type="text/template"><img src="template.png"></script>"#;
+        assert_eq!(
+            paths(html),
+            ["bg.png", "data.json", "imports", "app", "./app.js"]
+        );
+    }
+
+    #[test]
+    fn test_html_untouched() {
+        let html = r#"<!-- <script src="commented.js"></script> -->
+<meta name=viewport content="width=device-width, initial-scale=1">
+<meta name=description content="A Flutter app">
+<div data-id="42" data-label="hello world"></div>
+<a href="https://example.com/page.html">x</a>
+<img src="data:image/png;base64,AAAA">
+<p>src="text.js"</p>"#;
+        assert!(paths(html).is_empty());
+    }
+}
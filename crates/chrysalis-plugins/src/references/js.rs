@@ -0,0 +1,327 @@
+//! JavaScript and JSON references using SWC.
+
+use super::Reference;
+use swc_core::common::{BytePos, FileName, GLOBALS, SourceMap, Span, sync::Lrc};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::parser::{PResult, Parser, StringInput, Syntax, lexer::Lexer};
+use swc_core::ecma::visit::{Visit, VisitWith};
+use tracing::debug;
+
+/// Functions and methods whose first argument is a URL.
+const URL_CALLS: &[&str] = &["fetch", "importScripts", "loadScript", "register"];
+
+/// Constructors whose first argument is a URL.
+const URL_CONSTRUCTORS: &[&str] = &["URL", "Worker", "SharedWorker"];
+
+/// Properties holding a URL or a list of URLs: Flutter's build config and
+/// loader options, and dart2js deferred part tables.
+const URL_PROPERTIES: &[&str] = &[
+    "mainJsPath",
+    "mainWasmPath",
+    "jsSupportRuntimePath",
+    "entrypointUrl",
+    "serviceWorkerUrl",
+    "deferredPartUris",
+];
+
+/// Collects string literals in reference positions, or every string literal
+/// for JSON.
+struct LiteralCollector<'a> {
+    source: &'a str,
+    start: BytePos,
+    /// Collect every string literal (JSON data) instead of reference sites.
+    all_strings: bool,
+    references: Vec<Reference>,
+}
+
+/// Name of a property key, for plain and string keys.
+fn prop_name(name: &PropName) -> Option<&str> {
+    match name {
+        PropName::Ident(ident) => Some(&ident.sym),
+        PropName::Str(s) => s.value.as_str(),
+        _ => None,
+    }
+}
+
+/// Name called or constructed by a callee: `f` or the last member of `a.b.f`.
+fn callee_name(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Ident(ident) => Some(&ident.sym),
+        Expr::Member(member) => match &member.prop {
+            MemberProp::Ident(ident) => Some(&ident.sym),
+            _ => None,
+        },
+        Expr::Paren(paren) => callee_name(&paren.expr),
+        _ => None,
+    }
+}
+
+impl LiteralCollector<'_> {
+    /// Record the text between the delimiters of a literal.
+    fn push(&mut self, span: Span) {
+        let lo = (span.lo.0 - self.start.0) as usize + 1;
+        let hi = (span.hi.0 - self.start.0) as usize;
+        if lo >= hi || hi > self.source.len() {
+            return;
+        }
+
+        // Escaped literals would need re-escaping, and are never file names
+        let text = &self.source[lo..hi - 1];
+        if text.contains('\\') {
+            return;
+        }
+        if let Some(reference) = Reference::from_url(text, lo) {
+            self.references.push(reference);
+        }
+    }
+
+    /// Record a string or plain template literal.
+    fn push_literal(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Lit(Lit::Str(s)) => self.push(s.span),
+            // Only templates without substitutions are plain strings
+            Expr::Tpl(tpl) if tpl.exprs.is_empty() => self.push(tpl.span),
+            Expr::Paren(paren) => self.push_literal(&paren.expr),
+            _ => {}
+        }
+    }
+
+    /// Record a literal or the literals of an array literal.
+    fn push_literals(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Array(array) => {
+                for element in array.elems.iter().flatten() {
+                    if element.spread.is_none() {
+                        self.push_literal(&element.expr);
+                    }
+                }
+            }
+            expr => self.push_literal(expr),
+        }
+    }
+
+    /// Record the first argument of a call, if it is a literal.
+    fn push_first_arg(&mut self, args: &[ExprOrSpread]) {
+        if let Some(arg) = args.first()
+            && arg.spread.is_none()
+        {
+            self.push_literal(&arg.expr);
+        }
+    }
+}
+
+impl Visit for LiteralCollector<'_> {
+    fn visit_str(&mut self, node: &Str) {
+        if self.all_strings {
+            self.push(node.span);
+        }
+    }
+
+    fn visit_import_decl(&mut self, node: &ImportDecl) {
+        self.push(node.src.span);
+    }
+
+    fn visit_export_all(&mut self, node: &ExportAll) {
+        self.push(node.src.span);
+    }
+
+    fn visit_named_export(&mut self, node: &NamedExport) {
+        if let Some(src) = &node.src {
+            self.push(src.span);
+        }
+    }
+
+    fn visit_call_expr(&mut self, node: &CallExpr) {
+        let is_url_call = match &node.callee {
+            Callee::Import(_) => true,
+            Callee::Expr(callee) => callee_name(callee).is_some_and(|n| URL_CALLS.contains(&n)),
+            Callee::Super(_) => false,
+        };
+        if is_url_call {
+            self.push_first_arg(&node.args);
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_new_expr(&mut self, node: &NewExpr) {
+        if callee_name(&node.callee).is_some_and(|n| URL_CONSTRUCTORS.contains(&n))
+            && let Some(args) = &node.args
+        {
+            self.push_first_arg(args);
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_key_value_prop(&mut self, node: &KeyValueProp) {
+        // JSON data already collects every string
+        if !self.all_strings && prop_name(&node.key).is_some_and(|n| URL_PROPERTIES.contains(&n)) {
+            self.push_literals(&node.value);
+        }
+        node.visit_children_with(self);
+    }
+
+    fn visit_assign_expr(&mut self, node: &AssignExpr) {
+        let name = match &node.left {
+            AssignTarget::Simple(SimpleAssignTarget::Member(member)) => match &member.prop {
+                MemberProp::Ident(ident) => Some(&*ident.sym),
+                _ => None,
+            },
+            AssignTarget::Simple(SimpleAssignTarget::Ident(ident)) => Some(&*ident.id.sym),
+            _ => None,
+        };
+        if name.is_some_and(|n| URL_PROPERTIES.contains(&n)) {
+            self.push_literals(&node.right);
+        }
+        node.visit_children_with(self);
+    }
+}
+
+/// Parse `source` with SWC, returning the node and the position of the first
+/// byte (spans are offsets from it).
+fn parse<T>(
+    source: &str,
+    parse: impl FnOnce(&mut Parser<Lexer>) -> PResult<T>,
+) -> Option<(T, BytePos)> {
+    GLOBALS.set(&Default::default(), || {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(FileName::Anon.into(), source.to_string());
+
+        let lexer = Lexer::new(
+            Syntax::Es(Default::default()),
+            Default::default(),
+            StringInput::from(&*fm),
+            None,
+        );
+        let mut parser = Parser::new_from(lexer);
+        match parse(&mut parser) {
+            Ok(node) => Some((node, fm.start_pos)),
+            Err(e) => {
+                debug!("JavaScript parse error: {:?}", e);
+                None
+            }
+        }
+    })
+}
+
+/// Parse a JavaScript script or module.
+pub(crate) fn parse_program(source: &str) -> Option<(Program, BytePos)> {
+    parse(source, |parser| parser.parse_program())
+}
+
+/// Parse `source` and collect references.
+fn collect<T>(source: &str, all_strings: bool, node: Option<(T, BytePos)>) -> Vec<Reference>
+where
+    T: for<'a> VisitWith<LiteralCollector<'a>>,
+{
+    let Some((node, start)) = node else {
+        debug!("Skipping references in unparsable source");
+        return Vec::new();
+    };
+
+    let mut collector = LiteralCollector {
+        source,
+        start,
+        all_strings,
+        references: Vec::new(),
+    };
+    node.visit_with(&mut collector);
+    collector.references
+}
+
+/// Find references in JavaScript, at the sites that load a URL.
+///
+/// These are `import`/`export` specifiers, `import()`, `fetch(..)`,
+/// `importScripts(..)`, `new URL(..)` and `new Worker(..)`, and the paths in
+/// Flutter's build config and dart2js deferred part tables. Other string
+/// literals are left alone, even when they happen to name a file.
+pub fn js_references(source: &str) -> Vec<Reference> {
+    collect(source, false, parse_program(source))
+}
+
+/// Find references in JSON: string keys and values.
+pub fn json_references(source: &str) -> Vec<Reference> {
+    collect(source, true, parse(source, |parser| parser.parse_expr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(references: Vec<Reference>) -> Vec<String> {
+        references.into_iter().map(|r| r.path).collect()
+    }
+
+    #[test]
+    fn test_js_reference_sites() {
+        let source = r#"
+import a from "./a.js";
+export * from './b.js';
+export { c } from "./c.js";
+import("./lazy.js");
+fetch(`data/config.json`);
+importScripts("worker-lib.js");
+navigator.serviceWorker.register("sw.js");
+new URL("engine.wasm", import.meta.url);
+new Worker("worker.js");
+_flutter.buildConfig = {"builds":[{"mainJsPath":"main.dart.js"}]};
+init.deferredPartUris = ["main.dart.js_1.part.js", "main.dart.js_2.part.js"];
+"#;
+        assert_eq!(
+            paths(js_references(source)),
+            [
+                "./a.js",
+                "./b.js",
+                "./c.js",
+                "./lazy.js",
+                "data/config.json",
+                "worker-lib.js",
+                "sw.js",
+                "engine.wasm",
+                "worker.js",
+                "main.dart.js",
+                "main.dart.js_1.part.js",
+                "main.dart.js_2.part.js",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_js_spans_point_at_the_path() {
+        let source = r#"fetch("assets/a.png?v=1#x")"#;
+        let references = js_references(source);
+        assert_eq!(references.len(), 1);
+        assert_eq!(&source[references[0].span.clone()], "assets/a.png");
+    }
+
+    #[test]
+    fn test_js_untouched_literals() {
+        let source = r#"
+// fetch("commented.js")
+const name = "main.dart.js";
+console.log("assets/logo.png", 'style.css');
+const label = { title: "index.html" };
+fetch(`assets/${name}`);
+fetch("assets\/escaped.png");
+fetch("https://example.com/remote.js");
+fetch("data:text/plain,hello");
+fetch(base + "relative.js");
+"#;
+        assert!(js_references(source).is_empty());
+    }
+
+    #[test]
+    fn test_js_parse_error_yields_nothing() {
+        assert!(js_references(r#"fetch("a.js"); }}} var"#).is_empty());
+    }
+
+    #[test]
+    fn test_json_references() {
+        // Keys and values are candidates; only those naming a file are rewritten
+        let source =
+            r#"{"assets/logo.png": ["assets/logo.png"], "n": 1, "url": "https://x.dev/a"}"#;
+        assert_eq!(
+            paths(json_references(source)),
+            ["assets/logo.png", "assets/logo.png", "n", "url"]
+        );
+    }
+}
@@ -0,0 +1,645 @@
+//! Reference extraction and rewriting.
+//!
+//! References between output files are found by parsing: SWC for
+//! JavaScript (strings at URL-loading sites) and JSON (all strings),
+//! lightningcss for `url()` and `@import`, and lol_html for HTML attributes
+//! (with inline scripts and styles handed to the other two). Only strings
+//! that resolve to a file in the build count, so unrelated literals are never
+//! touched, and rewrites replace exactly the spans that were found. Chunks
+//! are read together as the file they were split from.
+
+mod css;
+mod html;
+mod js;
+
+pub use css::css_references;
+pub use html::html_references;
+pub use js::{js_references, json_references};
+
+pub(crate) use js::parse_program;
+
+use crate::Result;
+use crate::manifest::{ManifestPlugin, to_url_path};
+use chrysalis_core::{BuildContext, FileInfo};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// A path written in a file, found by parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// Byte range of the path in the content (without query or fragment).
+    pub span: Range<usize>,
+
+    /// The path as written.
+    pub path: String,
+}
+
+impl Reference {
+    /// Reference to the path part of a URL written at `offset`, unless the
+    /// URL cannot point to a local file.
+    pub fn from_url(url: &str, offset: usize) -> Option<Self> {
+        let path = url.split(['?', '#']).next()?;
+        if path.is_empty()
+            || path.len() > 1024
+            || path.starts_with("//")
+            || path.contains(|c: char| c.is_whitespace() || "\"'<>\\{}|^`".contains(c))
+        {
+            return None;
+        }
+
+        // Any scheme (`https:`, `data:`, `blob:`...) is not a local file
+        if let Some(colon) = path.find(':')
+            && !path[..colon].contains('/')
+        {
+            return None;
+        }
+
+        Some(Self {
+            span: offset..offset + path.len(),
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Find the references in a file's content, by file type.
+pub fn find_references(file: &FileInfo, content: &str) -> Vec<Reference> {
+    if file.is_js() {
+        js_references(content)
+    } else if file.is_css() {
+        css_references(content)
+    } else if file.is_html() {
+        html_references(content)
+    } else if file.is_json() {
+        json_references(content)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Check if a file can contain references.
+pub fn has_references(file: &FileInfo) -> bool {
+    file.is_js() || file.is_css() || file.is_html() || file.is_json()
+}
+
+/// How a reference locates its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Base {
+    /// `/path`, from the build root.
+    Absolute,
+    /// Relative to the referencing file.
+    File,
+    /// Relative to the build root (document-relative names in scripts).
+    Root,
+}
+
+/// Resolves references to files in the build, following renames.
+pub struct Resolver {
+    /// Current relative paths.
+    files: HashSet<PathBuf>,
+
+    /// Earlier relative paths of renamed files -> current relative paths.
+    renamed: HashMap<PathBuf, PathBuf>,
+}
+
+impl Resolver {
+    /// Snapshot the files and renames of a build.
+    pub fn new(ctx: &BuildContext) -> Self {
+        let files: HashSet<PathBuf> = ctx.files().map(|f| f.relative.clone()).collect();
+
+        // Follow rename chains to the current name (bounded in case of cycles)
+        let mapping = ctx.file_mapping();
+        let renamed = mapping
+            .keys()
+            .filter_map(|old| {
+                let mut current = old;
+                for _ in 0..mapping.len() {
+                    match mapping.get(current) {
+                        Some(new) => current = new,
+                        None => break,
+                    }
+                }
+                files
+                    .contains(current)
+                    .then(|| (old.clone(), current.clone()))
+            })
+            .collect();
+
+        Self { files, renamed }
+    }
+
+    /// Join a URL path onto a directory, resolving `.` and `..`.
+    fn join(dir: &Path, path: &str) -> Option<PathBuf> {
+        let mut joined = dir.to_path_buf();
+        for segment in path.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    if !joined.pop() {
+                        return None;
+                    }
+                }
+                segment => joined.push(segment),
+            }
+        }
+        Some(joined)
+    }
+
+    /// Resolve a path written in a file in `dir`.
+    ///
+    /// Returns the current relative path of the target, whether it was
+    /// renamed, and how the reference was written.
+    fn resolve(&self, dir: &Path, path: &str) -> Option<(PathBuf, bool, Base)> {
+        let candidates: &[(&Path, Base)] = if path.starts_with('/') {
+            &[(Path::new(""), Base::Absolute)]
+        } else {
+            &[(dir, Base::File), (Path::new(""), Base::Root)]
+        };
+
+        for (base_dir, base) in candidates {
+            let Some(target) = Self::join(base_dir, path) else {
+                continue;
+            };
+            if self.files.contains(&target) {
+                return Some((target, false, *base));
+            }
+            if let Some(current) = self.renamed.get(&target) {
+                return Some((current.clone(), true, *base));
+            }
+        }
+
+        None
+    }
+
+    /// Rewrite the references in `content` of `file`.
+    ///
+    /// Returns the new content if any reference pointed to a renamed file,
+    /// and the current relative paths of all referenced files.
+    pub fn rewrite(&self, file: &FileInfo, content: &str) -> (Option<String>, BTreeSet<PathBuf>) {
+        self.rewrite_from(file, content, &file.dir)
+    }
+
+    /// Rewrite the references in `content` of `file` as seen from `dir`,
+    /// the directory the file is about to move to.
+    ///
+    /// References relative to the file are rewritten for the new directory
+    /// even when their target was not renamed.
+    pub fn rewrite_from(
+        &self,
+        file: &FileInfo,
+        content: &str,
+        dir: &Path,
+    ) -> (Option<String>, BTreeSet<PathBuf>) {
+        let (edits, dependencies) = self.edits_from(file, content, dir);
+        if edits.is_empty() {
+            return (None, dependencies);
+        }
+
+        let mut result = String::with_capacity(content.len());
+        let mut last = 0;
+        for (span, written) in edits {
+            result.push_str(&content[last..span.start]);
+            result.push_str(&written);
+            last = span.end;
+        }
+        result.push_str(&content[last..]);
+        (Some(result), dependencies)
+    }
+
+    /// The replacements [`Resolver::rewrite_from`] makes, in order and
+    /// without overlaps, and the current relative paths of all referenced
+    /// files.
+    fn edits_from(
+        &self,
+        file: &FileInfo,
+        content: &str,
+        dir: &Path,
+    ) -> (Vec<(Range<usize>, String)>, BTreeSet<PathBuf>) {
+        let moved = dir != file.dir;
+        let mut references = find_references(file, content);
+        references.sort_by_key(|r| r.span.start);
+
+        let mut dependencies = BTreeSet::new();
+        let mut edits = Vec::new();
+        let mut last = 0;
+
+        for reference in references {
+            let Some((target, renamed, base)) = self.resolve(&file.dir, &reference.path) else {
+                continue;
+            };
+            if target != file.relative {
+                dependencies.insert(target.clone());
+            }
+            // A file naming itself (e.g. a chunk stub) keeps the name it was written with
+            let relocated = moved && base == Base::File && target != file.relative;
+            if !(renamed || relocated) || reference.span.start < last {
+                continue;
+            }
+
+            let written = match base {
+                Base::Absolute => format!("/{}", to_url_path(&target)),
+                Base::Root => to_url_path(&target),
+                Base::File => {
+                    let relative =
+                        pathdiff::diff_paths(&target, dir).unwrap_or_else(|| target.clone());
+                    let prefix = if reference.path.starts_with("./") {
+                        "./"
+                    } else {
+                        ""
+                    };
+                    format!("{}{}", prefix, to_url_path(&relative))
+                }
+            };
+
+            last = reference.span.end;
+            edits.push((reference.span, written));
+        }
+
+        (edits, dependencies)
+    }
+
+    /// Rewrite the references of a file on disk.
+    ///
+    /// Returns whether the file changed, and the current relative paths of
+    /// the files it references.
+    pub fn update_file(&self, file: &mut FileInfo) -> Result<(bool, BTreeSet<PathBuf>)> {
+        let dir = file.dir.clone();
+        self.update_file_from(file, &dir)
+    }
+
+    /// Rewrite the references of a file on disk as seen from `dir` (see
+    /// [`Resolver::rewrite_from`]).
+    pub fn update_file_from(
+        &self,
+        file: &mut FileInfo,
+        dir: &Path,
+    ) -> Result<(bool, BTreeSet<PathBuf>)> {
+        if let Err(e) = file.load_content() {
+            warn!("Failed to load {}: {}", file.name, e);
+            return Ok((false, BTreeSet::new()));
+        }
+        let Some(content) = file.content_as_str() else {
+            return Ok((false, BTreeSet::new()));
+        };
+
+        let (new_content, dependencies) = self.rewrite_from(file, content, dir);
+        let Some(new_content) = new_content else {
+            return Ok((false, dependencies));
+        };
+
+        let new_bytes = new_content.into_bytes();
+        chrysalis_core::write_file_content(&file.absolute, &new_bytes)?;
+        file.set_content(new_bytes);
+        Ok((true, dependencies))
+    }
+
+    /// Rewrite the references in the chunks of a chunked file, read as one
+    /// document written at `parent` (see [`ChunkGroup`]), as seen from `dir`.
+    ///
+    /// Chunks are fragments no parser accepts on their own. Chunk boundaries
+    /// move with the length changes before them; a reference across a
+    /// boundary ends up in the chunk where it starts.
+    pub fn update_chunks_from(
+        &self,
+        parent: &FileInfo,
+        chunks: &mut [&mut FileInfo],
+        dir: &Path,
+    ) -> Result<(bool, BTreeSet<PathBuf>)> {
+        let mut merged = Vec::new();
+        let mut boundaries = Vec::with_capacity(chunks.len());
+        for chunk in chunks.iter_mut() {
+            if let Err(e) = chunk.load_content() {
+                warn!("Failed to load {}: {}", chunk.name, e);
+                return Ok((false, BTreeSet::new()));
+            }
+            merged.extend_from_slice(chunk.content.as_deref().unwrap_or_default());
+            boundaries.push(merged.len());
+        }
+        let Ok(content) = std::str::from_utf8(&merged) else {
+            return Ok((false, BTreeSet::new()));
+        };
+
+        let (edits, dependencies) = self.edits_from(parent, content, dir);
+        if edits.is_empty() {
+            return Ok((false, dependencies));
+        }
+
+        let mut result = Vec::with_capacity(merged.len());
+        let mut new_boundaries = Vec::with_capacity(boundaries.len());
+        let mut boundary = boundaries.iter().peekable();
+        let mut last = 0;
+        for (span, written) in &edits {
+            // Boundaries before or inside this edit
+            while let Some(&&b) = boundary.peek()
+                && b <= span.end
+            {
+                new_boundaries.push(if b <= span.start {
+                    result.len() + b - last
+                } else {
+                    result.len() + span.start - last + written.len()
+                });
+                boundary.next();
+            }
+            result.extend_from_slice(&merged[last..span.start]);
+            result.extend_from_slice(written.as_bytes());
+            last = span.end;
+        }
+        for &b in boundary {
+            new_boundaries.push(result.len() + b - last);
+        }
+        result.extend_from_slice(&merged[last..]);
+
+        let mut start = 0;
+        for (chunk, end) in chunks.iter_mut().zip(new_boundaries) {
+            let bytes = &result[start..end];
+            start = end;
+            if chunk.content.as_deref() != Some(bytes) {
+                chrysalis_core::write_file_content(&chunk.absolute, bytes)?;
+                chunk.set_content(bytes.to_vec());
+            }
+        }
+        Ok((true, dependencies))
+    }
+}
+
+/// The chunks of a chunked file, whose references are found in their merged
+/// content.
+pub struct ChunkGroup {
+    /// The chunked file at its original path, where its content was written.
+    pub parent: FileInfo,
+
+    /// Absolute paths of the chunks, in order.
+    pub chunks: Vec<PathBuf>,
+}
+
+/// The chunk groups of a build whose files can contain references, sorted by
+/// parent path.
+pub fn chunk_groups(ctx: &BuildContext) -> Vec<ChunkGroup> {
+    let originals = ManifestPlugin::original_paths(ctx);
+    let build_dir = ctx.build_dir();
+
+    let mut groups: Vec<ChunkGroup> = ctx
+        .chunks()
+        .iter()
+        .filter_map(|(parent, chunks)| {
+            let relative = pathdiff::diff_paths(parent, build_dir)?;
+            let original = originals.get(&relative).unwrap_or(&relative);
+            let parent = FileInfo::new(build_dir.join(original), original.clone(), 0);
+            has_references(&parent).then(|| ChunkGroup {
+                parent,
+                chunks: chunks.clone(),
+            })
+        })
+        .collect();
+    groups.sort_by(|a, b| a.parent.relative.cmp(&b.parent.relative));
+    groups
+}
+
+/// Mutable references to the chunks of a group, in order.
+pub fn group_chunks<'a>(
+    files: impl Iterator<Item = &'a mut FileInfo>,
+    group: &ChunkGroup,
+) -> Vec<&'a mut FileInfo> {
+    let mut chunks: Vec<&mut FileInfo> = files
+        .filter(|f| group.chunks.contains(&f.absolute))
+        .collect();
+    chunks.sort_by_key(|f| group.chunks.iter().position(|c| *c == f.absolute));
+    chunks
+}
+
+/// Rewrite references to renamed files in all text files and record the
+/// reference graph in the build context.
+///
+/// Without renames, this only records the graph. Returns the number of files
+/// that were rewritten.
+pub fn update_references(ctx: &mut BuildContext) -> Result<usize> {
+    let resolver = Resolver::new(ctx);
+    let build_dir = ctx.build_dir().to_path_buf();
+    let workers = ctx.workers()?.clone();
+
+    // Chunks are read together, as the file they were split from
+    let groups = chunk_groups(ctx);
+    let mut updated_count = 0;
+    for group in &groups {
+        let mut chunks = group_chunks(ctx.files_mut(), group);
+        let (updated, dependencies) =
+            resolver.update_chunks_from(&group.parent, &mut chunks, &group.parent.dir)?;
+        for chunk in &group.chunks {
+            for dependency in &dependencies {
+                ctx.add_dependency(chunk, build_dir.join(dependency));
+            }
+        }
+        if updated {
+            updated_count += 1;
+        }
+    }
+    let chunk_paths: HashSet<PathBuf> = ctx.chunks().values().flatten().cloned().collect();
+
+    let mut files: Vec<&mut FileInfo> = ctx
+        .files_mut()
+        .filter(|f| has_references(f) && !chunk_paths.contains(&f.absolute))
+        .collect();
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+    let results = workers.map(files, |file| -> Result<_> {
+        let (updated, dependencies) = resolver.update_file(file)?;
+        Ok((file.absolute.clone(), dependencies, updated))
+    });
+
+    for result in results {
+        let (file, dependencies, updated) = result?;
+        for dependency in dependencies {
+            ctx.add_dependency(&file, build_dir.join(dependency));
+        }
+        if updated {
+            updated_count += 1;
+        }
+    }
+
+    Ok(updated_count)
+}
+
+/// The reference graph of a build, for export.
+#[derive(Debug, Default, Serialize)]
+pub struct ReferenceGraph {
+    /// All files (relative paths), sorted.
+    pub files: Vec<String>,
+
+    /// Referenced files per file, sorted.
+    pub dependencies: BTreeMap<String, Vec<String>>,
+}
+
+impl ReferenceGraph {
+    /// Export the dependencies recorded in a build context.
+    pub fn from_context(ctx: &BuildContext) -> Self {
+        let build_dir = ctx.build_dir();
+        let relative = |path: &Path| {
+            to_url_path(
+                pathdiff::diff_paths(path, build_dir)
+                    .as_deref()
+                    .unwrap_or(path),
+            )
+        };
+
+        let mut files: Vec<String> = ctx.files().map(|f| to_url_path(&f.relative)).collect();
+        files.sort();
+
+        let dependencies = ctx
+            .dependencies()
+            .iter()
+            .filter(|(_, deps)| !deps.is_empty())
+            .map(|(file, deps)| {
+                let mut deps: Vec<String> = deps.iter().map(|d| relative(d)).collect();
+                deps.sort();
+                (relative(file), deps)
+            })
+            .collect();
+
+        Self {
+            files,
+            dependencies,
+        }
+    }
+
+    /// Render the graph in Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let mut out = String::from("digraph references {\n  rankdir=LR;\n  node [shape=box];\n");
+        for file in &self.files {
+            out.push_str(&format!("  {};\n", quote(file)));
+        }
+        for (file, deps) in &self.dependencies {
+            for dep in deps {
+                out.push_str(&format!("  {} -> {};\n", quote(file), quote(dep)));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A build directory with `files`, scanned into a context.
+    fn context(files: &[(&str, &str)]) -> (TempDir, BuildContext) {
+        let temp = TempDir::new().unwrap();
+        for (path, content) in files {
+            let path = temp.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let mut ctx = BuildContext::new(temp.path(), vec![]).unwrap();
+        ctx.scan().unwrap();
+        (temp, ctx)
+    }
+
+    fn rename(ctx: &mut BuildContext, from: &str, to: &str) {
+        let build_dir = ctx.build_dir().to_path_buf();
+        ctx.rename_file(build_dir.join(from), build_dir.join(to))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_rewrite_renamed_references() {
+        let (_temp, mut ctx) = context(&[
+            ("index.html", ""),
+            ("js/app.js", ""),
+            ("css/style.css", ""),
+            ("img/logo.png", ""),
+        ]);
+        rename(&mut ctx, "js/app.js", "js/app.1234.js");
+        rename(&mut ctx, "img/logo.png", "img/logo.abcd.png");
+
+        let resolver = Resolver::new(&ctx);
+        let html = ctx.get_file(ctx.build_dir().join("index.html")).unwrap();
+        let (content, dependencies) = resolver.rewrite(
+            html,
+            r#"<script src="js/app.js"></script><link href="/css/style.css"><p>js/app.js</p>"#,
+        );
+        assert_eq!(
+            content.unwrap(),
+            r#"<script src="js/app.1234.js"></script><link href="/css/style.css"><p>js/app.js</p>"#
+        );
+        assert_eq!(
+            dependencies,
+            BTreeSet::from([
+                PathBuf::from("js/app.1234.js"),
+                PathBuf::from("css/style.css")
+            ])
+        );
+
+        // Relative to the referencing file, and untouched when nothing moved
+        let css = ctx.get_file(ctx.build_dir().join("css/style.css")).unwrap();
+        let (content, _) = resolver.rewrite(css, "a { background: url(../img/logo.png) }");
+        assert_eq!(
+            content.unwrap(),
+            "a { background: url(../img/logo.abcd.png) }"
+        );
+        let (content, _) = resolver.rewrite(css, "a { background: url(missing.png) }");
+        assert!(content.is_none());
+    }
+
+    #[test]
+    fn test_chunk_references_across_boundaries() {
+        let source = r#"fetch("assets/data.json");fetch("assets/other.json");"#;
+        // The first chunk ends inside the first path
+        let split = source.find("data").unwrap() + 2;
+        let (_temp, mut ctx) = context(&[
+            ("main.dart.js", "stub"),
+            ("main.dart.chunk0.js", &source[..split]),
+            ("main.dart.chunk1.js", &source[split..]),
+            ("assets/data.json", "{}"),
+            ("assets/other.json", "[]"),
+        ]);
+        let build_dir = ctx.build_dir().to_path_buf();
+        let chunks = vec![
+            build_dir.join("main.dart.chunk0.js"),
+            build_dir.join("main.dart.chunk1.js"),
+        ];
+        ctx.add_chunk_info(build_dir.join("main.dart.js"), chunks.clone());
+        rename(&mut ctx, "assets/data.json", "assets/data.1111.json");
+        rename(&mut ctx, "assets/other.json", "assets/other.2222.json");
+
+        assert_eq!(update_references(&mut ctx).unwrap(), 1);
+        let chunk0 = fs::read_to_string(&chunks[0]).unwrap();
+        let chunk1 = fs::read_to_string(&chunks[1]).unwrap();
+        assert_eq!(chunk0, r#"fetch("assets/data.1111.json"#);
+        assert_eq!(chunk1, r#"");fetch("assets/other.2222.json");"#);
+
+        // Every chunk depends on the files the merged content references
+        for chunk in &chunks {
+            assert_eq!(ctx.get_dependencies(chunk).unwrap().len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_reference_graph_output() {
+        let (_temp, mut ctx) = context(&[
+            ("index.html", r#"<script src="main.js"></script>"#),
+            ("main.js", r#"fetch("data \"1\".json")"#),
+            ("data \"1\".json", "{}"),
+            ("notes.txt", "main.js"),
+        ]);
+        update_references(&mut ctx).unwrap();
+
+        let graph = ReferenceGraph::from_context(&ctx);
+        assert_eq!(
+            serde_json::to_value(&graph).unwrap(),
+            serde_json::json!({
+                "files": ["data \"1\".json", "index.html", "main.js", "notes.txt"],
+                "dependencies": {"index.html": ["main.js"]},
+            })
+        );
+        assert_eq!(
+            graph.to_dot(),
+            "digraph references {\n  rankdir=LR;\n  node [shape=box];\n  \"data \\\"1\\\".json\";\n  \"index.html\";\n  \"main.js\";\n  \"notes.txt\";\n  \"index.html\" -> \"main.js\";\n}\n"
+        );
+    }
+}
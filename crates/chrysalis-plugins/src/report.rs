@@ -0,0 +1,364 @@
+//! Self-contained HTML bundle size report.
+
+use crate::chunk::folded_chunks;
+use crate::manifest::{ManifestPlugin, to_url_path};
+use crate::{PluginError, Result};
+use chrysalis_core::{BuildContext, FileInfo};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tera::{Context, Tera, Value};
+
+/// Report template (no external assets, so the file can be shared as-is).
+const REPORT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Chrysalis bundle report</title>
+<style>
+  body { font: 14px/1.5 system-ui, sans-serif; margin: 2rem; color: #1f2328; }
+  h1 { font-size: 1.5rem; margin-bottom: 0; }
+  h2 { font-size: 1.1rem; margin-top: 2rem; }
+  .muted { color: #656d76; }
+  .cards { display: flex; gap: 1rem; flex-wrap: wrap; margin: 1.5rem 0; }
+  .card { border: 1px solid #d0d7de; border-radius: 6px; padding: .75rem 1rem; min-width: 10rem; }
+  .card b { display: block; font-size: 1.25rem; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { text-align: left; padding: .3rem .6rem; border-bottom: 1px solid #eaeef2; white-space: nowrap; }
+  th { background: #f6f8fa; }
+  td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; }
+  td.name { white-space: normal; word-break: break-all; }
+  tr.chunk td { color: #656d76; font-size: 12px; }
+  tr.chunk td.name { padding-left: 2rem; }
+  .bar { background: #eaeef2; height: .6rem; border-radius: 3px; min-width: 8rem; }
+  .bar span { display: block; height: 100%; background: #6639ba; border-radius: 3px; }
+</style>
+</head>
+<body>
+<h1>Chrysalis bundle report</h1>
+<div class="muted">{{ build_dir }}</div>
+
+<div class="cards">
+  <div class="card">Files<b>{{ total_files }}</b></div>
+  <div class="card">Original<b>{{ original_size | bytes }}</b></div>
+  <div class="card">Final<b>{{ size | bytes }}</b></div>
+  <div class="card">Transfer<b>{{ transfer_size | bytes }}</b></div>
+  <div class="card">Chunks<b>{{ total_chunks }}</b></div>
+</div>
+
+<h2>By type</h2>
+<table>
+  <tr><th>Type</th><th class="num">Files</th><th class="num">Original</th><th class="num">Final</th><th class="num">Transfer</th><th>Share of final size</th></tr>
+  {% for group in groups %}
+  <tr>
+    <td>{{ group.kind }}</td>
+    <td class="num">{{ group.files | length }}</td>
+    <td class="num">{{ group.original_size | bytes }}</td>
+    <td class="num">{{ group.size | bytes }}</td>
+    <td class="num">{{ group.transfer_size | bytes }}</td>
+    <td><div class="bar"><span style="width: {{ group.percent | round(precision=2) }}%"></span></div></td>
+  </tr>
+  {% endfor %}
+</table>
+
+{% for group in groups %}
+<h2>{{ group.kind }}</h2>
+<table>
+  <tr><th>Original</th><th>Final name</th><th class="num">Original</th><th class="num">Minified</th><th class="num">Final</th><th class="num">Compressed</th><th>Share</th></tr>
+  {% for file in group.files %}
+  <tr>
+    <td class="name">{{ file.original }}</td>
+    <td class="name">{{ file.file }}</td>
+    <td class="num">{{ file.original_size | bytes }}</td>
+    <td class="num">{% if file.minified_size %}{{ file.minified_size | bytes }}{% else %}–{% endif %}</td>
+    <td class="num">{{ file.size | bytes }}</td>
+    <td class="num">{% for encoding, size in file.compressed %}{{ encoding }} {{ size | bytes }}{% if not loop.last %}<br>{% endif %}{% else %}–{% endfor %}</td>
+    <td><div class="bar"><span style="width: {{ file.percent | round(precision=2) }}%"></span></div></td>
+  </tr>
+  {% for chunk in file.chunks %}
+  <tr class="chunk">
+    <td class="name" colspan="2">{{ chunk.file }}</td>
+    <td class="num"></td>
+    <td class="num"></td>
+    <td class="num">{{ chunk.size | bytes }}</td>
+    <td class="num">{% for encoding, size in chunk.compressed %}{{ encoding }} {{ size | bytes }}{% if not loop.last %}<br>{% endif %}{% else %}–{% endfor %}</td>
+    <td></td>
+  </tr>
+  {% endfor %}
+  {% endfor %}
+</table>
+{% endfor %}
+
+<h2>Plugin timings</h2>
+<table>
+  <tr><th>Plugin</th><th class="num">Time</th><th>Share</th></tr>
+  {% for timing in timings %}
+  <tr>
+    <td>{{ timing.plugin }}</td>
+    <td class="num">{{ timing.ms | round(precision=1) }} ms</td>
+    <td><div class="bar"><span style="width: {{ timing.percent | round(precision=2) }}%"></span></div></td>
+  </tr>
+  {% endfor %}
+</table>
+</body>
+</html>
+"#;
+
+/// A chunk of a report file.
+#[derive(Debug, Serialize)]
+struct ReportChunk {
+    file: String,
+    size: u64,
+    compressed: BTreeMap<String, u64>,
+}
+
+/// A single output file.
+#[derive(Debug, Serialize)]
+struct ReportFile {
+    original: String,
+    file: String,
+    original_size: u64,
+    minified_size: Option<u64>,
+    /// Final size, including chunks.
+    size: u64,
+    transfer_size: u64,
+    compressed: BTreeMap<String, u64>,
+    chunks: Vec<ReportChunk>,
+    percent: f64,
+}
+
+/// Files of one type.
+#[derive(Debug, Serialize)]
+struct ReportGroup {
+    kind: &'static str,
+    files: Vec<ReportFile>,
+    original_size: u64,
+    size: u64,
+    transfer_size: u64,
+    percent: f64,
+}
+
+/// Time spent in one plugin.
+#[derive(Debug, Serialize)]
+struct ReportTiming {
+    plugin: String,
+    ms: f64,
+    percent: f64,
+}
+
+/// Report group for a file extension.
+pub(crate) fn kind_of(ext: &str) -> &'static str {
+    match ext {
+        ".js" | ".mjs" => "JavaScript",
+        ".wasm" => "WebAssembly",
+        ".css" => "CSS",
+        ".html" => "HTML",
+        ".json" => "JSON",
+        ".png" | ".jpg" | ".jpeg" | ".gif" | ".webp" | ".avif" | ".svg" | ".ico" => "Images",
+        ".ttf" | ".otf" | ".woff" | ".woff2" => "Fonts",
+        _ => "Other",
+    }
+}
+
+/// Share of `part` in `total`, as a percentage.
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+/// Tera filter formatting a byte count.
+fn bytes_filter(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let bytes = value
+        .as_u64()
+        .ok_or_else(|| tera::Error::msg("bytes filter expects an unsigned integer"))?;
+    Ok(Value::String(chrysalis_core::format_bytes(bytes)))
+}
+
+/// Render the bundle size report for a processed build.
+pub fn render_report(ctx: &BuildContext) -> Result<String> {
+    let originals = ManifestPlugin::original_paths(ctx);
+    let folded = folded_chunks(ctx);
+    let mut files: Vec<&FileInfo> = ctx
+        .files()
+        .filter(|f| !folded.contains(f.absolute.as_path()))
+        .collect();
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+    // Totals count every output file once (chunks via their parent, if kept)
+    let report_files: Vec<(&'static str, ReportFile)> = files
+        .iter()
+        .map(|file| {
+            let chunks: Vec<ReportChunk> = ctx
+                .get_chunk_info(&file.absolute)
+                .into_iter()
+                .flatten()
+                .filter_map(|path| ctx.get_file(path))
+                .map(|chunk| ReportChunk {
+                    file: to_url_path(&chunk.relative),
+                    size: chunk.size,
+                    compressed: chunk.compressed_sizes.clone(),
+                })
+                .collect();
+            let chunk_transfer: u64 = ctx
+                .get_chunk_info(&file.absolute)
+                .into_iter()
+                .flatten()
+                .filter_map(|path| ctx.get_file(path))
+                .map(|chunk| chunk.transfer_size())
+                .sum();
+
+            let original = originals
+                .get(&file.relative)
+                .cloned()
+                .unwrap_or_else(|| file.relative.clone());
+
+            let entry = ReportFile {
+                original: to_url_path(&original),
+                file: to_url_path(&file.relative),
+                original_size: file.original_size,
+                minified_size: file.minified_size,
+                size: file.size + chunks.iter().map(|c| c.size).sum::<u64>(),
+                transfer_size: file.transfer_size() + chunk_transfer,
+                compressed: file.compressed_sizes.clone(),
+                chunks,
+                percent: 0.0,
+            };
+            (kind_of(&file.ext), entry)
+        })
+        .collect();
+
+    let total_size: u64 = report_files.iter().map(|(_, f)| f.size).sum();
+    let mut groups: BTreeMap<&'static str, ReportGroup> = BTreeMap::new();
+    for (kind, mut file) in report_files {
+        file.percent = percent(file.size, total_size);
+        let group = groups.entry(kind).or_insert_with(|| ReportGroup {
+            kind,
+            files: Vec::new(),
+            original_size: 0,
+            size: 0,
+            transfer_size: 0,
+            percent: 0.0,
+        });
+        group.original_size += file.original_size;
+        group.size += file.size;
+        group.transfer_size += file.transfer_size;
+        group.files.push(file);
+    }
+
+    // Largest groups and files first
+    let mut groups: Vec<ReportGroup> = groups.into_values().collect();
+    for group in &mut groups {
+        group.percent = percent(group.size, total_size);
+        group.files.sort_by_key(|f| std::cmp::Reverse(f.size));
+    }
+    groups.sort_by_key(|g| std::cmp::Reverse(g.size));
+
+    let stats = ctx.stats();
+    let total_time: u128 = stats
+        .plugin_timings
+        .iter()
+        .map(|(_, t)| t.as_micros())
+        .sum();
+    let timings: Vec<ReportTiming> = stats
+        .plugin_timings
+        .iter()
+        .map(|(plugin, elapsed)| ReportTiming {
+            plugin: plugin.clone(),
+            ms: elapsed.as_secs_f64() * 1000.0,
+            percent: percent(elapsed.as_micros() as u64, total_time as u64),
+        })
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("build_dir", &ctx.build_dir().display().to_string());
+    context.insert("total_files", &files.len());
+    context.insert(
+        "total_chunks",
+        &ctx.chunks().values().map(Vec::len).sum::<usize>(),
+    );
+    context.insert(
+        "original_size",
+        &groups.iter().map(|g| g.original_size).sum::<u64>(),
+    );
+    context.insert("size", &total_size);
+    context.insert(
+        "transfer_size",
+        &groups.iter().map(|g| g.transfer_size).sum::<u64>(),
+    );
+    context.insert("groups", &groups);
+    context.insert("timings", &timings);
+
+    let mut tera = Tera::default();
+    tera.register_filter("bytes", bytes_filter);
+    tera.add_raw_template("report.html", REPORT_TEMPLATE)
+        .and_then(|_| tera.render("report.html", &context))
+        .map_err(|e| PluginError::TemplateError(format!("{:?}", e)))
+}
+
+/// Render the bundle size report and write it to `path`.
+pub fn write_report<P: AsRef<Path>>(ctx: &BuildContext, path: P) -> Result<()> {
+    let html = render_report(ctx)?;
+    chrysalis_core::write_file_content(path, html.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_chunks_of_removed_parents_are_counted() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        for (path, size) in [
+            ("index.html", 50),
+            ("main.dart.js", 100),
+            ("main.dart.chunk0.js", 300),
+            ("main.dart.chunk1.js", 300),
+            ("canvaskit/canvaskit.chunk0.wasm", 1000),
+            ("canvaskit/canvaskit.chunk1.wasm", 1000),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![b'x'; size]).unwrap();
+        }
+        let mut ctx = BuildContext::new(root, vec![]).unwrap();
+        ctx.scan().unwrap();
+
+        // The script keeps a stub; the WebAssembly module was removed
+        ctx.add_chunk_info(
+            root.join("main.dart.js"),
+            vec![
+                root.join("main.dart.chunk0.js"),
+                root.join("main.dart.chunk1.js"),
+            ],
+        );
+        ctx.add_chunk_info(
+            root.join("canvaskit/canvaskit.wasm"),
+            vec![
+                root.join("canvaskit/canvaskit.chunk0.wasm"),
+                root.join("canvaskit/canvaskit.chunk1.wasm"),
+            ],
+        );
+
+        let html = render_report(&ctx).unwrap();
+        assert!(html.contains("Files<b>4</b>"), "{}", html);
+        assert!(html.contains("Chunks<b>4</b>"));
+        // 50 + 100 + 2 * 300 + 2 * 1000 bytes
+        assert!(html.contains(&format!(
+            "Final<b>{}</b>",
+            chrysalis_core::format_bytes(2750)
+        )));
+        assert!(html.contains(&format!(
+            "<td>WebAssembly</td>\n    <td class=\"num\">2</td>\n    <td class=\"num\">{0}</td>\n    <td class=\"num\">{0}</td>",
+            chrysalis_core::format_bytes(2000)
+        )));
+        assert!(html.contains("canvaskit.chunk1.wasm"));
+    }
+}
@@ -0,0 +1,534 @@
+//! Service worker plugin for Flutter's `flutter_service_worker.js`.
+//!
+//! Flutter writes a `RESOURCES` map (path -> MD5) and a `CORE` list into the
+//! worker. After minification, hashing and chunking those entries point to
+//! stale names and hashes, so this plugin rewrites them from the final output.
+
+use crate::manifest::to_url_path;
+use crate::references::parse_program;
+use crate::{Plugin, PluginError, Result};
+use chrysalis_config::ServiceWorkerConfig;
+use chrysalis_core::BuildContext;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::PathBuf;
+use swc_core::common::{BytePos, Spanned};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::{Visit, VisitWith};
+use tracing::{debug, info, warn};
+
+/// Flutter service worker file name.
+const SERVICE_WORKER_FILE: &str = "flutter_service_worker.js";
+
+/// Service worker plugin rewrites the worker's resource list.
+pub struct ServiceWorkerPlugin {
+    config: ServiceWorkerConfig,
+}
+
+/// Finds the value a variable is declared with.
+struct InitFinder<'a> {
+    name: &'a str,
+    init: Option<Expr>,
+}
+
+impl Visit for InitFinder<'_> {
+    fn visit_var_declarator(&mut self, node: &VarDeclarator) {
+        if self.init.is_none()
+            && let Pat::Ident(ident) = &node.name
+            && &*ident.id.sym == self.name
+            && let Some(init) = &node.init
+        {
+            self.init = Some((**init).clone());
+        }
+        node.visit_children_with(self);
+    }
+}
+
+/// Final location of a resource listed by the worker.
+struct Resolved {
+    /// Final relative path, or None if the file no longer exists.
+    path: Option<String>,
+    /// Chunk files (final relative paths) that replaced or back the resource.
+    chunks: Vec<String>,
+}
+
+impl ServiceWorkerPlugin {
+    /// Create a new service worker plugin.
+    pub fn new(config: ServiceWorkerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Find the value assigned to the variable `name` where it is declared.
+    fn find_init(program: &Program, name: &str) -> Option<Expr> {
+        let mut finder = InitFinder { name, init: None };
+        program.visit_with(&mut finder);
+        finder.init
+    }
+
+    /// Byte range of a node in the parsed source.
+    fn byte_range(node: &impl Spanned, start: BytePos) -> Range<usize> {
+        let span = node.span();
+        (span.lo.0 - start.0) as usize..(span.hi.0 - start.0) as usize
+    }
+
+    /// Value of a string or plain template literal.
+    fn string_value(expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Lit(Lit::Str(s)) => s.value.as_str().map(str::to_string),
+            Expr::Tpl(tpl) if tpl.exprs.is_empty() => tpl
+                .quasis
+                .first()?
+                .cooked
+                .as_ref()?
+                .as_str()
+                .map(str::to_string),
+            Expr::Paren(paren) => Self::string_value(&paren.expr),
+            _ => None,
+        }
+    }
+
+    /// Keys and values of an object literal of strings, in order.
+    ///
+    /// Returns None if the object holds anything else (e.g. computed values),
+    /// since it cannot be rewritten safely.
+    fn object_strings(expr: &Expr) -> Option<Vec<String>> {
+        let Expr::Object(object) = expr else {
+            return None;
+        };
+        let mut strings = Vec::new();
+        for prop in &object.props {
+            let PropOrSpread::Prop(prop) = prop else {
+                return None;
+            };
+            let Prop::KeyValue(prop) = &**prop else {
+                return None;
+            };
+            let key = match &prop.key {
+                PropName::Str(s) => s.value.as_str()?.to_string(),
+                PropName::Ident(ident) => ident.sym.to_string(),
+                _ => return None,
+            };
+            strings.push(key);
+            strings.push(Self::string_value(&prop.value)?);
+        }
+        Some(strings)
+    }
+
+    /// Elements of an array literal of strings, in order.
+    fn array_strings(expr: &Expr) -> Option<Vec<String>> {
+        let Expr::Array(array) = expr else {
+            return None;
+        };
+        array
+            .elems
+            .iter()
+            .map(|element| match element {
+                Some(element) if element.spread.is_none() => Self::string_value(&element.expr),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Quote a string as a JavaScript (JSON) string literal.
+    fn quote(value: &str) -> String {
+        serde_json::to_string(value).unwrap_or_else(|_| format!("\"{}\"", value))
+    }
+
+    /// Map original relative paths to final relative paths.
+    fn final_paths(ctx: &BuildContext) -> HashMap<String, String> {
+        let mapping = ctx.file_mapping();
+        mapping
+            .keys()
+            .map(|old| {
+                // Follow rename chains forward (bounded in case of cycles)
+                let mut new = old;
+                for _ in 0..mapping.len() {
+                    match mapping.get(new) {
+                        Some(next) => new = next,
+                        None => break,
+                    }
+                }
+                (to_url_path(old), to_url_path(new))
+            })
+            .collect()
+    }
+
+    /// Resolve a resource key to its final path and chunks.
+    fn resolve(
+        key: &str,
+        ctx: &BuildContext,
+        final_paths: &HashMap<String, String>,
+        chunk_lists: &HashMap<String, Vec<String>>,
+    ) -> Resolved {
+        let path = final_paths
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_string());
+
+        // Chunks are recorded against the parent's path at chunk time, which
+        // may be either the original or the final name.
+        let chunks = chunk_lists
+            .get(&path)
+            .or_else(|| chunk_lists.get(key))
+            .cloned()
+            .unwrap_or_default();
+
+        let exists = ctx.build_dir().join(&path).is_file();
+        Resolved {
+            path: exists.then_some(path),
+            chunks,
+        }
+    }
+
+    /// Files written by plugins (e.g. the chunk loader), which Flutter never
+    /// listed, as sorted relative paths.
+    fn generated_files(ctx: &BuildContext) -> Vec<String> {
+        let mut generated: Vec<String> = ctx
+            .files()
+            .filter(|f| ctx.is_generated(&f.absolute))
+            .map(|f| to_url_path(&f.relative))
+            .collect();
+        generated.sort();
+        generated
+    }
+
+    /// Compute the MD5 hash Flutter uses for a resource.
+    fn resource_hash(ctx: &BuildContext, relative: &str) -> Option<String> {
+        let absolute = ctx.build_dir().join(relative);
+        let content = match ctx.get_file(&absolute).and_then(|f| f.content.as_ref()) {
+            Some(content) => content.clone(),
+            None => std::fs::read(&absolute).ok()?,
+        };
+        Some(chrysalis_core::calculate_hash(&content, 32))
+    }
+
+    /// Rewrite the `RESOURCES` map.
+    fn rewrite_resources(
+        strings: &[String],
+        ctx: &BuildContext,
+        final_paths: &HashMap<String, String>,
+        chunk_lists: &HashMap<String, Vec<String>>,
+    ) -> String {
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+
+        for pair in strings.chunks(2) {
+            let key = &pair[0];
+
+            // "/" is the app shell, served as index.html
+            let resolved = if key == "/" {
+                Self::resolve("index.html", ctx, final_paths, chunk_lists)
+            } else {
+                Self::resolve(key, ctx, final_paths, chunk_lists)
+            };
+
+            match &resolved.path {
+                Some(path) => {
+                    let entry_key = if key == "/" { key } else { path };
+                    if seen.insert(entry_key.clone())
+                        && let Some(hash) = Self::resource_hash(ctx, path)
+                    {
+                        entries.push((entry_key.clone(), hash));
+                    }
+                }
+                None => debug!("  Dropping missing resource: {}", key),
+            }
+
+            for chunk in &resolved.chunks {
+                if seen.insert(chunk.clone())
+                    && let Some(hash) = Self::resource_hash(ctx, chunk)
+                {
+                    entries.push((chunk.clone(), hash));
+                }
+            }
+        }
+
+        for path in Self::generated_files(ctx) {
+            if seen.insert(path.clone())
+                && let Some(hash) = Self::resource_hash(ctx, &path)
+            {
+                entries.push((path, hash));
+            }
+        }
+
+        let body = entries
+            .iter()
+            .map(|(k, v)| format!("{}:{}", Self::quote(k), Self::quote(v)))
+            .collect::<Vec<_>>()
+            .join(",\n");
+        format!("{{{}}}", body)
+    }
+
+    /// Rewrite the `CORE` list.
+    fn rewrite_core(
+        strings: &[String],
+        ctx: &BuildContext,
+        final_paths: &HashMap<String, String>,
+        chunk_lists: &HashMap<String, Vec<String>>,
+    ) -> String {
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+
+        for entry in strings {
+            let resolved = Self::resolve(entry, ctx, final_paths, chunk_lists);
+            for path in resolved.path.into_iter().chain(resolved.chunks) {
+                if seen.insert(path.clone()) {
+                    entries.push(Self::quote(&path));
+                }
+            }
+        }
+
+        // Generated files (the loader and its manifest) are needed at startup
+        for path in Self::generated_files(ctx) {
+            if seen.insert(path.clone()) {
+                entries.push(Self::quote(&path));
+            }
+        }
+
+        format!("[{}]", entries.join(",\n"))
+    }
+
+    /// Rewrite the worker source.
+    ///
+    /// Returns None, with a warning, when the worker has no `RESOURCES`
+    /// object literal to rewrite (e.g. a cleanup worker from
+    /// `--pwa-strategy=none`).
+    pub fn rewrite(&self, content: &str, ctx: &BuildContext) -> Result<Option<String>> {
+        let final_paths = Self::final_paths(ctx);
+        let build_dir = ctx.build_dir();
+
+        // Chunk lists keyed by the parent's relative path (at chunk time)
+        let chunk_lists: HashMap<String, Vec<String>> = ctx
+            .chunks()
+            .iter()
+            .filter_map(|(parent, chunks)| {
+                let parent = pathdiff::diff_paths(parent, build_dir)?;
+                let chunks = chunks
+                    .iter()
+                    .filter_map(|c| pathdiff::diff_paths(c, build_dir))
+                    .map(|c| to_url_path(&c))
+                    .collect();
+                Some((to_url_path(&parent), chunks))
+            })
+            .collect();
+
+        let Some((program, start)) = parse_program(content) else {
+            warn!(
+                "Failed to parse {}, leaving it unchanged",
+                SERVICE_WORKER_FILE
+            );
+            return Ok(None);
+        };
+        let resources = match Self::find_init(&program, "RESOURCES") {
+            Some(resources @ Expr::Object(_)) => resources,
+            Some(_) => {
+                warn!(
+                    "RESOURCES in {} is not an object literal, leaving it unchanged",
+                    SERVICE_WORKER_FILE
+                );
+                return Ok(None);
+            }
+            None => {
+                warn!(
+                    "No RESOURCES map in {}, leaving it unchanged",
+                    SERVICE_WORKER_FILE
+                );
+                return Ok(None);
+            }
+        };
+
+        // Found but holding computed entries: rewriting would drop them
+        let strings = Self::object_strings(&resources).ok_or_else(|| {
+            PluginError::ServiceWorkerFailed("RESOURCES map is not a plain string map".to_string())
+        })?;
+        let mut edits = vec![(
+            Self::byte_range(&resources, start),
+            Self::rewrite_resources(&strings, ctx, &final_paths, &chunk_lists),
+        )];
+
+        if let Some(core) = Self::find_init(&program, "CORE") {
+            match Self::array_strings(&core) {
+                Some(strings) => edits.push((
+                    Self::byte_range(&core, start),
+                    Self::rewrite_core(&strings, ctx, &final_paths, &chunk_lists),
+                )),
+                None => warn!("CORE list in service worker is not a plain string list"),
+            }
+        }
+
+        // Splice from the end so earlier ranges stay valid
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        let mut result = content.to_string();
+        for (range, replacement) in edits {
+            result.replace_range(range, &replacement);
+        }
+
+        Ok(Some(result))
+    }
+}
+
+#[async_trait::async_trait]
+impl Plugin for ServiceWorkerPlugin {
+    fn name(&self) -> &str {
+        "service_worker"
+    }
+
+    fn runs_after(&self) -> &[&str] {
+        // RESOURCES must match the final names and bytes
+        &["minify", "chunk", "hash", "inject"]
+    }
+
+    async fn execute(&self, ctx: &mut BuildContext) -> Result<()> {
+        if !self.config.enabled {
+            info!("Service worker rewriting disabled");
+            return Ok(());
+        }
+
+        // The worker itself is a framework file: never renamed, only rewritten
+        let worker_path: PathBuf = ctx.build_dir().join(SERVICE_WORKER_FILE);
+        let content = match ctx.get_file_mut(&worker_path) {
+            Some(file) => {
+                if let Err(e) = file.load_content() {
+                    warn!("Failed to load {}: {}", file.name, e);
+                    return Ok(());
+                }
+                match file.content_as_str() {
+                    Some(s) => s.to_string(),
+                    None => {
+                        warn!("{} is not valid UTF-8", file.name);
+                        return Ok(());
+                    }
+                }
+            }
+            None => {
+                info!("No {} found, skipping", SERVICE_WORKER_FILE);
+                return Ok(());
+            }
+        };
+
+        info!("Updating service worker resources...");
+
+        let Some(updated) = self.rewrite(&content, ctx)? else {
+            return Ok(());
+        };
+        if updated != content {
+            let bytes = updated.into_bytes();
+            chrysalis_core::write_file_content(&worker_path, &bytes)?;
+
+            let file = ctx.get_file_mut(&worker_path).unwrap();
+            file.set_content(bytes);
+        }
+
+        info!("✓ Service worker resources updated");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A build directory holding `main.dart.js` and `index.html`.
+    fn context() -> (TempDir, BuildContext) {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("main.dart.js"), "main()").unwrap();
+        fs::write(temp.path().join("index.html"), "<html></html>").unwrap();
+        let mut ctx = BuildContext::new(temp.path(), vec![]).unwrap();
+        ctx.scan().unwrap();
+        (temp, ctx)
+    }
+
+    fn rewrite(ctx: &BuildContext, content: &str) -> Result<Option<String>> {
+        ServiceWorkerPlugin::new(ServiceWorkerConfig::default()).rewrite(content, ctx)
+    }
+
+    fn md5(content: &str) -> String {
+        chrysalis_core::calculate_hash(content.as_bytes(), 32)
+    }
+
+    #[test]
+    fn test_rewrite_resources_and_core() {
+        let (_temp, ctx) = context();
+        let worker = r#"'use strict';
+// const RESOURCES = {"commented.js": "0"};
+const label = "const RESOURCES = {";
+const RESOURCES = {"main.dart.js": "0", /* dropped */ "gone.js": "1",
+"/": "2"};
+const CORE = [`main.dart.js`, "index.html", "gone.js"];
+"#;
+        let rewritten = rewrite(&ctx, worker).unwrap().unwrap();
+        let resources = format!(
+            "const RESOURCES = {{\"main.dart.js\":\"{}\",\n\"/\":\"{}\"}};",
+            md5("main()"),
+            md5("<html></html>")
+        );
+        assert!(rewritten.contains(&resources), "{}", rewritten);
+        assert!(rewritten.contains("const CORE = [\"main.dart.js\",\n\"index.html\"];"));
+        assert!(rewritten.contains(r#"// const RESOURCES = {"commented.js": "0"};"#));
+        assert!(rewritten.contains(r#"const label = "const RESOURCES = {";"#));
+    }
+
+    #[test]
+    fn test_escaped_keys() {
+        let (_temp, ctx) = context();
+        let worker = r#"const RESOURCES = {"main\x2edart.js": "0", 'index\u{2E}html': "1"};"#;
+        let rewritten = rewrite(&ctx, worker).unwrap().unwrap();
+        assert!(rewritten.contains("\"main.dart.js\":"));
+        assert!(rewritten.contains("\"index.html\":"));
+    }
+
+    #[test]
+    fn test_missing_resources_are_skipped() {
+        let (_temp, ctx) = context();
+        for worker in [
+            "const CORE = [\"main.dart.js\"];",
+            // Only a declaration counts, not a comment or string
+            "// const RESOURCES = {}\nvar s = 'RESOURCES = {}';",
+            // Not a literal
+            "const RESOURCES = loadResources();",
+            // A cleanup worker (`--pwa-strategy=none`)
+            "self.addEventListener('activate', () => self.registration.unregister());",
+            "const RESOURCES = {",
+        ] {
+            assert!(rewrite(&ctx, worker).unwrap().is_none(), "{}", worker);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_leaves_workers_without_resources() {
+        let (temp, mut ctx) = context();
+        let worker = "self.registration.unregister();";
+        let path = temp.path().join(SERVICE_WORKER_FILE);
+        fs::write(&path, worker).unwrap();
+        ctx.scan().unwrap();
+
+        let plugin = ServiceWorkerPlugin::new(ServiceWorkerConfig::default());
+        plugin.execute(&mut ctx).await.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), worker);
+    }
+
+    #[test]
+    fn test_computed_literals_are_rejected() {
+        let (_temp, ctx) = context();
+        for worker in [
+            "const RESOURCES = {\"main.dart.js\": hash};",
+            "const RESOURCES = {[key]: \"0\"};",
+            "const RESOURCES = {\"main.dart.js\": `${hash}`};",
+            "const RESOURCES = {...base};",
+        ] {
+            let error = rewrite(&ctx, worker).unwrap_err();
+            assert!(
+                error.to_string().contains("not a plain string map"),
+                "{}",
+                worker
+            );
+        }
+
+        // A CORE list that cannot be rewritten is left as is
+        let worker = "const RESOURCES = {};\nconst CORE = [base + \"main.dart.js\"];";
+        let rewritten = rewrite(&ctx, worker).unwrap().unwrap();
+        assert!(rewritten.ends_with("const CORE = [base + \"main.dart.js\"];"));
+    }
+}